@@ -24,8 +24,10 @@ public class SurfmanInstrumentedTest {
     private static native void testGenericSurfaceCreation();
     private static native void testGL();
     private static native void testNewlyCreatedContextsAreNotCurrent();
+    private static native void testSurfacePool();
     private static native void testSurfaceTextureBlitFramebuffer();
     private static native void testSurfaceTextureRightSideUp();
+    private static native void testSwapChain();
 
     static {
         System.loadLibrary("surfman_android_threads");
@@ -79,6 +81,11 @@ public class SurfmanInstrumentedTest {
         testNewlyCreatedContextsAreNotCurrent();
     }
 
+    @Test
+    public void surfacePool() {
+        testSurfacePool();
+    }
+
     @Test
     public void surfaceTextureBlitFramebuffer() {
         testSurfaceTextureBlitFramebuffer();
@@ -88,4 +95,9 @@ public class SurfmanInstrumentedTest {
     public void surfaceTextureRightSideUp() {
         testSurfaceTextureRightSideUp();
     }
+
+    @Test
+    public void swapChain() {
+        testSwapChain();
+    }
 }
@@ -135,6 +135,14 @@ pub unsafe extern "system" fn Java_org_mozilla_surfmanthreadsexample_SurfmanInst
     tests::test_newly_created_contexts_are_not_current();
 }
 
+#[no_mangle]
+pub unsafe extern "system" fn Java_org_mozilla_surfmanthreadsexample_SurfmanInstrumentedTest_testSurfacePool(
+    _env: JNIEnv,
+    _class: JClass,
+) {
+    tests::test_surface_pool();
+}
+
 #[no_mangle]
 pub unsafe extern "system" fn Java_org_mozilla_surfmanthreadsexample_SurfmanInstrumentedTest_testSurfaceTextureBlitFramebuffer(
     _env: JNIEnv,
@@ -151,6 +159,14 @@ pub unsafe extern "system" fn Java_org_mozilla_surfmanthreadsexample_SurfmanInst
     tests::test_surface_texture_right_side_up();
 }
 
+#[no_mangle]
+pub unsafe extern "system" fn Java_org_mozilla_surfmanthreadsexample_SurfmanInstrumentedTest_testSwapChain(
+    _env: JNIEnv,
+    _class: JClass,
+) {
+    tests::test_swap_chain();
+}
+
 struct JavaResourceLoader {
     loader: GlobalRef,
     vm: JavaVM,
@@ -6,7 +6,7 @@ use cfg_aliases::cfg_aliases;
 use gl_generator::{Api, Fallbacks, Profile, Registry, StructGenerator};
 use std::env;
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn main() {
     // Setup aliases for #[cfg] checks
@@ -45,12 +45,33 @@ fn main() {
 
     // Generate GL bindings.
     if target_os == "android" {
-        let mut file = File::create(&dest.join("gl_bindings.rs")).unwrap();
-        let registry = Registry::new(Api::Gles2, (3, 0), Profile::Core, Fallbacks::All, []);
-        registry.write_bindings(StructGenerator, &mut file).unwrap();
+        write_gles_bindings(&dest.join("gl_bindings.rs"));
     } else {
         let mut file = File::create(&dest.join("gl_bindings.rs")).unwrap();
-        let registry = Registry::new(Api::Gl, (3, 3), Profile::Core, Fallbacks::All, []);
+        let registry = Registry::new(
+            Api::Gl,
+            (3, 3),
+            Profile::Core,
+            Fallbacks::All,
+            ["GL_ARB_robustness", "GL_KHR_debug"],
+        );
         registry.write_bindings(StructGenerator, &mut file).unwrap();
     }
+
+    // Generate OpenGL ES bindings for applications that choose OpenGL ES contexts.
+    if target_os != "macos" && target_family.as_ref().map_or(false, |f| f == "unix") {
+        write_gles_bindings(&dest.join("gles_bindings.rs"));
+    }
+}
+
+fn write_gles_bindings(path: &Path) {
+    let mut file = File::create(path).unwrap();
+    let registry = Registry::new(
+        Api::Gles2,
+        (3, 2),
+        Profile::Core,
+        Fallbacks::All,
+        ["GL_EXT_robustness", "GL_KHR_debug"],
+    );
+    registry.write_bindings(StructGenerator, &mut file).unwrap();
 }
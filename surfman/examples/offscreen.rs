@@ -14,8 +14,8 @@ use std::fs::File;
 use std::mem;
 use std::path::Path;
 use std::slice;
-use surfman::{Connection, ContextAttributeFlags, ContextAttributes, GLApi, GLVersion};
-use surfman::{SurfaceAccess, SurfaceType};
+use surfman::{Connection, ContextAttributeFlags, ContextAttributes, DepthFormat, GLApi};
+use surfman::{ContextPriority, GLVersion, SurfaceAccess, SurfaceType};
 
 mod common;
 
@@ -103,6 +103,9 @@ fn main() {
     let context_attributes = ContextAttributes {
         version: GLVersion::new(3, 3),
         flags: ContextAttributeFlags::empty(),
+        samples: 0,
+        depth_format: DepthFormat::Depth24,
+        priority: ContextPriority::Medium,
     };
     let context_descriptor = device
         .create_context_descriptor(&context_attributes)
@@ -15,7 +15,7 @@ use surfman::{Adapter, Connection, Context, ContextDescriptor, Device, GLApi, Su
 use self::common::FilesystemResourceLoader;
 
 #[cfg(not(target_os = "android"))]
-use surfman::{ContextAttributeFlags, ContextAttributes, GLVersion};
+use surfman::{ContextAttributeFlags, ContextAttributes, ContextPriority, DepthFormat, GLVersion};
 #[cfg(not(target_os = "android"))]
 use winit::dpi::PhysicalSize;
 #[cfg(not(target_os = "android"))]
@@ -108,6 +108,9 @@ fn main() {
     let context_attributes = ContextAttributes {
         version: GLVersion::new(3, 0),
         flags: ContextAttributeFlags::ALPHA,
+        samples: 0,
+        depth_format: DepthFormat::Depth24,
+        priority: ContextPriority::Medium,
     };
     let context_descriptor = device
         .create_context_descriptor(&context_attributes)
@@ -0,0 +1,99 @@
+// surfman/surfman/src/capabilities.rs
+//
+//! Information about what a device supports.
+
+use crate::gl;
+use crate::gl::types::{GLenum, GLint};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextPriority, DepthFormat, GLVersion};
+use crate::{Gl, SurfaceFormat};
+
+/// Describes what a device supports, as returned by `Device::capabilities()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceCapabilities {
+    /// The EGL client extensions, which don't depend on the display.
+    ///
+    /// This is empty on backends that don't use EGL.
+    pub client_extensions: Vec<String>,
+    /// The extensions that the EGL display of the device supports.
+    ///
+    /// This is empty on backends that don't use EGL.
+    pub display_extensions: Vec<String>,
+    /// The largest width or height of a texture, and therefore of a surface.
+    pub max_texture_size: i32,
+    /// The largest width or height of a renderbuffer.
+    pub max_renderbuffer_size: i32,
+    /// The largest number of samples for multisampled surfaces.
+    ///
+    /// This is 0 if the implementation doesn't support multisampled renderbuffers.
+    pub max_samples: i32,
+    /// The formats that generic surfaces can be created with.
+    ///
+    /// `SurfaceFormat::RGBA8` is always included.
+    pub surface_formats: Vec<SurfaceFormat>,
+    /// Whether surface textures can be created from surfaces of contexts that are current on
+    /// other threads.
+    pub cross_thread_surface_textures: bool,
+    /// Whether surfaces can be exported and imported into other devices, or other processes.
+    pub cross_device_surface_textures: bool,
+    /// Whether `lock_surface_data()` can read surfaces back on the CPU.
+    pub lock_surface_data: bool,
+    /// Whether widget surfaces, which display on screen, can be created.
+    pub widgets: bool,
+}
+
+// The formats other than `RGBA8` that generic surfaces may support, depending on the driver.
+#[allow(dead_code)]
+pub(crate) static OPTIONAL_SURFACE_FORMATS: [SurfaceFormat; 4] = [
+    SurfaceFormat::BGRA8,
+    SurfaceFormat::RGBA16F,
+    SurfaceFormat::RGB10A2,
+    SurfaceFormat::SRGB8A8,
+];
+
+impl DeviceCapabilities {
+    // The attributes of the temporary context that capabilities are probed with. If the
+    // implementation doesn't support this version, context creation falls back to a lower one.
+    #[allow(dead_code)]
+    pub(crate) fn probe_context_attributes() -> ContextAttributes {
+        ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::empty(),
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        }
+    }
+
+    // Describes the current context. Everything that isn't a property of the context is left for
+    // the backend to fill in.
+    #[allow(dead_code)]
+    pub(crate) fn current(gl: &Gl) -> DeviceCapabilities {
+        let max_samples = get_integer(gl, gl::MAX_SAMPLES);
+        if max_samples == 0 {
+            // OpenGL ES 2.0 doesn't know about this query and flags it as an error.
+            unsafe {
+                gl.GetError();
+            }
+        }
+        DeviceCapabilities {
+            client_extensions: vec![],
+            display_extensions: vec![],
+            max_texture_size: get_integer(gl, gl::MAX_TEXTURE_SIZE),
+            max_renderbuffer_size: get_integer(gl, gl::MAX_RENDERBUFFER_SIZE),
+            max_samples,
+            surface_formats: vec![SurfaceFormat::RGBA8],
+            cross_thread_surface_textures: false,
+            cross_device_surface_textures: false,
+            lock_surface_data: false,
+            widgets: false,
+        }
+    }
+}
+
+fn get_integer(gl: &Gl, name: GLenum) -> GLint {
+    unsafe {
+        let mut value = 0;
+        gl.GetIntegerv(name, &mut value);
+        value
+    }
+}
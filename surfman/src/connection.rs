@@ -34,6 +34,14 @@ pub trait Connection: Sized {
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
     fn gl_api(&self) -> GLApi;
 
+    /// Chooses whether devices opened from now on create OpenGL or OpenGL ES contexts.
+    ///
+    /// Backends that only support one of the two return `UnsupportedGLType` for the other.
+    /// Implementations that don't override this method return `UnsupportedOnThisPlatform`.
+    fn set_gl_api(&mut self, _gl_api: GLApi) -> Result<(), Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
@@ -37,7 +37,8 @@ bitflags! {
         /// channels, 32 bits per pixel, 8 bits per channel). If this is not present, surfaces will
         /// be RGBX or BGRX (i.e. 3 channels, 32 bits per pixel, 8 bits per channel).
         const ALPHA                 = 0x01;
-        /// Surfaces created for this context will have a 24-bit depth buffer.
+        /// Surfaces created for this context will have a depth buffer, of the format given by
+        /// `ContextAttributes::depth_format`.
         const DEPTH                 = 0x02;
         /// Surfaces created for this context will have an 8-bit stencil buffer, possibly using
         /// packed depth/stencil if the GL implementation supports it.
@@ -45,6 +46,29 @@ bitflags! {
         /// The OpenGL compatibility profile will be used. If this is not present, the core profile
         /// is used.
         const COMPATIBILITY_PROFILE = 0x08;
+        /// The depth buffer of generic surfaces created for this context will be a texture that
+        /// other contexts can sample through a `SurfaceTexture`, instead of a renderbuffer. If the
+        /// depth format is packed with stencil, the texture holds the stencil buffer too.
+        ///
+        /// Backends that don't support this ignore this flag and don't report it.
+        const SAMPLEABLE_DEPTH      = 0x10;
+        /// The context will have robust buffer access, and will be lost if the GPU is reset, so
+        /// that the reset can be detected with `Device::context_reset_status()`.
+        ///
+        /// Backends that don't support this ignore this flag and don't report it.
+        const ROBUST_ACCESS         = 0x20;
+        /// The context will be a debug context. Its debug messages are forwarded to the `log`
+        /// crate, or to a callback registered with `set_debug_callback()`.
+        ///
+        /// Backends that don't support this ignore this flag and don't report it.
+        const DEBUG                 = 0x40;
+        /// The context will not report OpenGL errors, which lets the implementation skip error
+        /// checking. Calls that would have raised an error have undefined results instead.
+        ///
+        /// Debug and robust contexts always check for errors, so this flag is ignored if
+        /// `DEBUG` or `ROBUST_ACCESS` is present. Backends that don't support this ignore this
+        /// flag and don't report it.
+        const NO_ERROR              = 0x80;
     }
 }
 
@@ -57,9 +81,106 @@ pub struct ContextAttributes {
     ///
     /// Keep in mind that OpenGL and OpenGL ES have different version numbering schemes. Before
     /// filling in this field, check the result of `Device::gl_api()`.
+    ///
+    /// On the EGL backends, if the implementation doesn't support this version, contexts are
+    /// created with the highest supported version below it instead. `Device::context_descriptor()`
+    /// reports the version that a context actually got.
     pub version: GLVersion,
     /// Various flags.
     pub flags: ContextAttributeFlags,
+    /// The number of samples per pixel for multisample antialiasing of surfaces created for this
+    /// context. 0 or 1 means that surfaces are single-sampled.
+    ///
+    /// Multisampled surfaces are rendered to through multisampled renderbuffers and resolved into
+    /// their shareable texture automatically. The count is clamped to what the GL implementation
+    /// supports. Backends that don't support multisampled surfaces report 0 here.
+    pub samples: u8,
+    /// The format of the depth buffer of surfaces created for this context, if they have one.
+    ///
+    /// If surfaces also have a stencil buffer, the depth buffer is packed together with it, and
+    /// `Depth16` is rounded up to 24 bits, since there's no packed format for it. Backends that
+    /// don't support formats other than `Depth24` report `Depth24` here.
+    pub depth_format: DepthFormat,
+    /// How the GPU should schedule this context's work relative to that of other contexts.
+    ///
+    /// The implementation may grant a different priority than the one requested; the granted
+    /// priority is reported for the descriptors of existing contexts. Backends that don't support
+    /// context priorities report `Medium` here.
+    pub priority: ContextPriority,
+}
+
+/// The format of the depth buffer of surfaces.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DepthFormat {
+    /// 16-bit normalized depth.
+    Depth16,
+    /// 24-bit normalized depth. This is the default.
+    Depth24,
+    /// 32-bit floating point depth.
+    Depth32F,
+}
+
+/// Describes the pixel format behind a context descriptor, as reported by
+/// `Device::context_descriptor_info()`.
+///
+/// This is mostly useful for diagnosing why no pixel format matches a set of context attributes;
+/// see `Device::enumerate_context_descriptors()`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ContextDescriptorInfo {
+    /// The ID of the pixel format. On EGL, this is `EGL_CONFIG_ID`.
+    pub id: i32,
+    /// The number of bits in the red channel of the color buffer.
+    pub red_bits: u8,
+    /// The number of bits in the green channel of the color buffer.
+    pub green_bits: u8,
+    /// The number of bits in the blue channel of the color buffer.
+    pub blue_bits: u8,
+    /// The number of bits in the alpha channel of the color buffer.
+    pub alpha_bits: u8,
+    /// The number of bits in the depth buffer.
+    pub depth_bits: u8,
+    /// The number of bits in the stencil buffer.
+    pub stencil_bits: u8,
+    /// The number of samples per pixel of the default framebuffer, or 0 if it's single-sampled.
+    ///
+    /// This is unrelated to `ContextAttributes::samples`, which applies to generic surfaces.
+    pub samples: u8,
+    /// The kinds of native surfaces that the pixel format supports. On EGL, this is the
+    /// `EGL_SURFACE_TYPE` bitmask.
+    pub surface_types: i32,
+    /// The client APIs that the pixel format supports. On EGL, this is the `EGL_RENDERABLE_TYPE`
+    /// bitmask.
+    pub renderable_types: i32,
+    /// The ID of the native visual that corresponds to the pixel format, or 0 if there is none.
+    pub native_visual_id: i32,
+}
+
+/// The scheduling priority of a context.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ContextPriority {
+    /// Work from this context may be preempted by that of other contexts.
+    Low,
+    /// The usual priority. This is the default.
+    Medium,
+    /// Work from this context may preempt that of other contexts.
+    High,
+}
+
+/// Whether a context has been lost to a GPU reset, as reported by
+/// `Device::context_reset_status()`.
+///
+/// Once a reset has been reported, the context and every surface created from it are unusable.
+/// They should be destroyed, and new ones created in their place.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ContextResetStatus {
+    /// No reset has occurred.
+    NoReset,
+    /// A reset occurred that was caused by this context.
+    Guilty,
+    /// A reset occurred that was caused by another context.
+    Innocent,
+    /// A reset occurred whose cause is unknown.
+    Unknown,
 }
 
 impl ContextAttributes {
@@ -68,6 +189,55 @@ impl ContextAttributes {
         ContextAttributes {
             version: GLVersion::new(0, 0),
             flags: ContextAttributeFlags::empty(),
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        }
+    }
+}
+
+impl Default for DepthFormat {
+    #[inline]
+    fn default() -> DepthFormat {
+        DepthFormat::Depth24
+    }
+}
+
+impl Default for ContextPriority {
+    #[inline]
+    fn default() -> ContextPriority {
+        ContextPriority::Medium
+    }
+}
+
+#[cfg(target_os = "android")]
+#[allow(dead_code)]
+pub(crate) fn current_context_reset_status(gl: &Gl) -> ContextResetStatus {
+    unsafe {
+        if !gl.GetGraphicsResetStatusEXT.is_loaded() {
+            return ContextResetStatus::NoReset;
+        }
+        match gl.GetGraphicsResetStatusEXT() {
+            gl::GUILTY_CONTEXT_RESET_EXT => ContextResetStatus::Guilty,
+            gl::INNOCENT_CONTEXT_RESET_EXT => ContextResetStatus::Innocent,
+            gl::UNKNOWN_CONTEXT_RESET_EXT => ContextResetStatus::Unknown,
+            _ => ContextResetStatus::NoReset,
+        }
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+#[allow(dead_code)]
+pub(crate) fn current_context_reset_status(gl: &Gl) -> ContextResetStatus {
+    unsafe {
+        if !gl.GetGraphicsResetStatusARB.is_loaded() {
+            return ContextResetStatus::NoReset;
+        }
+        match gl.GetGraphicsResetStatusARB() {
+            gl::GUILTY_CONTEXT_RESET_ARB => ContextResetStatus::Guilty,
+            gl::INNOCENT_CONTEXT_RESET_ARB => ContextResetStatus::Innocent,
+            gl::UNKNOWN_CONTEXT_RESET_ARB => ContextResetStatus::Unknown,
+            _ => ContextResetStatus::NoReset,
         }
     }
 }
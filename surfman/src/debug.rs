@@ -0,0 +1,189 @@
+// surfman/surfman/src/debug.rs
+//
+//! Forwarding of the debug messages of contexts created with `ContextAttributeFlags::DEBUG`.
+
+use crate::context::ContextID;
+use crate::gl::types::{GLchar, GLenum, GLsizei, GLuint};
+use crate::Gl;
+
+use std::collections::HashMap;
+use std::fmt::{self, Debug, Formatter};
+use std::os::raw::c_void;
+use std::panic;
+use std::slice;
+use std::sync::{Arc, Mutex};
+
+// These have a `_KHR` suffix in the OpenGL ES bindings, so spell them out.
+const GL_DEBUG_OUTPUT_SYNCHRONOUS: GLenum = 0x8242;
+const GL_DEBUG_SEVERITY_NOTIFICATION: GLenum = 0x826b;
+const GL_DEBUG_SEVERITY_HIGH: GLenum = 0x9146;
+const GL_DEBUG_SEVERITY_MEDIUM: GLenum = 0x9147;
+
+lazy_static! {
+    static ref DEBUG_CALLBACKS: Mutex<HashMap<ContextID, Arc<DebugCallback>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// A function that receives the debug messages of a context.
+pub type DebugCallback = dyn Fn(&DebugMessage) + Send + Sync;
+
+/// A message that the OpenGL implementation reported about a debug context, via `KHR_debug`.
+#[derive(Clone)]
+pub struct DebugMessage<'a> {
+    /// The context that the message is about.
+    pub context_id: ContextID,
+    /// The source of the message, such as `GL_DEBUG_SOURCE_API`.
+    pub source: GLenum,
+    /// The type of the message, such as `GL_DEBUG_TYPE_ERROR`.
+    pub message_type: GLenum,
+    /// The implementation-specific ID of the message.
+    pub id: GLuint,
+    /// How important the message is.
+    pub severity: DebugSeverity,
+    /// The text of the message.
+    pub text: &'a str,
+}
+
+/// How important a debug message is.
+///
+/// Messages that no callback has been registered for are logged at the level given for each
+/// severity.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum DebugSeverity {
+    /// Errors and undefined behavior. Logged as errors.
+    High,
+    /// Major performance problems and use of deprecated functionality. Logged as warnings.
+    Medium,
+    /// Redundant state changes and minor performance problems. Logged as information.
+    Low,
+    /// Anything else. Logged as debug messages.
+    Notification,
+}
+
+impl<'a> Debug for DebugMessage<'a> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "GL debug message {:#x} (source {:#x}, type {:#x}) from context {}: {}",
+            self.id, self.source, self.message_type, self.context_id.0, self.text
+        )
+    }
+}
+
+impl DebugSeverity {
+    fn from_gl(severity: GLenum) -> DebugSeverity {
+        match severity {
+            GL_DEBUG_SEVERITY_HIGH => DebugSeverity::High,
+            GL_DEBUG_SEVERITY_MEDIUM => DebugSeverity::Medium,
+            GL_DEBUG_SEVERITY_NOTIFICATION => DebugSeverity::Notification,
+            _ => DebugSeverity::Low,
+        }
+    }
+
+    fn log_level(self) -> log::Level {
+        match self {
+            DebugSeverity::High => log::Level::Error,
+            DebugSeverity::Medium => log::Level::Warn,
+            DebugSeverity::Low => log::Level::Info,
+            DebugSeverity::Notification => log::Level::Debug,
+        }
+    }
+}
+
+/// Sends the debug messages of the given context to `callback` instead of the `log` crate.
+///
+/// Passing `None` goes back to logging them. The callback is called on whichever thread the
+/// context is current on, while the offending OpenGL call is being made. It is dropped when the
+/// context is destroyed. If the callback panics, the panic is caught and logged rather than
+/// unwinding into the OpenGL implementation.
+///
+/// This has no effect unless the context was created with `ContextAttributeFlags::DEBUG` on a
+/// backend that supports it.
+pub fn set_debug_callback(context_id: ContextID, callback: Option<Box<DebugCallback>>) {
+    let mut debug_callbacks = DEBUG_CALLBACKS.lock().unwrap();
+    match callback {
+        Some(callback) => drop(debug_callbacks.insert(context_id, Arc::from(callback))),
+        None => drop(debug_callbacks.remove(&context_id)),
+    }
+}
+
+// Routes the debug messages of the current context to `debug_message_callback()`.
+//
+// The context is identified by the user parameter, so that the callback can look it up in
+// `DEBUG_CALLBACKS`. Messages are delivered synchronously, so that a debugger breaking in the
+// callback shows the offending call.
+#[cfg(not(target_os = "android"))]
+#[allow(dead_code)]
+pub(crate) unsafe fn install_debug_callback(gl: &Gl, context_id: ContextID) {
+    if !gl.DebugMessageCallback.is_loaded() {
+        return;
+    }
+    gl.Enable(GL_DEBUG_OUTPUT_SYNCHRONOUS);
+    gl.DebugMessageCallback(
+        Some(debug_message_callback),
+        context_id.0 as usize as *const c_void,
+    );
+}
+
+#[cfg(target_os = "android")]
+#[allow(dead_code)]
+pub(crate) unsafe fn install_debug_callback(gl: &Gl, context_id: ContextID) {
+    if !gl.DebugMessageCallbackKHR.is_loaded() {
+        return;
+    }
+    gl.Enable(GL_DEBUG_OUTPUT_SYNCHRONOUS);
+    gl.DebugMessageCallbackKHR(
+        Some(debug_message_callback),
+        context_id.0 as usize as *const c_void,
+    );
+}
+
+extern "system" fn debug_message_callback(
+    source: GLenum,
+    message_type: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    text: *const GLchar,
+    user_param: *mut c_void,
+) {
+    // Unwinding into the OpenGL implementation is undefined behavior, so a panicking callback
+    // only gets its panic logged.
+    let result = panic::catch_unwind(|| unsafe {
+        forward_debug_message(source, message_type, id, severity, length, text, user_param)
+    });
+    if result.is_err() {
+        error!("A debug callback panicked; the debug message was dropped.");
+    }
+}
+
+unsafe fn forward_debug_message(
+    source: GLenum,
+    message_type: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    length: GLsizei,
+    text: *const GLchar,
+    user_param: *mut c_void,
+) {
+    let text = slice::from_raw_parts(text as *const u8, length.max(0) as usize);
+    let message = DebugMessage {
+        context_id: ContextID(user_param as usize as u64),
+        source,
+        message_type,
+        id,
+        severity: DebugSeverity::from_gl(severity),
+        text: &String::from_utf8_lossy(text),
+    };
+
+    // Don't hold the lock while calling out, in case the callback replaces itself.
+    let callback = DEBUG_CALLBACKS
+        .lock()
+        .unwrap()
+        .get(&message.context_id)
+        .cloned();
+    match callback {
+        Some(callback) => callback(&message),
+        None => log!(message.severity.log_level(), "{:?}", message),
+    }
+}
@@ -4,7 +4,9 @@
 
 use super::connection::Connection as ConnectionInterface;
 use crate::gl::types::{GLenum, GLuint};
-use crate::{ContextAttributes, ContextID, Error, GLApi, SurfaceAccess, SurfaceInfo, SurfaceType};
+use crate::{ContextAttributes, ContextDescriptorInfo, ContextID, ContextResetStatus};
+use crate::{DeviceCapabilities, Error, GLApi, GLInfo, LeakReport};
+use crate::{SurfaceAccess, SurfaceInfo, SurfaceType};
 use euclid::default::Size2D;
 
 use std::os::raw::c_void;
@@ -28,6 +30,8 @@ where
     type Surface;
     /// The surface texture type associated with this device.
     type SurfaceTexture;
+    /// The fence type associated with this device.
+    type Fence;
 
     // device.rs
 
@@ -43,6 +47,24 @@ where
     /// Returns the OpenGL API flavor that this device supports (OpenGL or OpenGL ES).
     fn gl_api(&self) -> GLApi;
 
+    /// Describes what this device supports.
+    ///
+    /// The first call may create a temporary context and surfaces to find out, so it can be
+    /// relatively expensive. Devices keep the result, so later calls are cheap. Limits such as
+    /// the maximum texture size are those of an OpenGL or OpenGL ES 3.0 context, or of the
+    /// closest lower version that the implementation supports. Backends that can't answer return
+    /// `UnsupportedOnThisPlatform`.
+    fn capabilities(&mut self) -> Result<DeviceCapabilities, Error>;
+
+    /// Returns the contexts, surfaces, and surface textures that haven't been destroyed, along
+    /// with where they were created.
+    ///
+    /// This covers the objects of every device in the process. Objects are only tracked with the
+    /// `sm-leak-tracking` feature; without it, the report is always empty.
+    fn leak_report(&self) -> LeakReport {
+        crate::leaks::report()
+    }
+
     // context.rs
 
     /// Creates a context descriptor with the given attributes.
@@ -53,6 +75,18 @@ where
         attributes: &ContextAttributes,
     ) -> Result<Self::ContextDescriptor, Error>;
 
+    /// Returns a context descriptor for every pixel format of the device, whether or not it
+    /// matches the given attributes, starting with the one that `create_context_descriptor()`
+    /// chooses if there is one.
+    ///
+    /// Contexts created from them get the version and flags in the attributes. Use
+    /// `context_descriptor_info()` to tell them apart; not every pixel format can create a
+    /// context. Backends that can't enumerate pixel formats return `UnsupportedOnThisPlatform`.
+    fn enumerate_context_descriptors(
+        &self,
+        attributes: &ContextAttributes,
+    ) -> Result<Vec<Self::ContextDescriptor>, Error>;
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -94,6 +128,14 @@ where
         context_descriptor: &Self::ContextDescriptor,
     ) -> ContextAttributes;
 
+    /// Describes the pixel format behind the given context descriptor.
+    ///
+    /// Backends that can't describe pixel formats return `UnsupportedOnThisPlatform`.
+    fn context_descriptor_info(
+        &self,
+        context_descriptor: &Self::ContextDescriptor,
+    ) -> Result<ContextDescriptorInfo, Error>;
+
     /// Fetches the address of an OpenGL function associated with this context.
     ///
     /// OpenGL functions are local to a context. You should not use OpenGL functions on one context
@@ -139,9 +181,49 @@ where
     /// This includes, most notably, the OpenGL framebuffer object needed to render to the surface.
     fn context_surface_info(&self, context: &Self::Context) -> Result<Option<SurfaceInfo>, Error>;
 
+    /// Returns whether the context has been lost to a GPU reset since it was created.
+    ///
+    /// Resets are only reported for contexts created with `ContextAttributeFlags::ROBUST_ACCESS`
+    /// on backends that support it. For other contexts, this always returns `NoReset`.
+    ///
+    /// The context need not be current when calling this.
+    fn context_reset_status(&self, context: &Self::Context) -> Result<ContextResetStatus, Error>;
+
+    /// Returns the vendor, renderer, and version information of the given context.
+    ///
+    /// The context is temporarily made current if it isn't already.
+    /// If the version string is missing or can't be parsed, this returns an `UnknownGLVersion`
+    /// error.
+    fn context_gl_info(&self, context: &Self::Context) -> Result<GLInfo, Error>;
+
     /// Returns the native context associated with the given context.
     fn native_context(&self, context: &Self::Context) -> Self::NativeContext;
 
+    /// Inserts a fence into the command stream of the given context and flushes the context.
+    ///
+    /// Waiting on the fence with `wait_fence()` makes another context wait for all the commands
+    /// issued so far, such as rendering to a surface that is then handed to another thread.
+    /// Backends that don't support fences return `UnsupportedOnThisPlatform`.
+    ///
+    /// A fence must be explicitly destroyed with `destroy_fence()`.
+    fn create_fence(&self, context: &Self::Context) -> Result<Self::Fence, Error>;
+
+    /// Makes the given context wait for a fence to be signaled before executing any further
+    /// commands.
+    ///
+    /// The wait happens on the GPU, so this returns immediately. If the context can't see the
+    /// fence, an `IncompatibleContext` error is returned.
+    fn wait_fence(&self, context: &Self::Context, fence: &Self::Fence) -> Result<(), Error>;
+
+    /// Destroys a fence.
+    ///
+    /// If an error is returned, the fence is returned alongside it.
+    fn destroy_fence(
+        &self,
+        context: &Self::Context,
+        fence: Self::Fence,
+    ) -> Result<(), (Error, Self::Fence)>;
+
     // surface.rs
 
     /// Creates either a generic or a widget surface, depending on the supplied surface type.
@@ -176,8 +258,8 @@ where
     /// The supplied context must be the context the surface is associated with, or this returns
     /// an `IncompatibleSurface` error.
     ///
-    /// You must explicitly call this method to dispose of a surface. Otherwise, a panic occurs in
-    /// the `drop` method.
+    /// You must explicitly call this method to dispose of a surface. Otherwise, the surface leaks
+    /// when it's dropped, and the leak is reported as chosen with `set_leak_policy()`.
     fn destroy_surface(
         &self,
         context: &mut Self::Context,
@@ -215,7 +297,10 @@ where
         surface: &mut Self::Surface,
     ) -> Result<(), Error>;
 
-    /// Resizes a widget surface.
+    /// Resizes a surface.
+    ///
+    /// On some backends, generic surfaces are reallocated at the new size, which leaves their
+    /// contents undefined.
     fn resize_surface(
         &self,
         context: &Self::Context,
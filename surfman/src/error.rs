@@ -2,6 +2,9 @@
 //
 //! Various errors that methods can produce.
 
+use std::error;
+use std::fmt::{self, Display, Formatter};
+
 /// Various errors that methods can produce.
 #[derive(Debug)]
 pub enum Error {
@@ -21,18 +24,28 @@ pub enum Error {
     UnsupportedGLProfile,
     /// The system doesn't support the requested OpenGL API version.
     UnsupportedGLVersion,
+    /// The version of an OpenGL context couldn't be determined.
+    ///
+    /// The string is the `GL_VERSION` string that couldn't be parsed, or empty if the
+    /// implementation didn't return one.
+    UnknownGLVersion(String),
     /// Choosing an OpenGL pixel format failed.
-    PixelFormatSelectionFailed(WindowingApiError),
+    PixelFormatSelectionFailed(NativeError),
     /// The system couldn't choose an OpenGL pixel format.
     NoPixelFormatFound,
     /// The system couldn't create an OpenGL context.
-    ContextCreationFailed(WindowingApiError),
+    ContextCreationFailed(NativeError),
     /// The system couldn't destroy the OpenGL context.
-    ContextDestructionFailed(WindowingApiError),
+    ContextDestructionFailed(NativeError),
     /// The system couldn't make the OpenGL context current or not current.
-    MakeCurrentFailed(WindowingApiError),
+    MakeCurrentFailed(NativeError),
     /// The system OpenGL library couldn't be located.
-    NoGLLibraryFound,
+    ///
+    /// The string describes why loading each candidate library failed, as reported by the
+    /// dynamic loader.
+    NoGLLibraryFound(String),
+    /// The system OpenGL library has already been loaded, so it can no longer be replaced.
+    GLLibraryAlreadyLoaded,
     /// An extension necessary for this library to function isn't supported.
     RequiredExtensionUnavailable,
     /// Looking up an OpenGL function address failed.
@@ -46,13 +59,21 @@ pub enum Error {
     /// The device couldn't be opened.
     DeviceOpenFailed,
     /// The system couldn't create a surface.
-    SurfaceCreationFailed(WindowingApiError),
+    SurfaceCreationFailed(NativeError),
+    /// The system can't create or render to a surface with the requested format.
+    UnsupportedSurfaceFormat,
     /// The system couldn't import a surface from another thread.
-    SurfaceImportFailed(WindowingApiError),
+    SurfaceImportFailed(NativeError),
+    /// The system couldn't export a surface to a native buffer.
+    SurfaceExportFailed(NativeError),
     /// The system couldn't create a surface texture from a surface.
-    SurfaceTextureCreationFailed(WindowingApiError),
+    SurfaceTextureCreationFailed(NativeError),
     /// The system couldn't present a widget surface.
-    PresentFailed(WindowingApiError),
+    PresentFailed(NativeError),
+    /// The system couldn't create a GPU fence.
+    FenceCreationFailed(NativeError),
+    /// The system couldn't wait on a GPU fence.
+    FenceWaitFailed(NativeError),
     /// A context couldn't be created because there is no current context.
     NoCurrentContext,
     /// The current connection couldn't be fetched because there is no current connection.
@@ -96,10 +117,237 @@ pub enum Error {
     IncompatibleNativeDevice,
 }
 
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::Failed => f.write_str("the operation failed"),
+            Error::UnsupportedOnThisPlatform => {
+                f.write_str("the operation isn't supported on this platform")
+            }
+            Error::Unimplemented => f.write_str("the operation isn't implemented yet"),
+            Error::UnsupportedGLType => f.write_str("the requested OpenGL API type is unsupported"),
+            Error::UnsupportedGLProfile => {
+                f.write_str("the requested OpenGL compatibility profile is unsupported")
+            }
+            Error::UnsupportedGLVersion => {
+                f.write_str("the requested OpenGL version is unsupported")
+            }
+            Error::UnknownGLVersion(ref version_string) if version_string.is_empty() => {
+                f.write_str("the OpenGL implementation didn't report a version")
+            }
+            Error::UnknownGLVersion(ref version_string) => {
+                write!(f, "couldn't parse the OpenGL version {:?}", version_string)
+            }
+            Error::PixelFormatSelectionFailed(ref err) => {
+                write!(f, "couldn't choose a pixel format: {}", err)
+            }
+            Error::NoPixelFormatFound => f.write_str("no suitable pixel format was found"),
+            Error::ContextCreationFailed(ref err) => {
+                write!(f, "couldn't create a context: {}", err)
+            }
+            Error::ContextDestructionFailed(ref err) => {
+                write!(f, "couldn't destroy a context: {}", err)
+            }
+            Error::MakeCurrentFailed(ref err) => {
+                write!(f, "couldn't make a context current or not current: {}", err)
+            }
+            Error::NoGLLibraryFound(ref reason) => {
+                write!(
+                    f,
+                    "the system OpenGL library couldn't be loaded: {}",
+                    reason
+                )
+            }
+            Error::GLLibraryAlreadyLoaded => {
+                f.write_str("the system OpenGL library has already been loaded")
+            }
+            Error::RequiredExtensionUnavailable => {
+                f.write_str("a required extension is unavailable")
+            }
+            Error::GLFunctionNotFound => f.write_str("an OpenGL function couldn't be found"),
+            Error::ExternalRenderTarget => {
+                f.write_str("the context renders to an external render target")
+            }
+            Error::SurfaceAlreadyBound => f.write_str("a surface is already bound to the context"),
+            Error::NoAdapterFound => f.write_str("no suitable adapter was found"),
+            Error::DeviceOpenFailed => f.write_str("the device couldn't be opened"),
+            Error::SurfaceCreationFailed(ref err) => {
+                write!(f, "couldn't create a surface: {}", err)
+            }
+            Error::UnsupportedSurfaceFormat => f.write_str("the surface format is unsupported"),
+            Error::SurfaceImportFailed(ref err) => write!(f, "couldn't import a surface: {}", err),
+            Error::SurfaceExportFailed(ref err) => write!(f, "couldn't export a surface: {}", err),
+            Error::SurfaceTextureCreationFailed(ref err) => {
+                write!(f, "couldn't create a surface texture: {}", err)
+            }
+            Error::PresentFailed(ref err) => write!(f, "couldn't present a surface: {}", err),
+            Error::FenceCreationFailed(ref err) => write!(f, "couldn't create a fence: {}", err),
+            Error::FenceWaitFailed(ref err) => write!(f, "couldn't wait on a fence: {}", err),
+            Error::NoCurrentContext => f.write_str("there is no current context"),
+            Error::NoCurrentConnection => f.write_str("there is no current connection"),
+            Error::IncompatibleSurface => {
+                f.write_str("the surface was not created from this context")
+            }
+            Error::IncompatibleContextDescriptor => {
+                f.write_str("the context descriptor is incompatible with this device")
+            }
+            Error::IncompatibleContext => {
+                f.write_str("the context is incompatible with this device")
+            }
+            Error::IncompatibleSharedContext => {
+                f.write_str("the shared context is incompatible for sharing")
+            }
+            Error::IncompatibleSurfaceTexture => {
+                f.write_str("the surface texture is incompatible with this device")
+            }
+            Error::NoWidgetAttached => f.write_str("the surface has no widget attached"),
+            Error::WidgetAttached => f.write_str("the surface has a widget attached"),
+            Error::InvalidNativeWidget => f.write_str("the native widget is invalid"),
+            Error::SurfaceDataInaccessible => {
+                f.write_str("the surface data isn't accessible from the CPU")
+            }
+            Error::SurfaceLockFailed => f.write_str("the surface couldn't be locked"),
+            Error::ConnectionFailed => {
+                f.write_str("a connection to the display server couldn't be opened")
+            }
+            Error::ConnectionRequired => {
+                f.write_str("a connection to the window server is required")
+            }
+            Error::IncompatibleAdapter => {
+                f.write_str("the adapter is incompatible with this connection")
+            }
+            Error::IncompatibleNativeWidget => {
+                f.write_str("the native widget is incompatible with this device")
+            }
+            Error::IncompatibleWinitWindow => {
+                f.write_str("the winit window is incompatible with this backend")
+            }
+            Error::IncompatibleNativeContext => {
+                f.write_str("the native context is incompatible with this device")
+            }
+            Error::IncompatibleNativeDevice => {
+                f.write_str("the native device is incompatible with this connection")
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::PixelFormatSelectionFailed(ref err)
+            | Error::ContextCreationFailed(ref err)
+            | Error::ContextDestructionFailed(ref err)
+            | Error::MakeCurrentFailed(ref err)
+            | Error::SurfaceCreationFailed(ref err)
+            | Error::SurfaceImportFailed(ref err)
+            | Error::SurfaceExportFailed(ref err)
+            | Error::SurfaceTextureCreationFailed(ref err)
+            | Error::PresentFailed(ref err)
+            | Error::FenceCreationFailed(ref err)
+            | Error::FenceWaitFailed(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// An error reported by the native windowing API, along with the raw error code if there was one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NativeError {
+    /// The abstracted kind of error.
+    pub kind: WindowingApiError,
+    /// The raw error code that the native API returned, if any.
+    pub code: Option<NativeErrorCode>,
+    /// The native operation that failed, such as `eglCreateContext`, if known.
+    pub context: Option<&'static str>,
+}
+
+impl NativeError {
+    /// Creates an error of the given kind with a raw error code and the name of the native
+    /// operation that failed.
+    #[inline]
+    pub fn new(
+        kind: WindowingApiError,
+        code: NativeErrorCode,
+        context: &'static str,
+    ) -> NativeError {
+        NativeError {
+            kind,
+            code: Some(code),
+            context: Some(context),
+        }
+    }
+
+    /// Creates an error of the given kind with the name of the native operation that failed, for
+    /// operations that don't report an error code.
+    #[inline]
+    pub fn in_operation(kind: WindowingApiError, context: &'static str) -> NativeError {
+        NativeError {
+            kind,
+            code: None,
+            context: Some(context),
+        }
+    }
+}
+
+impl From<WindowingApiError> for NativeError {
+    #[inline]
+    fn from(kind: WindowingApiError) -> NativeError {
+        NativeError {
+            kind,
+            code: None,
+            context: None,
+        }
+    }
+}
+
+impl Display for NativeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        Display::fmt(&self.kind, f)?;
+        if let Some(code) = self.code {
+            write!(f, " ({})", code)?;
+        }
+        if let Some(context) = self.context {
+            write!(f, " in {}", context)?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for NativeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.kind)
+    }
+}
+
+/// A raw error code, as returned by the native windowing or graphics API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NativeErrorCode {
+    /// An `EGLint` error code, as returned by `eglGetError()`.
+    EGL(i32),
+    /// A `GLenum` error code, as returned by `glGetError()`.
+    GL(u32),
+    /// A `CGLError` error code.
+    CGL(i32),
+    /// A Windows `HRESULT`.
+    HRESULT(i32),
+}
+
+impl Display for NativeErrorCode {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            NativeErrorCode::EGL(code) => write!(f, "EGL error {:#x}", code),
+            NativeErrorCode::GL(code) => write!(f, "GL error {:#x}", code),
+            NativeErrorCode::CGL(code) => write!(f, "CGL error {}", code),
+            NativeErrorCode::HRESULT(code) => write!(f, "HRESULT {:#010x}", code),
+        }
+    }
+}
+
 /// Abstraction of the errors that EGL, CGL, GLX, CGL, etc. return.
 ///
 /// They all tend to follow similar patterns.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum WindowingApiError {
     /// Miscellaneous error.
     Failed,
@@ -189,3 +437,44 @@ pub enum WindowingApiError {
     /// EGL: The EGL configuration is unsupported.
     BadConfig,
 }
+
+impl Display for WindowingApiError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            WindowingApiError::Failed => "failed",
+            WindowingApiError::BadAttribute => "bad attribute",
+            WindowingApiError::BadProperty => "bad property",
+            WindowingApiError::BadPixelFormat => "bad pixel format",
+            WindowingApiError::BadRendererInfo => "bad renderer info",
+            WindowingApiError::BadContext => "bad context",
+            WindowingApiError::BadDrawable => "bad drawable",
+            WindowingApiError::BadDisplay => "bad display",
+            WindowingApiError::BadState => "bad state",
+            WindowingApiError::BadValue => "bad value",
+            WindowingApiError::BadMatch => "bad match",
+            WindowingApiError::BadEnumeration => "bad enumeration",
+            WindowingApiError::BadOffScreen => "bad off-screen drawable",
+            WindowingApiError::BadFullScreen => "bad full-screen drawable",
+            WindowingApiError::BadWindow => "bad window",
+            WindowingApiError::BadAddress => "bad address",
+            WindowingApiError::BadCodeModule => "bad code module",
+            WindowingApiError::BadAlloc => "allocation failed",
+            WindowingApiError::BadConnection => "bad connection",
+            WindowingApiError::NotInitialized => "not initialized",
+            WindowingApiError::BadAccess => "bad access",
+            WindowingApiError::BadCurrentSurface => "bad current surface",
+            WindowingApiError::BadSurface => "bad surface",
+            WindowingApiError::BadParameter => "bad parameter",
+            WindowingApiError::BadNativePixmap => "bad native pixmap",
+            WindowingApiError::BadNativeWindow => "bad native window",
+            WindowingApiError::ContextLost => "context lost",
+            WindowingApiError::BadScreen => "bad screen",
+            WindowingApiError::NoExtension => "extension unavailable",
+            WindowingApiError::BadVisual => "bad visual",
+            WindowingApiError::BadOperation => "bad operation",
+            WindowingApiError::BadConfig => "bad config",
+        })
+    }
+}
+
+impl error::Error for WindowingApiError {}
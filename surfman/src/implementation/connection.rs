@@ -40,6 +40,11 @@ impl ConnectionInterface for Connection {
         Connection::gl_api(self)
     }
 
+    #[inline]
+    fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        Connection::set_gl_api(self, gl_api)
+    }
+
     #[inline]
     fn create_adapter(&self) -> Result<Adapter, Error> {
         Connection::create_adapter(self)
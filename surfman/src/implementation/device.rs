@@ -4,13 +4,15 @@
 //! `Device` trait for a backend.
 
 use super::super::connection::Connection;
-use super::super::context::{Context, ContextDescriptor, NativeContext};
+use super::super::context::{Context, ContextDescriptor, Fence, NativeContext};
 use super::super::device::{Adapter, Device};
 use super::super::surface::{NativeWidget, Surface, SurfaceTexture};
 use crate::connection::Connection as ConnectionInterface;
 use crate::device::Device as DeviceInterface;
 use crate::gl::types::{GLenum, GLuint};
-use crate::{ContextAttributes, ContextID, Error, GLApi, SurfaceAccess, SurfaceInfo, SurfaceType};
+use crate::{ContextAttributes, ContextDescriptorInfo, ContextID, ContextResetStatus};
+use crate::{DeviceCapabilities, Error, GLApi, GLInfo};
+use crate::{SurfaceAccess, SurfaceInfo, SurfaceType};
 use euclid::default::Size2D;
 
 use std::os::raw::c_void;
@@ -23,6 +25,7 @@ impl DeviceInterface for Device {
     type NativeContext = NativeContext;
     type Surface = Surface;
     type SurfaceTexture = SurfaceTexture;
+    type Fence = Fence;
 
     // device.rs
 
@@ -47,6 +50,11 @@ impl DeviceInterface for Device {
         Device::gl_api(self)
     }
 
+    #[inline]
+    fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        Device::capabilities(self)
+    }
+
     // context.rs
 
     #[inline]
@@ -57,6 +65,14 @@ impl DeviceInterface for Device {
         Device::create_context_descriptor(self, attributes)
     }
 
+    #[inline]
+    fn enumerate_context_descriptors(
+        &self,
+        attributes: &ContextAttributes,
+    ) -> Result<Vec<Self::ContextDescriptor>, Error> {
+        Device::enumerate_context_descriptors(self, attributes)
+    }
+
     #[inline]
     fn create_context(
         &mut self,
@@ -102,6 +118,14 @@ impl DeviceInterface for Device {
         Device::context_descriptor_attributes(self, context_descriptor)
     }
 
+    #[inline]
+    fn context_descriptor_info(
+        &self,
+        context_descriptor: &Self::ContextDescriptor,
+    ) -> Result<ContextDescriptorInfo, Error> {
+        Device::context_descriptor_info(self, context_descriptor)
+    }
+
     #[inline]
     fn get_proc_address(&self, context: &Self::Context, symbol_name: &str) -> *const c_void {
         Device::get_proc_address(self, context, symbol_name)
@@ -134,11 +158,40 @@ impl DeviceInterface for Device {
         Device::context_surface_info(self, context)
     }
 
+    #[inline]
+    fn context_reset_status(&self, context: &Self::Context) -> Result<ContextResetStatus, Error> {
+        Device::context_reset_status(self, context)
+    }
+
+    #[inline]
+    fn context_gl_info(&self, context: &Self::Context) -> Result<GLInfo, Error> {
+        Device::context_gl_info(self, context)
+    }
+
     #[inline]
     fn native_context(&self, context: &Self::Context) -> Self::NativeContext {
         Device::native_context(self, context)
     }
 
+    #[inline]
+    fn create_fence(&self, context: &Self::Context) -> Result<Self::Fence, Error> {
+        Device::create_fence(self, context)
+    }
+
+    #[inline]
+    fn wait_fence(&self, context: &Self::Context, fence: &Self::Fence) -> Result<(), Error> {
+        Device::wait_fence(self, context, fence)
+    }
+
+    #[inline]
+    fn destroy_fence(
+        &self,
+        context: &Self::Context,
+        fence: Self::Fence,
+    ) -> Result<(), (Error, Self::Fence)> {
+        Device::destroy_fence(self, context, fence)
+    }
+
     // surface.rs
 
     #[inline]
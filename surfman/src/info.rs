@@ -3,7 +3,8 @@
 //! OpenGL information.
 
 use crate::gl;
-use crate::Gl;
+use crate::gl::types::GLenum;
+use crate::{Error, Gl};
 
 use std::ffi::CStr;
 use std::os::raw::c_char;
@@ -36,30 +37,101 @@ impl GLVersion {
         GLVersion { major, minor }
     }
 
+    /// Parses an OpenGL version string, as returned by `glGetString(GL_VERSION)`.
+    ///
+    /// This understands the formats of the major desktop and OpenGL ES implementations, such as
+    /// "4.6.0 NVIDIA 535.54", "4.5 (Core Profile) Mesa 22.3.6", "OpenGL ES 3.2 Mesa 22.3.6", and
+    /// "OpenGL ES-CM 1.1", as well as WebGL-style strings such as "WebGL 1.0 (OpenGL ES 2.0
+    /// Chromium)", for which the underlying OpenGL ES version is returned. Returns `None` if no
+    /// version number can be found.
+    pub fn parse(version_string: &str) -> Option<GLVersion> {
+        // If an OpenGL ES version is mentioned, it's the one that describes the context, even if
+        // a wrapper API like WebGL comes first.
+        let version_string = match version_string.rfind("OpenGL ES") {
+            Some(index) => &version_string[index..],
+            None => version_string,
+        };
+        version_string
+            .split_whitespace()
+            .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))
+            .and_then(|word| {
+                let mut numbers = word.split('.');
+                let major = numbers.next()?.parse().ok()?;
+                let minor = numbers.next()?;
+                let minor_length = minor
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(minor.len());
+                let minor = minor[..minor_length].parse().ok()?;
+                Some(GLVersion { major, minor })
+            })
+    }
+
+    // Returns the version of the current context, parsed from its version string.
+    #[allow(dead_code)]
+    pub(crate) fn current(gl: &Gl) -> Result<GLVersion, Error> {
+        let version_string = get_string(gl, gl::VERSION).unwrap_or_default();
+        GLVersion::parse(&version_string).ok_or(Error::UnknownGLVersion(version_string))
+    }
+}
+
+/// Describes the OpenGL implementation behind a context.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GLInfo {
+    /// The company responsible for the implementation, as reported by `GL_VENDOR`.
+    pub vendor: String,
+    /// The name of the renderer, which is typically specific to the GPU, as reported by
+    /// `GL_RENDERER`.
+    pub renderer: String,
+    /// The version of the context, parsed from `GL_VERSION`.
+    pub version: GLVersion,
+    /// The full version string, as reported by `GL_VERSION`.
+    pub version_string: String,
+    /// The version of the shading language, as reported by `GL_SHADING_LANGUAGE_VERSION`.
+    ///
+    /// This is `None` on implementations without shaders, such as OpenGL ES 1.x.
+    pub shading_language_version: Option<String>,
+    /// Whether the context implements OpenGL or OpenGL ES.
+    pub api: GLApi,
+}
+
+impl GLInfo {
+    // Describes the current context.
     #[allow(dead_code)]
-    pub(crate) fn current(gl: &Gl) -> GLVersion {
-        unsafe {
-            let version_string = gl.GetString(gl::VERSION) as *const c_char;
-            let version_string = CStr::from_ptr(version_string)
-                .to_string_lossy()
-                .trim_start_matches("OpenGL ES")
-                .trim_start()
-                .to_owned();
-            let mut version_string_iter = version_string.split(|c| c == '.' || c == ' ');
-            let major_version: u8 = version_string_iter
-                .next()
-                .expect("Where's the major GL version?")
-                .parse()
-                .expect("Couldn't parse the major GL version!");
-            let minor_version: u8 = version_string_iter
-                .next()
-                .expect("Where's the minor GL version?")
-                .parse()
-                .expect("Couldn't parse the minor GL version!");
-            GLVersion {
-                major: major_version,
-                minor: minor_version,
+    pub(crate) fn current(gl: &Gl) -> Result<GLInfo, Error> {
+        let version_string = get_string(gl, gl::VERSION).unwrap_or_default();
+        let version = match GLVersion::parse(&version_string) {
+            Some(version) => version,
+            None => return Err(Error::UnknownGLVersion(version_string)),
+        };
+        let api = if version_string.contains("OpenGL ES") {
+            GLApi::GLES
+        } else {
+            GLApi::GL
+        };
+        let shading_language_version = get_string(gl, gl::SHADING_LANGUAGE_VERSION);
+        if shading_language_version.is_none() {
+            // Implementations without shaders flag the query as an error; don't leave that around.
+            unsafe {
+                gl.GetError();
             }
         }
+        Ok(GLInfo {
+            vendor: get_string(gl, gl::VENDOR).unwrap_or_default(),
+            renderer: get_string(gl, gl::RENDERER).unwrap_or_default(),
+            version,
+            version_string,
+            shading_language_version,
+            api,
+        })
+    }
+}
+
+fn get_string(gl: &Gl, name: GLenum) -> Option<String> {
+    unsafe {
+        let string = gl.GetString(name) as *const c_char;
+        if string.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(string).to_string_lossy().into_owned())
     }
 }
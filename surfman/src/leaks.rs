@@ -0,0 +1,249 @@
+// surfman/surfman/src/leaks.rs
+//
+//! Tracking of contexts, surfaces, surface textures, and fences that are dropped without being
+//! destroyed.
+//!
+//! With the `sm-leak-tracking` feature, every live object is recorded along with a backtrace of
+//! where it was created, so that leaks can be traced back to their origin.
+
+use crate::context::ContextID;
+use crate::surface::SurfaceID;
+
+#[cfg(feature = "sm-leak-tracking")]
+use std::backtrace::Backtrace;
+#[cfg(feature = "sm-leak-tracking")]
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::sync::Mutex;
+use std::thread;
+
+lazy_static! {
+    static ref LEAK_POLICY: Mutex<LeakPolicy> = Mutex::new(LeakPolicy::default());
+}
+
+#[cfg(feature = "sm-leak-tracking")]
+lazy_static! {
+    static ref LIVE_OBJECTS: Mutex<HashMap<TrackedObject, Backtrace>> = Mutex::new(HashMap::new());
+}
+
+/// What happens when a context, surface, surface texture, or fence is dropped without being
+/// destroyed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LeakPolicy {
+    /// Panic, unless the thread is already panicking, in which case the leak is logged instead.
+    ///
+    /// This is the default with the `sm-leak-tracking` feature. Surfaces on the Unix backends,
+    /// fences, and WGL contexts never panic when dropped; their leaks are logged instead.
+    Panic,
+    /// Log the leak as an error and carry on.
+    ///
+    /// This is the default without the `sm-leak-tracking` feature.
+    Log,
+    /// Silently carry on.
+    Ignore,
+}
+
+impl Default for LeakPolicy {
+    #[inline]
+    fn default() -> LeakPolicy {
+        if cfg!(feature = "sm-leak-tracking") {
+            LeakPolicy::Panic
+        } else {
+            LeakPolicy::Log
+        }
+    }
+}
+
+/// A context, surface, surface texture, or fence that must be destroyed explicitly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TrackedObject {
+    /// A context, which must be destroyed with `Device::destroy_context()`.
+    Context(ContextID),
+    /// A surface, which must be destroyed with `Device::destroy_surface()`.
+    Surface(SurfaceID),
+    /// A surface texture wrapping the surface with the given ID, which must be destroyed with
+    /// `Device::destroy_surface_texture()`.
+    SurfaceTexture(SurfaceID),
+    /// A fence, identified by the address of its native sync object, which must be destroyed with
+    /// `Device::destroy_fence()`.
+    Fence(usize),
+}
+
+/// The objects that haven't been destroyed, as returned by `Device::leak_report()`.
+///
+/// This includes objects that are still in use, as well as objects that were dropped without
+/// being destroyed.
+///
+/// Without the `sm-leak-tracking` feature, nothing is tracked and the report is always empty.
+#[derive(Clone, Debug, Default)]
+pub struct LeakReport {
+    /// The objects, in no particular order.
+    pub objects: Vec<LiveObject>,
+}
+
+/// An object that hasn't been destroyed, and where it was created.
+#[derive(Clone, Debug)]
+pub struct LiveObject {
+    /// The object.
+    pub object: TrackedObject,
+    /// The backtrace of the call that created the object.
+    pub backtrace: String,
+}
+
+/// Sets what happens when a context, surface, surface texture, or fence is dropped without being
+/// destroyed.
+///
+/// This applies to all devices in the process.
+pub fn set_leak_policy(policy: LeakPolicy) {
+    *LEAK_POLICY.lock().unwrap() = policy;
+}
+
+/// Returns what happens when a context, surface, surface texture, or fence is dropped without
+/// being destroyed.
+pub fn leak_policy() -> LeakPolicy {
+    *LEAK_POLICY.lock().unwrap()
+}
+
+impl TrackedObject {
+    fn destroy_method(&self) -> &'static str {
+        match *self {
+            TrackedObject::Context(_) => "destroy_context",
+            TrackedObject::Surface(_) => "destroy_surface",
+            TrackedObject::SurfaceTexture(_) => "destroy_surface_texture",
+            TrackedObject::Fence(_) => "destroy_fence",
+        }
+    }
+}
+
+impl Display for TrackedObject {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match *self {
+            TrackedObject::Context(context_id) => write!(f, "context {}", context_id.0),
+            TrackedObject::Surface(surface_id) => write!(f, "surface {}", surface_id),
+            TrackedObject::SurfaceTexture(surface_id) => {
+                write!(f, "surface texture of surface {}", surface_id)
+            }
+            TrackedObject::Fence(address) => write!(f, "fence {:x}", address),
+        }
+    }
+}
+
+impl Display for LeakReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        if self.objects.is_empty() {
+            return f.write_str("no undestroyed objects");
+        }
+        for live_object in &self.objects {
+            writeln!(f, "{} was created at:", live_object.object)?;
+            writeln!(f, "{}", live_object.backtrace)?;
+        }
+        Ok(())
+    }
+}
+
+// Records that an object was created here.
+#[cfg(feature = "sm-leak-tracking")]
+pub(crate) fn track(object: TrackedObject) {
+    let backtrace = Backtrace::force_capture();
+    LIVE_OBJECTS.lock().unwrap().insert(object, backtrace);
+}
+
+// Records that an object was destroyed.
+#[cfg(feature = "sm-leak-tracking")]
+pub(crate) fn untrack(object: TrackedObject) {
+    LIVE_OBJECTS.lock().unwrap().remove(&object);
+}
+
+// Records that an object is now known by another ID, as surfaces are after being resized.
+#[cfg(feature = "sm-leak-tracking")]
+pub(crate) fn retrack(old_object: TrackedObject, new_object: TrackedObject) {
+    let mut live_objects = LIVE_OBJECTS.lock().unwrap();
+    if let Some(backtrace) = live_objects.remove(&old_object) {
+        live_objects.insert(new_object, backtrace);
+    }
+}
+
+// Returns the objects that haven't been destroyed.
+#[cfg(feature = "sm-leak-tracking")]
+pub(crate) fn report() -> LeakReport {
+    let live_objects = LIVE_OBJECTS.lock().unwrap();
+    LeakReport {
+        objects: live_objects
+            .iter()
+            .map(|(&object, backtrace)| LiveObject {
+                object,
+                backtrace: backtrace.to_string(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(feature = "sm-leak-tracking")]
+fn creation_backtrace(object: TrackedObject) -> Option<String> {
+    let live_objects = LIVE_OBJECTS.lock().unwrap();
+    live_objects
+        .get(&object)
+        .map(|backtrace| backtrace.to_string())
+}
+
+#[cfg(not(feature = "sm-leak-tracking"))]
+#[inline]
+pub(crate) fn track(_: TrackedObject) {}
+
+#[cfg(not(feature = "sm-leak-tracking"))]
+#[inline]
+pub(crate) fn untrack(_: TrackedObject) {}
+
+#[cfg(not(feature = "sm-leak-tracking"))]
+#[inline]
+pub(crate) fn retrack(_: TrackedObject, _: TrackedObject) {}
+
+#[cfg(not(feature = "sm-leak-tracking"))]
+#[inline]
+pub(crate) fn report() -> LeakReport {
+    LeakReport::default()
+}
+
+#[cfg(not(feature = "sm-leak-tracking"))]
+#[inline]
+fn creation_backtrace(_: TrackedObject) -> Option<String> {
+    None
+}
+
+// Applies the leak policy to an object that is being dropped without having been destroyed.
+//
+// The object stays in the leak report, since its resources are never freed.
+pub(crate) fn dropped_without_destroying(object: TrackedObject) {
+    report_drop(object, leak_policy())
+}
+
+// Like `dropped_without_destroying()`, but logs the leak instead of panicking under the `Panic`
+// policy. This is for objects that used to be leaked silently, so that dropping them doesn't
+// start panicking.
+pub(crate) fn dropped_without_destroying_no_panic(object: TrackedObject) {
+    match leak_policy() {
+        LeakPolicy::Panic => report_drop(object, LeakPolicy::Log),
+        policy => report_drop(object, policy),
+    }
+}
+
+fn report_drop(object: TrackedObject, policy: LeakPolicy) {
+    if policy == LeakPolicy::Ignore {
+        return;
+    }
+
+    let mut message = format!(
+        "{} must be destroyed explicitly with `{}`!",
+        object,
+        object.destroy_method()
+    );
+    if let Some(backtrace) = creation_backtrace(object) {
+        message.push_str(&format!(" It was created at:\n{}", backtrace));
+    }
+
+    // Panicking again while unwinding would abort the process.
+    if policy == LeakPolicy::Panic && !thread::panicking() {
+        panic!("{}", message)
+    }
+    error!("{}", message);
+}
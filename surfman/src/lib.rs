@@ -40,16 +40,37 @@ pub mod connection;
 pub mod device;
 
 pub mod error;
-pub use crate::error::{Error, WindowingApiError};
+pub use crate::error::{Error, NativeError, NativeErrorCode, WindowingApiError};
 
 mod context;
-pub use crate::context::{ContextAttributeFlags, ContextAttributes, ContextID};
+pub use crate::context::{
+    ContextAttributeFlags, ContextAttributes, ContextDescriptorInfo, ContextID, ContextPriority,
+    ContextResetStatus, DepthFormat,
+};
+
+mod debug;
+pub use crate::debug::{set_debug_callback, DebugCallback, DebugMessage, DebugSeverity};
 
 mod info;
-pub use crate::info::{GLApi, GLVersion};
+pub use crate::info::{GLApi, GLInfo, GLVersion};
+
+mod leaks;
+pub use crate::leaks::{leak_policy, set_leak_policy};
+pub use crate::leaks::{LeakPolicy, LeakReport, LiveObject, TrackedObject};
+
+mod capabilities;
+pub use crate::capabilities::DeviceCapabilities;
 
 mod surface;
-pub use crate::surface::{SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType, SystemSurfaceInfo};
+pub use crate::surface::{
+    SurfaceAccess, SurfaceFormat, SurfaceID, SurfaceInfo, SurfaceType, SystemSurfaceInfo,
+};
+
+mod surface_pool;
+pub use crate::surface_pool::SurfacePool;
+
+mod swap_chain;
+pub use crate::swap_chain::SwapChain;
 
 pub mod macros;
 
@@ -58,6 +79,16 @@ pub(crate) use crate::gl::Gl;
 #[cfg(target_os = "android")]
 pub(crate) use crate::gl::Gles2 as Gl;
 
+#[cfg(any(android, angle, linux))]
+pub use crate::platform::generic::egl::device::{
+    set_egl_functions, set_egl_library_path, EGL_LIBRARY_ENV_VAR,
+};
+/// The EGL function table that `set_egl_functions()` takes.
+///
+/// Load it with `Egl::load_with()`.
+#[cfg(any(android, angle, linux))]
+pub use crate::egl::Egl;
+
 mod gl_utils;
 mod renderbuffers;
 
@@ -65,13 +96,23 @@ mod gl {
     include!(concat!(env!("OUT_DIR"), "/gl_bindings.rs"));
 }
 
+/// OpenGL ES 3.2 bindings, for rendering with contexts created by devices whose `gl_api()` is
+/// `GLApi::GLES`.
+///
+/// Load them with `Gles2::load_with()` and `Device::get_proc_address()`.
+#[cfg(any(android, linux))]
+#[allow(missing_docs)]
+pub mod gles {
+    include!(concat!(env!("OUT_DIR"), "/gles_bindings.rs"));
+}
+
 #[cfg(any(
     target_os = "android",
     all(target_os = "windows", feature = "sm-angle"),
     unix
 ))]
-#[allow(non_camel_case_types)]
-mod egl {
+#[allow(missing_docs, non_camel_case_types)]
+pub(crate) mod egl {
     use std::os::raw::{c_long, c_void};
     pub type khronos_utime_nanoseconds_t = khronos_uint64_t;
     pub type khronos_uint64_t = u64;
@@ -7,6 +7,7 @@
 use super::device::{Adapter, Device, NativeDevice};
 use super::ffi::ANativeWindow;
 use super::surface::NativeWidget;
+use crate::platform::generic::egl::device;
 use crate::Error;
 use crate::GLApi;
 
@@ -29,6 +30,7 @@ impl Connection {
     /// Connects to the default display.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        device::load_egl_library()?;
         Ok(Connection)
     }
 
@@ -50,6 +52,18 @@ impl Connection {
         GLApi::GLES
     }
 
+    /// Chooses the OpenGL API flavor for devices opened from now on.
+    ///
+    /// This backend only supports OpenGL ES, so any other choice returns `UnsupportedGLType`.
+    #[inline]
+    pub fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        if gl_api == GLApi::GLES {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedGLType)
+        }
+    }
+
     /// Returns the "best" adapter on this system.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
@@ -97,6 +111,7 @@ impl Connection {
         Ok(Device {
             egl_display: native_device.0,
             display_is_owned: false,
+            capabilities: None,
         })
     }
 
@@ -3,10 +3,12 @@
 //! A thread-local handle to the device.
 
 use super::connection::Connection;
+use super::context::{Context, GL_FUNCTIONS};
 use crate::egl;
 use crate::egl::types::EGLDisplay;
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
-use crate::{Error, GLApi};
+use crate::platform::generic::egl::context::CurrentContextGuard;
+use crate::platform::generic::egl::device::{self, EGL_FUNCTIONS};
+use crate::{DeviceCapabilities, Error, GLApi};
 
 /// Represents a hardware display adapter that can be used for rendering (including the CPU).
 ///
@@ -20,6 +22,7 @@ pub struct Adapter;
 pub struct Device {
     pub(crate) egl_display: EGLDisplay,
     pub(crate) display_is_owned: bool,
+    pub(crate) capabilities: Option<DeviceCapabilities>,
 }
 
 /// Wrapper for an `EGLDisplay`.
@@ -64,6 +67,7 @@ impl Device {
                 Ok(Device {
                     egl_display,
                     display_is_owned: true,
+                    capabilities: None,
                 })
             }
         })
@@ -92,4 +96,41 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GLES
     }
+
+    /// Describes what this device supports.
+    ///
+    /// The first call creates a temporary context to find out, so it's relatively expensive. The
+    /// result is kept, so later calls are cheap.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        if let Some(ref capabilities) = self.capabilities {
+            return Ok(capabilities.clone());
+        }
+
+        // Destroying the temporary context may leave no context current, so restore the current
+        // one afterward.
+        let _guard = CurrentContextGuard::new();
+        let context_attributes = DeviceCapabilities::probe_context_attributes();
+        let context_descriptor = self.create_context_descriptor(&context_attributes)?;
+        let mut context = self.create_context(&context_descriptor, None)?;
+        let result = self.probe_capabilities(&context);
+        self.destroy_context(&mut context)?;
+        let capabilities = result?;
+        self.capabilities = Some(capabilities.clone());
+        Ok(capabilities)
+    }
+
+    fn probe_capabilities(&self, context: &Context) -> Result<DeviceCapabilities, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        let capabilities = GL_FUNCTIONS.with(DeviceCapabilities::current);
+
+        // Surfaces on this backend are always RGBA8 and can't be read back on the CPU, but they
+        // can be shared with contexts on other threads.
+        Ok(DeviceCapabilities {
+            client_extensions: device::extensions(egl::NO_DISPLAY),
+            display_extensions: device::extensions(self.egl_display),
+            cross_thread_surface_textures: true,
+            widgets: true,
+            ..capabilities
+        })
+    }
 }
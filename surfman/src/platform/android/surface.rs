@@ -15,6 +15,7 @@ use crate::egl::types::{EGLSurface, EGLint};
 use crate::gl;
 use crate::gl::types::{GLenum, GLuint};
 use crate::gl_utils;
+use crate::leaks::{self, TrackedObject};
 use crate::platform::generic;
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGLImageKHR;
@@ -23,14 +24,14 @@ use crate::platform::generic::egl::ffi::EGL_IMAGE_PRESERVED_KHR;
 use crate::platform::generic::egl::ffi::EGL_NATIVE_BUFFER_ANDROID;
 use crate::platform::generic::egl::ffi::EGL_NO_IMAGE_KHR;
 use crate::renderbuffers::Renderbuffers;
-use crate::{Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType, WindowingApiError};
+use crate::{Error, NativeError, SurfaceAccess, SurfaceFormat, SurfaceID, SurfaceInfo};
+use crate::{SurfaceType, WindowingApiError};
 
 use euclid::default::Size2D;
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
 use std::os::raw::c_void;
 use std::ptr;
-use std::thread;
 
 pub use crate::platform::generic::egl::context::ContextDescriptor;
 
@@ -51,10 +52,12 @@ const SURFACE_GL_TEXTURE_TARGET: GLenum = gl::TEXTURE_2D;
 ///
 /// Depending on the platform, each surface may be internally double-buffered.
 ///
-/// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
+/// Surfaces must be destroyed with the `destroy_surface()` method. Dropping one without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Surface {
     pub(crate) context_id: ContextID,
     pub(crate) size: Size2D<i32>,
+    pub(crate) access: SurfaceAccess,
     pub(crate) objects: SurfaceObjects,
     pub(crate) destroyed: bool,
 }
@@ -98,8 +101,8 @@ impl Debug for Surface {
 
 impl Drop for Surface {
     fn drop(&mut self) {
-        if !self.destroyed && !thread::panicking() {
-            panic!("Should have destroyed the surface first with `destroy_surface()`!")
+        if !self.destroyed {
+            leaks::dropped_without_destroying(TrackedObject::Surface(self.id()));
         }
     }
 }
@@ -123,20 +126,23 @@ impl Device {
     pub fn create_surface(
         &mut self,
         context: &Context,
-        _: SurfaceAccess,
+        access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
-        match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+        let surface = match surface_type {
+            SurfaceType::Generic { size } => self.create_generic_surface(context, access, &size)?,
             SurfaceType::Widget { native_widget } => unsafe {
-                self.create_window_surface(context, native_widget.native_window)
+                self.create_window_surface(context, access, native_widget.native_window)?
             },
-        }
+        };
+        leaks::track(TrackedObject::Surface(surface.id()));
+        Ok(surface)
     }
 
     fn create_generic_surface(
         &mut self,
         context: &Context,
+        access: SurfaceAccess,
         size: &Size2D<i32>,
     ) -> Result<Surface, Error> {
         let _guard = self.temporarily_make_context_current(context)?;
@@ -160,7 +166,10 @@ impl Device {
                 let mut hardware_buffer = ptr::null_mut();
                 let result = AHardwareBuffer_allocate(&hardware_buffer_desc, &mut hardware_buffer);
                 if result != 0 {
-                    return Err(Error::SurfaceCreationFailed(WindowingApiError::Failed));
+                    return Err(Error::SurfaceCreationFailed(NativeError::in_operation(
+                        WindowingApiError::Failed,
+                        "AHardwareBuffer_allocate",
+                    )));
                 }
 
                 // Create an EGL image, and bind it to a texture.
@@ -191,6 +200,7 @@ impl Device {
                 Ok(Surface {
                     size: *size,
                     context_id: context.id,
+                    access,
                     objects: SurfaceObjects::HardwareBuffer {
                         hardware_buffer,
                         egl_image,
@@ -207,6 +217,7 @@ impl Device {
     unsafe fn create_window_surface(
         &mut self,
         context: &Context,
+        access: SurfaceAccess,
         native_window: *mut ANativeWindow,
     ) -> Result<Surface, Error> {
         let width = ANativeWindow_getWidth(native_window);
@@ -224,6 +235,7 @@ impl Device {
             Ok(Surface {
                 context_id: context.id,
                 size: Size2D::new(width, height),
+                access,
                 objects: SurfaceObjects::Window { egl_surface },
                 destroyed: false,
             })
@@ -259,6 +271,7 @@ impl Device {
                     let local_egl_image = self.create_egl_image(context, hardware_buffer);
                     let texture_object =
                         generic::egl::surface::bind_egl_image_to_gl_texture(gl, local_egl_image);
+                    leaks::track(TrackedObject::SurfaceTexture(surface.id()));
                     Ok(SurfaceTexture {
                         surface,
                         local_egl_image,
@@ -343,8 +356,8 @@ impl Device {
     /// The supplied context must be the context the surface is associated with, or this returns
     /// an `IncompatibleSurface` error.
     ///
-    /// You must explicitly call this method to dispose of a surface. Otherwise, a panic occurs in
-    /// the `drop` method.
+    /// You must explicitly call this method to dispose of a surface. Otherwise, the surface leaks
+    /// when it's dropped, and the leak is reported as chosen with `set_leak_policy()`.
     pub fn destroy_surface(
         &self,
         context: &mut Context,
@@ -354,6 +367,7 @@ impl Device {
             return Err(Error::IncompatibleSurface);
         }
 
+        leaks::untrack(TrackedObject::Surface(surface.id()));
         unsafe {
             match surface.objects {
                 SurfaceObjects::HardwareBuffer {
@@ -409,6 +423,7 @@ impl Device {
         mut surface_texture: SurfaceTexture,
     ) -> Result<Surface, (Error, SurfaceTexture)> {
         let _guard = self.temporarily_make_context_current(context);
+        leaks::untrack(TrackedObject::SurfaceTexture(surface_texture.surface.id()));
         GL_FUNCTIONS.with(|gl| {
             unsafe {
                 gl.DeleteTextures(1, &surface_texture.texture_object);
@@ -459,6 +474,8 @@ impl Device {
                 } => framebuffer_object,
                 SurfaceObjects::Window { .. } => 0,
             },
+            format: SurfaceFormat::RGBA8,
+            access: surface.access,
         }
     }
 
@@ -0,0 +1,91 @@
+// surfman/surfman/src/platform/generic/egl/capabilities.rs
+//
+//! Finding out what EGL-based devices support.
+
+use super::context::{ContextDescriptor, CurrentContextGuard, EGLBackedContext};
+use super::device;
+use super::surface::EGLBackedSurface;
+use crate::capabilities::OPTIONAL_SURFACE_FORMATS;
+use crate::egl;
+use crate::egl::types::EGLDisplay;
+use crate::{DeviceCapabilities, Error, GLApi, GLVersion, Gl, SurfaceAccess, SurfaceFormat};
+
+use euclid::default::Size2D;
+
+// Describes what the given display supports, by creating a temporary context from the descriptor
+// and a generic surface of each format in it.
+//
+// The limits are those of the temporary context. Backends pass a descriptor created from
+// `DeviceCapabilities::probe_context_attributes()`, so they're the limits of an OpenGL or OpenGL
+// ES 3.0 context, or of the highest version below that if the implementation lacks 3.0. Whether
+// widgets can be created depends on the backend.
+pub(crate) unsafe fn probe(
+    gl: &Gl,
+    egl_display: EGLDisplay,
+    context_descriptor: &ContextDescriptor,
+    gl_api: GLApi,
+    widgets: bool,
+) -> Result<DeviceCapabilities, Error> {
+    // Making the temporary context current replaces the current one, so restore that afterward.
+    let _guard = CurrentContextGuard::new();
+    let mut context = EGLBackedContext::new(gl, egl_display, context_descriptor, None, gl_api)?;
+    let result = probe_with_context(gl, egl_display, &context, context_descriptor, gl_api);
+    context.destroy(egl_display);
+    result.map(|capabilities| DeviceCapabilities {
+        widgets,
+        ..capabilities
+    })
+}
+
+unsafe fn probe_with_context(
+    gl: &Gl,
+    egl_display: EGLDisplay,
+    context: &EGLBackedContext,
+    context_descriptor: &ContextDescriptor,
+    gl_api: GLApi,
+) -> Result<DeviceCapabilities, Error> {
+    context.make_current(egl_display)?;
+
+    let context_attributes = context_descriptor.attributes(egl_display);
+    let mut surface_formats = vec![SurfaceFormat::RGBA8];
+    for &format in &OPTIONAL_SURFACE_FORMATS {
+        match EGLBackedSurface::new_generic(
+            gl,
+            egl_display,
+            context.egl_context,
+            context.id,
+            &context_attributes,
+            gl_api,
+            &Size2D::new(1, 1),
+            SurfaceAccess::GPUOnly,
+            format,
+        ) {
+            Ok(mut surface) => {
+                surface.destroy(gl, egl_display, context.id)?;
+                surface_formats.push(format);
+            }
+            Err(Error::UnsupportedSurfaceFormat) => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    // Surface textures alias the surface's EGL image in the other context. Locking reads the
+    // surface back through a pixel buffer object, which needs OpenGL or OpenGL ES 3.0.
+    let display_extensions = device::extensions(egl_display);
+    let display_extension_supported = |name: &str| display_extensions.iter().any(|ext| ext == name);
+    let cross_thread_surface_textures = display_extension_supported("EGL_KHR_gl_texture_2D_image");
+    let cross_device_surface_textures =
+        display_extension_supported("EGL_MESA_image_dma_buf_export")
+            && display_extension_supported("EGL_EXT_image_dma_buf_import");
+    let lock_surface_data = GLVersion::current(gl)?.major >= 3;
+
+    Ok(DeviceCapabilities {
+        client_extensions: device::extensions(egl::NO_DISPLAY),
+        display_extensions,
+        surface_formats,
+        cross_thread_surface_textures,
+        cross_device_surface_textures,
+        lock_surface_data,
+        ..DeviceCapabilities::current(gl)
+    })
+}
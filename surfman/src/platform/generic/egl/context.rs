@@ -2,33 +2,82 @@
 //
 //! Functionality common to backends using EGL contexts.
 
-use super::device::EGL_FUNCTIONS;
+use super::device::{self, EGL_FUNCTIONS};
 use super::error::ToWindowingApiError;
-use super::ffi::EGL_CONTEXT_OPENGL_PROFILE_MASK;
+use super::ffi::EGL_CONTEXT_OPENGL_NO_ERROR_KHR;
+use super::ffi::EGL_CONTEXT_OPENGL_ROBUST_ACCESS_EXT;
+use super::ffi::{EGL_CONTEXT_FLAGS_KHR, EGL_CONTEXT_OPENGL_DEBUG_BIT_KHR};
 use super::ffi::{EGL_CONTEXT_MINOR_VERSION_KHR, EGL_CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT};
+use super::ffi::{EGL_CONTEXT_OPENGL_PROFILE_MASK, EGL_CONTEXT_OPENGL_ROBUST_ACCESS_BIT_KHR};
+use super::ffi::{
+    EGL_CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY_EXT,
+    EGL_CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY_KHR,
+};
+use super::ffi::{
+    EGL_CONTEXT_PRIORITY_HIGH_IMG, EGL_CONTEXT_PRIORITY_LEVEL_IMG, EGL_CONTEXT_PRIORITY_LOW_IMG,
+    EGL_CONTEXT_PRIORITY_MEDIUM_IMG,
+};
 use super::surface::{EGLBackedSurface, ExternalEGLSurfaces};
 use crate::context::{self, CREATE_CONTEXT_MUTEX};
+use crate::debug;
 use crate::egl;
-use crate::egl::types::{EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLint};
+use crate::egl::types::{EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLenum, EGLint};
+use crate::gl;
+use crate::leaks::{self, TrackedObject};
 use crate::surface::Framebuffer;
-use crate::{ContextAttributeFlags, ContextAttributes, ContextID, Error, GLApi, GLVersion};
-use crate::{Gl, SurfaceInfo};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextID, ContextPriority};
+use crate::{ContextDescriptorInfo, ContextResetStatus, DepthFormat};
+use crate::{Error, GLApi, GLVersion, Gl, NativeError, SurfaceInfo, WindowingApiError};
 
 use std::ffi::CString;
 use std::mem;
 use std::os::raw::{c_char, c_void};
 use std::ptr;
-use std::thread;
 
 #[allow(dead_code)]
 const DUMMY_PBUFFER_SIZE: EGLint = 16;
 const RGB_CHANNEL_BIT_DEPTH: EGLint = 8;
 
+// The versions to try, in order, when the requested version can't be created.
+static GL_FALLBACK_VERSIONS: [GLVersion; 13] = [
+    GLVersion { major: 4, minor: 6 },
+    GLVersion { major: 4, minor: 5 },
+    GLVersion { major: 4, minor: 4 },
+    GLVersion { major: 4, minor: 3 },
+    GLVersion { major: 4, minor: 2 },
+    GLVersion { major: 4, minor: 1 },
+    GLVersion { major: 4, minor: 0 },
+    GLVersion { major: 3, minor: 3 },
+    GLVersion { major: 3, minor: 2 },
+    GLVersion { major: 3, minor: 1 },
+    GLVersion { major: 3, minor: 0 },
+    GLVersion { major: 2, minor: 1 },
+    GLVersion { major: 2, minor: 0 },
+];
+static GLES_FALLBACK_VERSIONS: [GLVersion; 4] = [
+    GLVersion { major: 3, minor: 2 },
+    GLVersion { major: 3, minor: 1 },
+    GLVersion { major: 3, minor: 0 },
+    GLVersion { major: 2, minor: 0 },
+];
+
 pub(crate) struct EGLBackedContext {
     pub(crate) egl_context: EGLContext,
     pub(crate) id: ContextID,
+    // The ID of the first context in the group of contexts that share objects with this one.
+    pub(crate) share_group: ContextID,
     framebuffer: Framebuffer<EGLBackedSurface, ExternalEGLSurfaces>,
     context_is_owned: bool,
+    // The number of samples and the depth buffer that surfaces for this context should have. EGL
+    // doesn't track these for us, since we render to framebuffer objects.
+    samples: u8,
+    depth_format: DepthFormat,
+    sampleable_depth: bool,
+    // EGL can't tell us whether a context is robust, a debug context, or a no-error context
+    // either.
+    robust_access: bool,
+    debug: bool,
+    no_error: bool,
 }
 
 /// Wrapper for a native `EGLContext`.
@@ -50,6 +99,13 @@ pub struct ContextDescriptor {
     pub(crate) egl_config_id: EGLint,
     pub(crate) gl_version: GLVersion,
     pub(crate) compatibility_profile: bool,
+    pub(crate) samples: u8,
+    pub(crate) depth_format: DepthFormat,
+    pub(crate) sampleable_depth: bool,
+    pub(crate) robust_access: bool,
+    pub(crate) debug: bool,
+    pub(crate) no_error: bool,
+    pub(crate) priority: ContextPriority,
 }
 
 #[must_use]
@@ -63,8 +119,8 @@ pub(crate) struct CurrentContextGuard {
 impl Drop for EGLBackedContext {
     #[inline]
     fn drop(&mut self) {
-        if self.egl_context != egl::NO_CONTEXT && !thread::panicking() {
-            panic!("Contexts must be destroyed explicitly with `destroy_context`!")
+        if self.egl_context != egl::NO_CONTEXT {
+            leaks::dropped_without_destroying(TrackedObject::Context(self.id));
         }
     }
 }
@@ -86,6 +142,7 @@ impl Drop for CurrentContextGuard {
 
 impl EGLBackedContext {
     pub(crate) unsafe fn new(
+        gl: &Gl,
         egl_display: EGLDisplay,
         descriptor: &ContextDescriptor,
         share_with: Option<&EGLBackedContext>,
@@ -100,14 +157,31 @@ impl EGLBackedContext {
             share_with.map_or(egl::NO_CONTEXT, |ctx| ctx.egl_context),
             gl_api,
         )?;
+        if descriptor.debug {
+            install_debug_callback(
+                gl,
+                egl_display,
+                egl::NO_SURFACE,
+                egl_context,
+                *next_context_id,
+            );
+        }
 
         // Wrap and return it.
         let context = EGLBackedContext {
             egl_context,
             id: *next_context_id,
+            share_group: share_with.map_or(*next_context_id, |ctx| ctx.share_group),
             framebuffer: Framebuffer::None,
             context_is_owned: true,
+            samples: descriptor.samples,
+            depth_format: descriptor.depth_format,
+            sampleable_depth: descriptor.sampleable_depth,
+            robust_access: descriptor.robust_access,
+            debug: descriptor.debug,
+            no_error: descriptor.no_error,
         };
+        leaks::track(TrackedObject::Context(context.id));
         next_context_id.0 += 1;
         Ok(context)
     }
@@ -117,12 +191,21 @@ impl EGLBackedContext {
         let context = EGLBackedContext {
             egl_context: native_context.egl_context,
             id: *next_context_id,
+            // We can't find out which contexts a native context shares objects with.
+            share_group: *next_context_id,
             framebuffer: Framebuffer::External(ExternalEGLSurfaces {
                 draw: native_context.egl_draw_surface,
                 read: native_context.egl_read_surface,
             }),
             context_is_owned: false,
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            sampleable_depth: false,
+            robust_access: false,
+            debug: false,
+            no_error: false,
         };
+        leaks::track(TrackedObject::Context(context.id));
         next_context_id.0 += 1;
         context
     }
@@ -143,6 +226,9 @@ impl EGLBackedContext {
 
             self.egl_context = egl::NO_CONTEXT;
         });
+
+        debug::set_debug_callback(self.id, None);
+        leaks::untrack(TrackedObject::Context(self.id));
     }
 
     pub(crate) fn native_context(&self) -> NativeContext {
@@ -174,7 +260,7 @@ impl EGLBackedContext {
                 self.egl_context,
             );
             if result == egl::FALSE {
-                let err = egl.GetError().to_windowing_api_error();
+                let err = egl.GetError().to_native_error("eglMakeCurrent");
                 return Err(Error::MakeCurrentFailed(err));
             }
             Ok(())
@@ -182,6 +268,26 @@ impl EGLBackedContext {
     }
 
     #[inline]
+    pub(crate) unsafe fn descriptor(&self, gl: &Gl, egl_display: EGLDisplay) -> ContextDescriptor {
+        let mut descriptor = ContextDescriptor::from_egl_context(gl, egl_display, self.egl_context);
+        descriptor.samples = self.samples;
+        descriptor.depth_format = self.depth_format;
+        descriptor.sampleable_depth = self.sampleable_depth;
+        descriptor.robust_access = self.robust_access;
+        descriptor.debug = self.debug;
+        descriptor.no_error = self.no_error;
+        descriptor
+    }
+
+    #[inline]
+    pub(crate) unsafe fn reset_status(
+        &self,
+        gl: &Gl,
+        egl_display: EGLDisplay,
+    ) -> Result<ContextResetStatus, Error> {
+        reset_status(gl, egl_display, self.egl_context)
+    }
+
     pub(crate) fn is_current(&self) -> bool {
         unsafe { EGL_FUNCTIONS.with(|egl| egl.GetCurrentContext() == self.egl_context) }
     }
@@ -265,121 +371,133 @@ impl ContextDescriptor {
         egl_display: EGLDisplay,
         attributes: &ContextAttributes,
         extra_config_attributes: &[EGLint],
+        gl_api: GLApi,
     ) -> Result<ContextDescriptor, Error> {
-        let flags = attributes.flags;
-
-        let alpha_size = if flags.contains(ContextAttributeFlags::ALPHA) {
-            8
-        } else {
-            0
-        };
-        let depth_size = if flags.contains(ContextAttributeFlags::DEPTH) {
-            24
-        } else {
-            0
-        };
-        let stencil_size = if flags.contains(ContextAttributeFlags::STENCIL) {
-            8
-        } else {
-            0
-        };
-
-        let compatibility_profile = flags.contains(ContextAttributeFlags::COMPATIBILITY_PROFILE);
-
-        // Mesa doesn't support the OpenGL compatibility profile post version 3.0. Take that into
-        // account.
-        if compatibility_profile
-            && (attributes.version.major > 3
-                || attributes.version.major == 3 && attributes.version.minor > 0)
-        {
-            return Err(Error::UnsupportedGLProfile);
+        let template = ContextDescriptor::from_attributes(egl_display, attributes, gl_api)?;
+        let egl_configs = choose_configs(egl_display, attributes.flags, extra_config_attributes)?;
+        match egl_configs.first() {
+            Some(&egl_config) => Ok(template.with_config(egl_display, egl_config)),
+            None => Err(Error::NoPixelFormatFound),
         }
+    }
 
-        // Create required config attributes.
-        //
-        // We check these separately because `eglChooseConfig` on its own might give us 32-bit
-        // color when 24-bit color is requested, and that can break code.
-        let required_config_attributes = [
-            egl::RED_SIZE as EGLint,
-            RGB_CHANNEL_BIT_DEPTH,
-            egl::GREEN_SIZE as EGLint,
-            RGB_CHANNEL_BIT_DEPTH,
-            egl::BLUE_SIZE as EGLint,
-            RGB_CHANNEL_BIT_DEPTH,
-        ];
-
-        // Create config attributes.
-        let mut requested_config_attributes = required_config_attributes.to_vec();
-        requested_config_attributes.extend_from_slice(&[
-            egl::ALPHA_SIZE as EGLint,
-            alpha_size,
-            egl::DEPTH_SIZE as EGLint,
-            depth_size,
-            egl::STENCIL_SIZE as EGLint,
-            stencil_size,
-        ]);
-        requested_config_attributes.extend_from_slice(extra_config_attributes);
-        requested_config_attributes.extend_from_slice(&[egl::NONE as EGLint, 0, 0, 0]);
-
+    // Returns a descriptor for every config of the display, whether or not it matches the given
+    // attributes, starting with the one that `new()` would choose if there is one. The rest are in
+    // the order that `eglGetConfigs()` returns them.
+    pub(crate) unsafe fn enumerate(
+        egl_display: EGLDisplay,
+        attributes: &ContextAttributes,
+        extra_config_attributes: &[EGLint],
+        gl_api: GLApi,
+    ) -> Result<Vec<ContextDescriptor>, Error> {
+        let template = ContextDescriptor::from_attributes(egl_display, attributes, gl_api)?;
+        let chosen_egl_config =
+            choose_configs(egl_display, attributes.flags, extra_config_attributes)?
+                .first()
+                .cloned();
+
+        let mut egl_configs: Vec<EGLConfig> = chosen_egl_config.into_iter().collect();
         EGL_FUNCTIONS.with(|egl| {
-            // See how many applicable configs there are.
             let mut config_count = 0;
-            let result = egl.ChooseConfig(
-                egl_display,
-                requested_config_attributes.as_ptr(),
-                ptr::null_mut(),
-                0,
-                &mut config_count,
-            );
+            let result = egl.GetConfigs(egl_display, ptr::null_mut(), 0, &mut config_count);
             if result == egl::FALSE {
-                let err = egl.GetError().to_windowing_api_error();
+                let err = egl.GetError().to_native_error("eglGetConfigs");
                 return Err(Error::PixelFormatSelectionFailed(err));
             }
-            if config_count == 0 {
-                return Err(Error::NoPixelFormatFound);
-            }
 
-            // Enumerate all those configs.
-            let mut configs = vec![ptr::null(); config_count as usize];
+            let mut all_egl_configs = vec![ptr::null(); config_count as usize];
             let mut real_config_count = config_count;
-            let result = egl.ChooseConfig(
+            let result = egl.GetConfigs(
                 egl_display,
-                requested_config_attributes.as_ptr(),
-                configs.as_mut_ptr(),
+                all_egl_configs.as_mut_ptr(),
                 config_count,
                 &mut real_config_count,
             );
             if result == egl::FALSE {
-                let err = egl.GetError().to_windowing_api_error();
+                let err = egl.GetError().to_native_error("eglGetConfigs");
                 return Err(Error::PixelFormatSelectionFailed(err));
             }
+            all_egl_configs.truncate(real_config_count as usize);
 
-            // Sanitize configs.
-            let egl_config = configs
-                .into_iter()
-                .filter(|&egl_config| {
-                    required_config_attributes
-                        .chunks(2)
-                        .all(|pair| get_config_attr(egl_display, egl_config, pair[0]) == pair[1])
-                })
-                .next();
-            let egl_config = match egl_config {
-                None => return Err(Error::NoPixelFormatFound),
-                Some(egl_config) => egl_config,
+            egl_configs.extend(
+                all_egl_configs
+                    .into_iter()
+                    .filter(|&egl_config| Some(egl_config) != chosen_egl_config),
+            );
+            Ok(())
+        })?;
+
+        Ok(egl_configs
+            .into_iter()
+            .map(|egl_config| template.with_config(egl_display, egl_config))
+            .collect())
+    }
+
+    // Returns a descriptor with the context parameters that follow from the given attributes and
+    // no config. The config is filled in by `with_config()`.
+    unsafe fn from_attributes(
+        egl_display: EGLDisplay,
+        attributes: &ContextAttributes,
+        gl_api: GLApi,
+    ) -> Result<ContextDescriptor, Error> {
+        let flags = attributes.flags;
+
+        let compatibility_profile = flags.contains(ContextAttributeFlags::COMPATIBILITY_PROFILE);
+        // OpenGL contexts ask for robustness through `EGL_KHR_create_context`, and OpenGL ES
+        // contexts through `EGL_EXT_create_context_robustness`.
+        let robustness_extension = match gl_api {
+            GLApi::GL => "EGL_KHR_create_context",
+            GLApi::GLES => "EGL_EXT_create_context_robustness",
+        };
+        let robust_access = flags.contains(ContextAttributeFlags::ROBUST_ACCESS)
+            && device::display_extension_supported(egl_display, robustness_extension);
+        // Debug and robust contexts can't skip error checking, so they take precedence.
+        let no_error = flags.contains(ContextAttributeFlags::NO_ERROR)
+            && !flags
+                .intersects(ContextAttributeFlags::DEBUG | ContextAttributeFlags::ROBUST_ACCESS)
+            && device::display_extension_supported(egl_display, "EGL_KHR_create_context_no_error");
+        let priority =
+            if device::display_extension_supported(egl_display, "EGL_IMG_context_priority") {
+                attributes.priority
+            } else {
+                ContextPriority::Medium
             };
 
-            // Get the config ID and version.
-            let egl_config_id = get_config_attr(egl_display, egl_config, egl::CONFIG_ID as EGLint);
-            let gl_version = attributes.version;
+        // Mesa doesn't support the OpenGL compatibility profile post version 3.0. Take that into
+        // account.
+        if compatibility_profile
+            && (attributes.version.major > 3
+                || attributes.version.major == 3 && attributes.version.minor > 0)
+        {
+            return Err(Error::UnsupportedGLProfile);
+        }
 
-            Ok(ContextDescriptor {
-                egl_config_id,
-                gl_version,
-                compatibility_profile,
-            })
+        Ok(ContextDescriptor {
+            egl_config_id: 0,
+            gl_version: attributes.version,
+            compatibility_profile,
+            samples: attributes.samples,
+            depth_format: attributes.depth_format,
+            sampleable_depth: flags.contains(ContextAttributeFlags::SAMPLEABLE_DEPTH),
+            robust_access,
+            debug: flags.contains(ContextAttributeFlags::DEBUG),
+            no_error,
+            priority,
         })
     }
 
+    // Returns a copy of this descriptor that uses the given config.
+    unsafe fn with_config(
+        &self,
+        egl_display: EGLDisplay,
+        egl_config: EGLConfig,
+    ) -> ContextDescriptor {
+        ContextDescriptor {
+            egl_config_id: get_config_attr(egl_display, egl_config, egl::CONFIG_ID as EGLint),
+            ..self.clone()
+        }
+    }
+
     pub(crate) unsafe fn from_egl_context(
         gl: &Gl,
         egl_display: EGLDisplay,
@@ -387,16 +505,43 @@ impl ContextDescriptor {
     ) -> ContextDescriptor {
         let egl_config_id = get_context_attr(egl_display, egl_context, egl::CONFIG_ID as EGLint);
 
+        // The implementation may not have granted the priority that was asked for, so ask it.
+        let priority =
+            if device::display_extension_supported(egl_display, "EGL_IMG_context_priority") {
+                let priority = get_context_attr(
+                    egl_display,
+                    egl_context,
+                    EGL_CONTEXT_PRIORITY_LEVEL_IMG as EGLint,
+                );
+                match priority as EGLenum {
+                    EGL_CONTEXT_PRIORITY_HIGH_IMG => ContextPriority::High,
+                    EGL_CONTEXT_PRIORITY_LOW_IMG => ContextPriority::Low,
+                    _ => ContextPriority::Medium,
+                }
+            } else {
+                ContextPriority::Medium
+            };
+
         EGL_FUNCTIONS.with(|egl| {
             let _guard = CurrentContextGuard::new();
             egl.MakeCurrent(egl_display, egl::NO_SURFACE, egl::NO_SURFACE, egl_context);
-            let gl_version = GLVersion::current(gl);
+            let gl_version = GLVersion::current(gl).unwrap_or_else(|err| {
+                warn!("Guessing the version of an EGL context: {}", err);
+                current_gl_version_fallback(gl, egl_display, egl_context)
+            });
             let compatibility_profile = context::current_context_uses_compatibility_profile(gl);
 
             ContextDescriptor {
                 egl_config_id,
                 gl_version,
                 compatibility_profile,
+                samples: 0,
+                depth_format: DepthFormat::Depth24,
+                sampleable_depth: false,
+                robust_access: false,
+                debug: false,
+                no_error: false,
+                priority,
             }
         })
     }
@@ -427,6 +572,24 @@ impl ContextDescriptor {
         })
     }
 
+    pub(crate) unsafe fn info(&self, egl_display: EGLDisplay) -> ContextDescriptorInfo {
+        let egl_config = egl_config_from_id(egl_display, self.egl_config_id);
+        let get = |attr: EGLenum| get_config_attr(egl_display, egl_config, attr as EGLint);
+        ContextDescriptorInfo {
+            id: self.egl_config_id,
+            red_bits: get(egl::RED_SIZE) as u8,
+            green_bits: get(egl::GREEN_SIZE) as u8,
+            blue_bits: get(egl::BLUE_SIZE) as u8,
+            alpha_bits: get(egl::ALPHA_SIZE) as u8,
+            depth_bits: get(egl::DEPTH_SIZE) as u8,
+            stencil_bits: get(egl::STENCIL_SIZE) as u8,
+            samples: get(egl::SAMPLES) as u8,
+            surface_types: get(egl::SURFACE_TYPE),
+            renderable_types: get(egl::RENDERABLE_TYPE),
+            native_visual_id: get(egl::NATIVE_VISUAL_ID),
+        }
+    }
+
     pub(crate) unsafe fn attributes(&self, egl_display: EGLDisplay) -> ContextAttributes {
         let egl_config = egl_config_from_id(egl_display, self.egl_config_id);
 
@@ -444,11 +607,21 @@ impl ContextDescriptor {
             ContextAttributeFlags::COMPATIBILITY_PROFILE,
             self.compatibility_profile,
         );
+        attribute_flags.set(
+            ContextAttributeFlags::SAMPLEABLE_DEPTH,
+            self.sampleable_depth,
+        );
+        attribute_flags.set(ContextAttributeFlags::ROBUST_ACCESS, self.robust_access);
+        attribute_flags.set(ContextAttributeFlags::DEBUG, self.debug);
+        attribute_flags.set(ContextAttributeFlags::NO_ERROR, self.no_error);
 
         // Create appropriate context attributes.
         ContextAttributes {
             flags: attribute_flags,
             version: self.gl_version,
+            samples: self.samples,
+            depth_format: self.depth_format,
+            priority: self.priority,
         }
     }
 }
@@ -466,6 +639,160 @@ impl CurrentContextGuard {
     }
 }
 
+// Works out the version of the current context when its version string is missing or in a format
+// we don't understand.
+//
+// OpenGL and OpenGL ES 3.0 and up report their version as integers too. Before that, EGL only
+// knows the major version of OpenGL ES contexts, and nothing about desktop OpenGL ones, which are
+// assumed to be version 1.0.
+unsafe fn current_gl_version_fallback(
+    gl: &Gl,
+    egl_display: EGLDisplay,
+    egl_context: EGLContext,
+) -> GLVersion {
+    let (mut major_version, mut minor_version) = (0, 0);
+    gl.GetIntegerv(gl::MAJOR_VERSION, &mut major_version);
+    gl.GetIntegerv(gl::MINOR_VERSION, &mut minor_version);
+    // Older implementations flag the query as an error; don't leave that around.
+    gl.GetError();
+    if major_version > 0 {
+        return GLVersion::new(major_version as u8, minor_version as u8);
+    }
+
+    let client_type =
+        get_context_attr(egl_display, egl_context, egl::CONTEXT_CLIENT_TYPE as EGLint);
+    if client_type as EGLenum == egl::OPENGL_ES_API {
+        let major_version = get_context_attr(
+            egl_display,
+            egl_context,
+            egl::CONTEXT_CLIENT_VERSION as EGLint,
+        );
+        GLVersion::new(major_version as u8, 0)
+    } else {
+        GLVersion::new(1, 0)
+    }
+}
+
+// Returns the configs that match the given attributes, in the order that `eglChooseConfig()` sorts
+// them.
+unsafe fn choose_configs(
+    egl_display: EGLDisplay,
+    flags: ContextAttributeFlags,
+    extra_config_attributes: &[EGLint],
+) -> Result<Vec<EGLConfig>, Error> {
+    let alpha_size = if flags.contains(ContextAttributeFlags::ALPHA) {
+        8
+    } else {
+        0
+    };
+    let depth_size = if flags.contains(ContextAttributeFlags::DEPTH) {
+        24
+    } else {
+        0
+    };
+    let stencil_size = if flags.contains(ContextAttributeFlags::STENCIL) {
+        8
+    } else {
+        0
+    };
+
+    // Create required config attributes.
+    //
+    // We check these separately because `eglChooseConfig` on its own might give us 32-bit
+    // color when 24-bit color is requested, and that can break code.
+    let required_config_attributes = [
+        egl::RED_SIZE as EGLint,
+        RGB_CHANNEL_BIT_DEPTH,
+        egl::GREEN_SIZE as EGLint,
+        RGB_CHANNEL_BIT_DEPTH,
+        egl::BLUE_SIZE as EGLint,
+        RGB_CHANNEL_BIT_DEPTH,
+    ];
+
+    // Create config attributes.
+    let mut requested_config_attributes = required_config_attributes.to_vec();
+    requested_config_attributes.extend_from_slice(&[
+        egl::ALPHA_SIZE as EGLint,
+        alpha_size,
+        egl::DEPTH_SIZE as EGLint,
+        depth_size,
+        egl::STENCIL_SIZE as EGLint,
+        stencil_size,
+    ]);
+    requested_config_attributes.extend_from_slice(extra_config_attributes);
+    requested_config_attributes.extend_from_slice(&[egl::NONE as EGLint, 0, 0, 0]);
+
+    EGL_FUNCTIONS.with(|egl| {
+        // See how many applicable configs there are.
+        let mut config_count = 0;
+        let result = egl.ChooseConfig(
+            egl_display,
+            requested_config_attributes.as_ptr(),
+            ptr::null_mut(),
+            0,
+            &mut config_count,
+        );
+        if result == egl::FALSE {
+            let err = egl.GetError().to_native_error("eglChooseConfig");
+            return Err(Error::PixelFormatSelectionFailed(err));
+        }
+        if config_count == 0 {
+            return Ok(vec![]);
+        }
+
+        // Enumerate all those configs.
+        let mut configs = vec![ptr::null(); config_count as usize];
+        let mut real_config_count = config_count;
+        let result = egl.ChooseConfig(
+            egl_display,
+            requested_config_attributes.as_ptr(),
+            configs.as_mut_ptr(),
+            config_count,
+            &mut real_config_count,
+        );
+        if result == egl::FALSE {
+            let err = egl.GetError().to_native_error("eglChooseConfig");
+            return Err(Error::PixelFormatSelectionFailed(err));
+        }
+
+        // Sanitize configs.
+        configs.truncate(real_config_count as usize);
+        configs.retain(|&egl_config| {
+            required_config_attributes
+                .chunks(2)
+                .all(|pair| get_config_attr(egl_display, egl_config, pair[0]) == pair[1])
+        });
+        Ok(configs)
+    })
+}
+
+// Returns the `EGL_RENDERABLE_TYPE` that configs for contexts with the given attributes must
+// support.
+//
+// OpenGL ES has no compatibility profile, so that flag is rejected for it.
+pub(crate) fn renderable_type(
+    gl_api: GLApi,
+    attributes: &ContextAttributes,
+) -> Result<EGLint, Error> {
+    match gl_api {
+        GLApi::GL => Ok(egl::OPENGL_BIT as EGLint),
+        GLApi::GLES
+            if attributes
+                .flags
+                .contains(ContextAttributeFlags::COMPATIBILITY_PROFILE) =>
+        {
+            Err(Error::UnsupportedGLProfile)
+        }
+        GLApi::GLES if attributes.version.major >= 3 => Ok(egl::OPENGL_ES3_BIT as EGLint),
+        GLApi::GLES => Ok(egl::OPENGL_ES2_BIT as EGLint),
+    }
+}
+
+// Creates a context with the version in the descriptor, or the highest supported version below
+// it if the implementation doesn't support that one.
+//
+// The version that was actually created can be read back with `GLVersion::current()`, as
+// `ContextDescriptor::from_egl_context()` does.
 pub(crate) unsafe fn create_context(
     egl_display: EGLDisplay,
     descriptor: &ContextDescriptor,
@@ -482,11 +809,93 @@ pub(crate) unsafe fn create_context(
 
     let egl_config = egl_config_from_id(egl_display, descriptor.egl_config_id);
 
+    // `EGL_KHR_create_context` reports unsupported versions with `EGL_BAD_MATCH`. Any other error
+    // won't go away by lowering the version, so report it as is.
+    let mut result = create_context_with_version(
+        egl_display,
+        egl_config,
+        descriptor,
+        descriptor.gl_version,
+        share_with,
+        gl_api,
+        true,
+    );
+
+    // Robustness, debug, no-error, and priority attributes are rejected with `EGL_BAD_MATCH` too.
+    // Only fall back to lower versions if the version is rejected without them; otherwise,
+    // lowering the version would just hide the error.
+    if is_bad_match(&result) {
+        let bare_result = create_context_with_version(
+            egl_display,
+            egl_config,
+            descriptor,
+            descriptor.gl_version,
+            share_with,
+            gl_api,
+            false,
+        );
+        match bare_result {
+            Ok(bare_egl_context) => {
+                EGL_FUNCTIONS.with(|egl| egl.DestroyContext(egl_display, bare_egl_context));
+                return result;
+            }
+            Err(_) if !is_bad_match(&bare_result) => return result,
+            Err(_) => {}
+        }
+    }
+
+    let fallback_versions = match gl_api {
+        GLApi::GL => &GL_FALLBACK_VERSIONS[..],
+        GLApi::GLES => &GLES_FALLBACK_VERSIONS[..],
+    };
+    for &gl_version in fallback_versions {
+        if !is_bad_match(&result) {
+            break;
+        }
+        if (gl_version.major, gl_version.minor)
+            >= (descriptor.gl_version.major, descriptor.gl_version.minor)
+        {
+            continue;
+        }
+        result = create_context_with_version(
+            egl_display,
+            egl_config,
+            descriptor,
+            gl_version,
+            share_with,
+            gl_api,
+            true,
+        );
+    }
+    result
+}
+
+fn is_bad_match(result: &Result<EGLContext, Error>) -> bool {
+    matches!(
+        *result,
+        Err(Error::ContextCreationFailed(NativeError {
+            kind: WindowingApiError::BadMatch,
+            ..
+        }))
+    )
+}
+
+// Creates a context with the given version and the attributes in the descriptor. If
+// `optional_attributes` is false, only the version and profile are requested.
+unsafe fn create_context_with_version(
+    egl_display: EGLDisplay,
+    egl_config: EGLConfig,
+    descriptor: &ContextDescriptor,
+    gl_version: GLVersion,
+    share_with: EGLContext,
+    gl_api: GLApi,
+    optional_attributes: bool,
+) -> Result<EGLContext, Error> {
     let mut egl_context_attributes = vec![
         egl::CONTEXT_CLIENT_VERSION as EGLint,
-        descriptor.gl_version.major as EGLint,
+        gl_version.major as EGLint,
         EGL_CONTEXT_MINOR_VERSION_KHR as EGLint,
-        descriptor.gl_version.minor as EGLint,
+        gl_version.minor as EGLint,
     ];
 
     // D3D11 ANGLE doesn't seem happy if EGL_CONTEXT_OPENGL_PROFILE_MASK is set
@@ -498,6 +907,50 @@ pub(crate) unsafe fn create_context(
         ]);
     }
 
+    // Ask to be notified of GPU resets, instead of leaving the context in an undefined state.
+    // `EGL_EXT_create_context_robustness` only covers OpenGL ES; desktop OpenGL goes through
+    // `EGL_KHR_create_context` instead.
+    let mut context_flags = 0;
+    if descriptor.robust_access && optional_attributes {
+        match gl_api {
+            GLApi::GL => {
+                context_flags |= EGL_CONTEXT_OPENGL_ROBUST_ACCESS_BIT_KHR;
+                egl_context_attributes.extend(&[
+                    EGL_CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY_KHR as EGLint,
+                    egl::LOSE_CONTEXT_ON_RESET as EGLint,
+                ]);
+            }
+            GLApi::GLES => egl_context_attributes.extend(&[
+                EGL_CONTEXT_OPENGL_ROBUST_ACCESS_EXT as EGLint,
+                egl::TRUE as EGLint,
+                EGL_CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY_EXT as EGLint,
+                egl::LOSE_CONTEXT_ON_RESET as EGLint,
+            ]),
+        }
+    }
+    if descriptor.debug && optional_attributes {
+        context_flags |= EGL_CONTEXT_OPENGL_DEBUG_BIT_KHR;
+    }
+    if context_flags != 0 {
+        egl_context_attributes.extend(&[EGL_CONTEXT_FLAGS_KHR as EGLint, context_flags]);
+    }
+    if descriptor.no_error && optional_attributes {
+        egl_context_attributes.extend(&[
+            EGL_CONTEXT_OPENGL_NO_ERROR_KHR as EGLint,
+            egl::TRUE as EGLint,
+        ]);
+    }
+
+    let priority = match descriptor.priority {
+        ContextPriority::Low => EGL_CONTEXT_PRIORITY_LOW_IMG,
+        ContextPriority::Medium => EGL_CONTEXT_PRIORITY_MEDIUM_IMG,
+        ContextPriority::High => EGL_CONTEXT_PRIORITY_HIGH_IMG,
+    };
+    if priority != EGL_CONTEXT_PRIORITY_MEDIUM_IMG && optional_attributes {
+        egl_context_attributes
+            .extend(&[EGL_CONTEXT_PRIORITY_LEVEL_IMG as EGLint, priority as EGLint]);
+    }
+
     // Include some extra zeroes to work around broken implementations.
     //
     // FIXME(pcwalton): Which implementations are those? (This is copied from Gecko.)
@@ -511,8 +964,7 @@ pub(crate) unsafe fn create_context(
             egl_context_attributes.as_ptr(),
         );
         if egl_context == egl::NO_CONTEXT {
-            let err = egl.GetError();
-            let err = err.to_windowing_api_error();
+            let err = egl.GetError().to_native_error("eglCreateContext");
             return Err(Error::ContextCreationFailed(err));
         }
 
@@ -520,6 +972,54 @@ pub(crate) unsafe fn create_context(
     })
 }
 
+// Routes the debug messages of a newly-created debug context to the `debug` module, making the
+// context current temporarily to do so.
+//
+// Debug messages are only diagnostics, so failing to do this doesn't fail context creation.
+pub(crate) unsafe fn install_debug_callback(
+    gl: &Gl,
+    egl_display: EGLDisplay,
+    egl_surface: EGLSurface,
+    egl_context: EGLContext,
+    context_id: ContextID,
+) {
+    EGL_FUNCTIONS.with(|egl| {
+        let _guard = CurrentContextGuard::new();
+        let result = egl.MakeCurrent(egl_display, egl_surface, egl_surface, egl_context);
+        if result == egl::FALSE {
+            let err = egl.GetError().to_native_error("eglMakeCurrent");
+            warn!("Failed to make a new debug context current: {}", err);
+            return;
+        }
+        debug::install_debug_callback(gl, context_id);
+    })
+}
+
+// Queries whether the given context has been reset, making it current temporarily if it isn't.
+pub(crate) unsafe fn reset_status(
+    gl: &Gl,
+    egl_display: EGLDisplay,
+    egl_context: EGLContext,
+) -> Result<ContextResetStatus, Error> {
+    EGL_FUNCTIONS.with(|egl| {
+        if egl.GetCurrentContext() == egl_context {
+            return Ok(context::current_context_reset_status(gl));
+        }
+
+        let _guard = CurrentContextGuard::new();
+        let result = egl.MakeCurrent(egl_display, egl::NO_SURFACE, egl::NO_SURFACE, egl_context);
+        if result == egl::FALSE {
+            let err = egl.GetError().to_native_error("eglMakeCurrent");
+            return match err.kind {
+                // Some implementations refuse to make lost contexts current at all.
+                WindowingApiError::ContextLost => Ok(ContextResetStatus::Unknown),
+                _ => Err(Error::MakeCurrentFailed(err)),
+            };
+        }
+        Ok(context::current_context_reset_status(gl))
+    })
+}
+
 pub(crate) unsafe fn make_no_context_current(egl_display: EGLDisplay) -> Result<(), Error> {
     EGL_FUNCTIONS.with(|egl| {
         let result = egl.MakeCurrent(
@@ -529,7 +1029,7 @@ pub(crate) unsafe fn make_no_context_current(egl_display: EGLDisplay) -> Result<
             egl::NO_CONTEXT,
         );
         if result == egl::FALSE {
-            let err = egl.GetError().to_windowing_api_error();
+            let err = egl.GetError().to_native_error("eglMakeCurrent");
             return Err(Error::MakeCurrentFailed(err));
         }
         Ok(())
@@ -2,14 +2,24 @@
 //
 //! Functionality common to backends using EGL displays.
 
+use super::ffi::{EGL_DEVICE_EXT, EGL_EXTENSION_FUNCTIONS, EGL_NO_DEVICE_EXT};
+use crate::egl;
+use crate::egl::types::{EGLAttrib, EGLDeviceEXT, EGLDisplay, EGLenum, EGLint};
 use crate::egl::Egl;
+use crate::Error;
 
-use std::ffi::CString;
+use std::cell::RefCell;
+use std::env;
+use std::ffi::{CStr, CString};
 use std::mem;
 use std::os::raw::{c_char, c_void};
+use std::ptr;
+use std::sync::Mutex;
 
 #[cfg(not(target_os = "windows"))]
-use libc::{dlopen, dlsym, RTLD_LAZY};
+use libc::{dlerror, dlopen, dlsym, RTLD_LAZY};
+#[cfg(target_os = "windows")]
+use std::io;
 #[cfg(target_os = "windows")]
 use winapi::shared::minwindef::HMODULE;
 #[cfg(target_os = "windows")]
@@ -17,55 +27,207 @@ use winapi::shared::ntdef::LPCSTR;
 #[cfg(target_os = "windows")]
 use winapi::um::libloaderapi;
 
+/// The environment variable that, if set, names the EGL library to load instead of the default
+/// ones.
+pub const EGL_LIBRARY_ENV_VAR: &str = "SURFMAN_EGL_LIBRARY";
+
+// The libraries to try, in order. Only the versioned name is guaranteed to exist on Linux; the
+// unversioned one usually comes from a `-dev` package.
+#[cfg(target_os = "windows")]
+static EGL_LIBRARY_NAMES: &[&str] = &["libEGL.dll"];
+#[cfg(not(target_os = "windows"))]
+static EGL_LIBRARY_NAMES: &[&str] = &["libEGL.so.1", "libEGL.so"];
+
+// The EGL function table. Use it as if it were a thread-local, with `EGL_FUNCTIONS.with()`.
+pub(crate) static EGL_FUNCTIONS: EGLFunctions = EGLFunctions;
+
 thread_local! {
-    pub static EGL_FUNCTIONS: Egl = Egl::load_with(get_proc_address);
+    // `const` thread-local initializers need a newer compiler than the oldest one we support.
+    #[allow(clippy::missing_const_for_thread_local)]
+    static THREAD_EGL_FUNCTIONS: RefCell<Option<Egl>> = RefCell::new(None);
 }
 
-#[cfg(target_os = "windows")]
+pub(crate) struct EGLFunctions;
+
 lazy_static! {
-    static ref EGL_LIBRARY: EGLLibraryWrapper = {
-        unsafe {
-            let module = libloaderapi::LoadLibraryA(&b"libEGL.dll\0"[0] as *const u8 as LPCSTR);
-            EGLLibraryWrapper(module)
-        }
+    static ref EGL_LIBRARY: Mutex<EGLLibrary> = Mutex::new(EGLLibrary {
+        path: None,
+        functions: None,
+    });
+}
+
+struct EGLLibrary {
+    // Overrides `EGL_LIBRARY_NAMES` and the environment variable, if set.
+    path: Option<String>,
+    // The function table, once the library has been loaded or a table has been supplied.
+    functions: Option<EGLFunctionsWrapper>,
+}
+
+struct EGLFunctionsWrapper(Egl);
+
+unsafe impl Send for EGLFunctionsWrapper {}
+
+impl EGLFunctions {
+    // Calls `f` with this thread's copy of the EGL function table, loading the library first if
+    // need be.
+    //
+    // If the library can't be loaded, `f` gets a table whose every function panics with a message
+    // saying that it wasn't loaded, which is better than calling through a null pointer.
+    // `Connection::new()` reports the actual error. Failure isn't remembered, so the thread picks
+    // up the library once a later attempt to load it succeeds.
+    pub(crate) fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&Egl) -> R,
+    {
+        THREAD_EGL_FUNCTIONS.with(|thread_functions| {
+            if thread_functions.borrow().is_none() {
+                if let Ok(functions) = egl_functions() {
+                    *thread_functions.borrow_mut() = Some(functions);
+                }
+            }
+
+            // Don't hold the borrow across `f` unless the table is loaded, so that a nested call
+            // can still store it.
+            let functions = thread_functions.borrow();
+            match *functions {
+                Some(ref functions) => f(functions),
+                None => {
+                    drop(functions);
+                    f(&Egl::load_with(|_| ptr::null()))
+                }
+            }
+        })
+    }
+}
+
+/// An EGL device, as returned by `EGL_EXT_device_enumeration`.
+///
+/// These are global to the process and never destroyed, so they can be freely sent between
+/// threads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EGLDevice(pub(crate) EGLDeviceEXT);
+
+unsafe impl Send for EGLDevice {}
+unsafe impl Sync for EGLDevice {}
+
+#[cfg(target_os = "windows")]
+#[derive(Clone, Copy)]
+pub(crate) struct EGLLibraryHandle(HMODULE);
+#[cfg(not(target_os = "windows"))]
+#[derive(Clone, Copy)]
+pub(crate) struct EGLLibraryHandle(*mut c_void);
+
+/// Loads the EGL library from the given path instead of the default locations.
+///
+/// This takes precedence over the `SURFMAN_EGL_LIBRARY` environment variable. It must be called
+/// before the first connection is created; once the EGL library has been loaded, this returns
+/// `Error::GLLibraryAlreadyLoaded`.
+pub fn set_egl_library_path(path: &str) -> Result<(), Error> {
+    let mut library = EGL_LIBRARY.lock().unwrap();
+    if library.functions.is_some() {
+        return Err(Error::GLLibraryAlreadyLoaded);
+    }
+    library.path = Some(path.to_owned());
+    Ok(())
+}
+
+/// Uses an EGL function table that the application has already loaded, instead of loading the EGL
+/// library.
+///
+/// This is useful when the application embeds or has already opened a particular EGL
+/// implementation. It must be called before the first connection is created; once the EGL library
+/// has been loaded, this returns `Error::GLLibraryAlreadyLoaded`.
+pub fn set_egl_functions(egl: Egl) -> Result<(), Error> {
+    let mut library = EGL_LIBRARY.lock().unwrap();
+    if library.functions.is_some() {
+        return Err(Error::GLLibraryAlreadyLoaded);
+    }
+    library.functions = Some(EGLFunctionsWrapper(egl));
+    Ok(())
+}
+
+// Loads the EGL library if it hasn't been already, so that failure can be reported as an error
+// instead of a panic on first use.
+pub(crate) fn load_egl_library() -> Result<(), Error> {
+    egl_functions().map(drop)
+}
+
+fn egl_functions() -> Result<Egl, Error> {
+    let mut library = EGL_LIBRARY.lock().unwrap();
+    if let Some(ref functions) = library.functions {
+        return Ok(functions.0.clone());
+    }
+
+    let path = library
+        .path
+        .clone()
+        .or_else(|| env::var(EGL_LIBRARY_ENV_VAR).ok());
+    let handle = match path {
+        Some(path) => open_library(&path).map_err(Error::NoGLLibraryFound)?,
+        None => open_first_library(EGL_LIBRARY_NAMES)?,
     };
+
+    let functions = Egl::load_with(|symbol_name| get_proc_address(handle, symbol_name));
+    library.functions = Some(EGLFunctionsWrapper(functions.clone()));
+    Ok(functions)
 }
 
-#[cfg(not(any(target_os = "windows", target_os = "macos")))]
-lazy_static! {
-    static ref EGL_LIBRARY: EGLLibraryWrapper = {
-        unsafe {
-            EGLLibraryWrapper(dlopen(
-                &b"libEGL.so\0"[0] as *const u8 as *const _,
-                RTLD_LAZY,
-            ))
+// Opens the first of the given libraries that loads, or reports why each of them didn't.
+pub(crate) fn open_first_library(names: &[&str]) -> Result<EGLLibraryHandle, Error> {
+    let mut errors = vec![];
+    for name in names {
+        match open_library(name) {
+            Ok(handle) => return Ok(handle),
+            Err(err) => errors.push(err),
         }
-    };
+    }
+    Err(Error::NoGLLibraryFound(errors.join("; ")))
 }
 
 #[cfg(target_os = "windows")]
-struct EGLLibraryWrapper(HMODULE);
-#[cfg(not(target_os = "windows"))]
-struct EGLLibraryWrapper(*mut c_void);
+fn open_library(name: &str) -> Result<EGLLibraryHandle, String> {
+    unsafe {
+        let name = CString::new(name).map_err(|err| err.to_string())?;
+        let module = libloaderapi::LoadLibraryA(name.as_ptr() as *const u8 as LPCSTR);
+        if module.is_null() {
+            let err = io::Error::last_os_error();
+            return Err(format!("{}: {}", name.to_string_lossy(), err));
+        }
+        Ok(EGLLibraryHandle(module))
+    }
+}
 
-unsafe impl Send for EGLLibraryWrapper {}
-unsafe impl Sync for EGLLibraryWrapper {}
+#[cfg(not(target_os = "windows"))]
+fn open_library(name: &str) -> Result<EGLLibraryHandle, String> {
+    unsafe {
+        let name = CString::new(name).map_err(|err| err.to_string())?;
+        let handle = dlopen(name.as_ptr(), RTLD_LAZY);
+        if handle.is_null() {
+            let err = dlerror();
+            if err.is_null() {
+                return Err(format!("{}: unknown error", name.to_string_lossy()));
+            }
+            return Err(CStr::from_ptr(err).to_string_lossy().into_owned());
+        }
+        Ok(EGLLibraryHandle(handle))
+    }
+}
 
 #[cfg(target_os = "windows")]
-fn get_proc_address(symbol_name: &str) -> *const c_void {
+fn get_proc_address(library: EGLLibraryHandle, symbol_name: &str) -> *const c_void {
     unsafe {
         let symbol_name: CString = CString::new(symbol_name).unwrap();
         let symbol_ptr = symbol_name.as_ptr() as *const u8 as LPCSTR;
-        libloaderapi::GetProcAddress(EGL_LIBRARY.0, symbol_ptr) as *const c_void
+        libloaderapi::GetProcAddress(library.0, symbol_ptr) as *const c_void
     }
 }
 
 #[cfg(not(target_os = "windows"))]
-fn get_proc_address(symbol_name: &str) -> *const c_void {
+fn get_proc_address(library: EGLLibraryHandle, symbol_name: &str) -> *const c_void {
     unsafe {
         let symbol_name: CString = CString::new(symbol_name).unwrap();
         let symbol_ptr = symbol_name.as_ptr() as *const u8 as *const c_char;
-        dlsym(EGL_LIBRARY.0, symbol_ptr) as *const c_void
+        dlsym(library.0, symbol_ptr) as *const c_void
     }
 }
 
@@ -73,3 +235,120 @@ pub(crate) unsafe fn lookup_egl_extension(name: &'static [u8]) -> *mut c_void {
     EGL_FUNCTIONS
         .with(|egl| mem::transmute(egl.GetProcAddress(&name[0] as *const u8 as *const c_char)))
 }
+
+// Returns the extensions that the given display supports, or the client extensions if the display
+// is `EGL_NO_DISPLAY`.
+pub(crate) fn extensions(egl_display: EGLDisplay) -> Vec<String> {
+    EGL_FUNCTIONS.with(|egl| unsafe {
+        let extensions = egl.QueryString(egl_display, egl::EXTENSIONS as EGLint);
+        if extensions.is_null() {
+            // Clear the error so that it doesn't confuse later callers.
+            egl.GetError();
+            return vec![];
+        }
+        CStr::from_ptr(extensions)
+            .to_string_lossy()
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect()
+    })
+}
+
+pub(crate) fn client_extension_supported(extension_name: &str) -> bool {
+    display_extension_supported(egl::NO_DISPLAY, extension_name)
+}
+
+pub(crate) fn display_extension_supported(egl_display: EGLDisplay, extension_name: &str) -> bool {
+    EGL_FUNCTIONS.with(|egl| unsafe {
+        let extensions = egl.QueryString(egl_display, egl::EXTENSIONS as EGLint);
+        if extensions.is_null() {
+            return false;
+        }
+        CStr::from_ptr(extensions)
+            .to_string_lossy()
+            .split_whitespace()
+            .any(|extension| extension == extension_name)
+    })
+}
+
+impl EGLDevice {
+    // Returns all the devices that EGL knows about, via `EGL_EXT_device_enumeration`.
+    pub(crate) fn enumerate() -> Result<Vec<EGLDevice>, Error> {
+        if !client_extension_supported("EGL_EXT_device_enumeration")
+            || !client_extension_supported("EGL_EXT_device_query")
+        {
+            return Err(Error::RequiredExtensionUnavailable);
+        }
+        let query_devices = match EGL_EXTENSION_FUNCTIONS.QueryDevicesEXT {
+            Some(query_devices) => query_devices,
+            None => return Err(Error::RequiredExtensionUnavailable),
+        };
+
+        let mut device_count = 0;
+        if query_devices(0, ptr::null_mut(), &mut device_count) == egl::FALSE {
+            return Err(Error::NoAdapterFound);
+        }
+
+        let mut devices = vec![EGL_NO_DEVICE_EXT; device_count as usize];
+        if query_devices(device_count, devices.as_mut_ptr(), &mut device_count) == egl::FALSE {
+            return Err(Error::NoAdapterFound);
+        }
+        devices.truncate(device_count as usize);
+
+        Ok(devices.into_iter().map(EGLDevice).collect())
+    }
+
+    // Queries a string attribute of this device, such as `EGL_EXTENSIONS`.
+    pub(crate) fn query_string(&self, name: EGLint) -> Option<String> {
+        let query_device_string = EGL_EXTENSION_FUNCTIONS.QueryDeviceStringEXT?;
+        let string = query_device_string(self.0, name);
+        if string.is_null() {
+            // Clear the error so that it doesn't confuse later callers.
+            EGL_FUNCTIONS.with(|egl| unsafe { egl.GetError() });
+            return None;
+        }
+        unsafe { Some(CStr::from_ptr(string).to_string_lossy().into_owned()) }
+    }
+
+    pub(crate) fn extension_supported(&self, extension_name: &str) -> bool {
+        match self.query_string(egl::EXTENSIONS as EGLint) {
+            Some(extensions) => extensions
+                .split_whitespace()
+                .any(|extension| extension == extension_name),
+            None => false,
+        }
+    }
+
+    // Opens and initializes an EGL display for the given native display that renders with this
+    // device, via `EGL_EXT_explicit_device`. Returns `None` if EGL can't choose the device that a
+    // native display renders with.
+    pub(crate) unsafe fn open_platform_display(
+        &self,
+        platform: EGLenum,
+        native_display: *mut c_void,
+    ) -> Result<Option<EGLDisplay>, Error> {
+        if !client_extension_supported("EGL_EXT_explicit_device") {
+            return Ok(None);
+        }
+
+        EGL_FUNCTIONS.with(|egl| {
+            let egl_display_attributes = [
+                EGL_DEVICE_EXT as EGLAttrib,
+                self.0 as EGLAttrib,
+                egl::NONE as EGLAttrib,
+            ];
+            let egl_display =
+                egl.GetPlatformDisplay(platform, native_display, egl_display_attributes.as_ptr());
+            if egl_display == egl::NO_DISPLAY {
+                return Err(Error::DeviceOpenFailed);
+            }
+
+            let (mut egl_major_version, mut egl_minor_version) = (0, 0);
+            let ok = egl.Initialize(egl_display, &mut egl_major_version, &mut egl_minor_version);
+            if ok == egl::FALSE {
+                return Err(Error::DeviceOpenFailed);
+            }
+            Ok(Some(egl_display))
+        })
+    }
+}
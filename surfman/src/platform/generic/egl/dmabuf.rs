@@ -0,0 +1,272 @@
+// surfman/surfman/src/platform/generic/egl/dmabuf.rs
+//
+//! Conversion between EGL images and Linux dma-bufs.
+
+use super::device::{display_extension_supported, EGL_FUNCTIONS};
+use super::error::ToWindowingApiError;
+use super::ffi::{EGLImageKHR, EGL_EXTENSION_FUNCTIONS, EGL_NO_IMAGE_KHR};
+use super::ffi::{EGL_DMA_BUF_PLANE0_FD_EXT, EGL_DMA_BUF_PLANE0_MODIFIER_HI_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE0_MODIFIER_LO_EXT, EGL_DMA_BUF_PLANE0_OFFSET_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE0_PITCH_EXT, EGL_DMA_BUF_PLANE1_FD_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE1_MODIFIER_HI_EXT, EGL_DMA_BUF_PLANE1_MODIFIER_LO_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE1_OFFSET_EXT, EGL_DMA_BUF_PLANE1_PITCH_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE2_FD_EXT, EGL_DMA_BUF_PLANE2_MODIFIER_HI_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE2_MODIFIER_LO_EXT, EGL_DMA_BUF_PLANE2_OFFSET_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE2_PITCH_EXT, EGL_DMA_BUF_PLANE3_FD_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE3_MODIFIER_HI_EXT, EGL_DMA_BUF_PLANE3_MODIFIER_LO_EXT};
+use super::ffi::{EGL_DMA_BUF_PLANE3_OFFSET_EXT, EGL_DMA_BUF_PLANE3_PITCH_EXT};
+use super::ffi::{EGL_LINUX_DMA_BUF_EXT, EGL_LINUX_DRM_FOURCC_EXT};
+use crate::egl;
+use crate::egl::types::{EGLDisplay, EGLenum, EGLint};
+use crate::{Error, SurfaceFormat, WindowingApiError};
+
+use euclid::default::Size2D;
+use std::os::unix::io::RawFd;
+use std::ptr;
+
+// The most planes that any DRM format has.
+const MAX_PLANES: usize = 4;
+
+// `DRM_FORMAT_MOD_LINEAR` and `DRM_FORMAT_MOD_INVALID` from `drm_fourcc.h`.
+const DRM_FORMAT_MOD_LINEAR: u64 = 0;
+const DRM_FORMAT_MOD_INVALID: u64 = 0x00ff_ffff_ffff_ffff;
+
+// The DRM fourcc codes that correspond to surface formats, from `drm_fourcc.h`. DRM names formats
+// by the order of the channels in a little-endian word, so the byte order in memory is reversed.
+const DRM_FORMAT_ABGR8888: u32 = 0x3432_4241; // 'AB24'
+const DRM_FORMAT_XBGR8888: u32 = 0x3432_4258; // 'XB24'
+const DRM_FORMAT_ARGB8888: u32 = 0x3432_5241; // 'AR24'
+const DRM_FORMAT_XRGB8888: u32 = 0x3432_5258; // 'XR24'
+const DRM_FORMAT_ABGR2101010: u32 = 0x3033_4241; // 'AB30'
+const DRM_FORMAT_ABGR16161616F: u32 = 0x4834_4241; // 'AB4H'
+
+// The `EGL_LINUX_DMA_BUF_EXT` attributes for each plane: file descriptor, offset, pitch, and the
+// low and high halves of the modifier.
+static PLANE_ATTRIBUTES: [[EGLenum; 5]; MAX_PLANES] = [
+    [
+        EGL_DMA_BUF_PLANE0_FD_EXT,
+        EGL_DMA_BUF_PLANE0_OFFSET_EXT,
+        EGL_DMA_BUF_PLANE0_PITCH_EXT,
+        EGL_DMA_BUF_PLANE0_MODIFIER_LO_EXT,
+        EGL_DMA_BUF_PLANE0_MODIFIER_HI_EXT,
+    ],
+    [
+        EGL_DMA_BUF_PLANE1_FD_EXT,
+        EGL_DMA_BUF_PLANE1_OFFSET_EXT,
+        EGL_DMA_BUF_PLANE1_PITCH_EXT,
+        EGL_DMA_BUF_PLANE1_MODIFIER_LO_EXT,
+        EGL_DMA_BUF_PLANE1_MODIFIER_HI_EXT,
+    ],
+    [
+        EGL_DMA_BUF_PLANE2_FD_EXT,
+        EGL_DMA_BUF_PLANE2_OFFSET_EXT,
+        EGL_DMA_BUF_PLANE2_PITCH_EXT,
+        EGL_DMA_BUF_PLANE2_MODIFIER_LO_EXT,
+        EGL_DMA_BUF_PLANE2_MODIFIER_HI_EXT,
+    ],
+    [
+        EGL_DMA_BUF_PLANE3_FD_EXT,
+        EGL_DMA_BUF_PLANE3_OFFSET_EXT,
+        EGL_DMA_BUF_PLANE3_PITCH_EXT,
+        EGL_DMA_BUF_PLANE3_MODIFIER_LO_EXT,
+        EGL_DMA_BUF_PLANE3_MODIFIER_HI_EXT,
+    ],
+];
+
+/// Describes a dma-buf: a buffer of GPU memory shared via file descriptors.
+///
+/// A dma-buf consists of up to four planes, each of which may live in a different buffer. The
+/// layout of the pixels is described by a DRM fourcc format code and format modifier.
+#[derive(Clone, Debug)]
+pub struct DmaBufDescriptor {
+    /// The size of the buffer in pixels.
+    pub size: Size2D<i32>,
+    /// The DRM fourcc code describing the pixel format (e.g. `DRM_FORMAT_ABGR8888`).
+    pub fourcc: u32,
+    /// The DRM format modifier describing the tiling layout of the planes.
+    pub modifier: u64,
+    /// The planes of the buffer.
+    pub planes: Vec<DmaBufPlane>,
+}
+
+/// One plane of a dma-buf.
+#[derive(Clone, Copy, Debug)]
+pub struct DmaBufPlane {
+    /// The file descriptor of the buffer that this plane lives in.
+    pub fd: RawFd,
+    /// The offset of the plane within the buffer, in bytes.
+    pub offset: u32,
+    /// The number of bytes per row of the plane.
+    pub stride: u32,
+}
+
+// Returns the surface format of a dma-buf with the given DRM fourcc code, or
+// `UnsupportedOnThisPlatform` if no surface format has the same layout.
+pub(crate) fn surface_format(fourcc: u32) -> Result<SurfaceFormat, Error> {
+    match fourcc {
+        DRM_FORMAT_ABGR8888 | DRM_FORMAT_XBGR8888 => Ok(SurfaceFormat::RGBA8),
+        DRM_FORMAT_ARGB8888 | DRM_FORMAT_XRGB8888 => Ok(SurfaceFormat::BGRA8),
+        DRM_FORMAT_ABGR2101010 => Ok(SurfaceFormat::RGB10A2),
+        DRM_FORMAT_ABGR16161616F => Ok(SurfaceFormat::RGBA16F),
+        _ => Err(Error::UnsupportedOnThisPlatform),
+    }
+}
+
+// Exports the buffer behind an EGL image as a dma-buf, via `EGL_MESA_image_dma_buf_export`.
+//
+// The caller owns the returned file descriptors.
+pub(crate) unsafe fn export_egl_image(
+    egl_display: EGLDisplay,
+    egl_image: EGLImageKHR,
+    size: &Size2D<i32>,
+) -> Result<DmaBufDescriptor, Error> {
+    if !display_extension_supported(egl_display, "EGL_MESA_image_dma_buf_export") {
+        return Err(Error::RequiredExtensionUnavailable);
+    }
+    let (export_query, export) = match (
+        EGL_EXTENSION_FUNCTIONS.ExportDMABUFImageQueryMESA,
+        EGL_EXTENSION_FUNCTIONS.ExportDMABUFImageMESA,
+    ) {
+        (Some(export_query), Some(export)) => (export_query, export),
+        _ => return Err(Error::RequiredExtensionUnavailable),
+    };
+
+    // Query the plane count first, since EGL writes one modifier, file descriptor, stride, and
+    // offset per plane.
+    let (mut fourcc, mut plane_count) = (0, 0);
+    let ok = export_query(
+        egl_display,
+        egl_image,
+        &mut fourcc,
+        &mut plane_count,
+        ptr::null_mut(),
+    );
+    if ok == egl::FALSE {
+        let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+        return Err(Error::SurfaceExportFailed(
+            windowing_api_error.to_native_error("eglExportDMABUFImageQueryMESA"),
+        ));
+    }
+    if plane_count < 1 || plane_count as usize > MAX_PLANES {
+        return Err(Error::SurfaceExportFailed(
+            WindowingApiError::BadMatch.into(),
+        ));
+    }
+
+    let mut modifiers = [0; MAX_PLANES];
+    let ok = export_query(
+        egl_display,
+        egl_image,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        modifiers.as_mut_ptr(),
+    );
+    if ok == egl::FALSE {
+        let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+        return Err(Error::SurfaceExportFailed(
+            windowing_api_error.to_native_error("eglExportDMABUFImageQueryMESA"),
+        ));
+    }
+
+    let mut fds = [-1; MAX_PLANES];
+    let mut strides: [EGLint; MAX_PLANES] = [0; MAX_PLANES];
+    let mut offsets: [EGLint; MAX_PLANES] = [0; MAX_PLANES];
+    let ok = export(
+        egl_display,
+        egl_image,
+        fds.as_mut_ptr(),
+        strides.as_mut_ptr(),
+        offsets.as_mut_ptr(),
+    );
+    if ok == egl::FALSE {
+        let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+        return Err(Error::SurfaceExportFailed(
+            windowing_api_error.to_native_error("eglExportDMABUFImageMESA"),
+        ));
+    }
+
+    let planes = (0..plane_count as usize)
+        .map(|plane_index| DmaBufPlane {
+            fd: fds[plane_index],
+            offset: offsets[plane_index] as u32,
+            stride: strides[plane_index] as u32,
+        })
+        .collect();
+    Ok(DmaBufDescriptor {
+        size: *size,
+        fourcc: fourcc as u32,
+        modifier: modifiers[0],
+        planes,
+    })
+}
+
+// Wraps a dma-buf in an EGL image, via `EGL_EXT_image_dma_buf_import`.
+//
+// EGL doesn't take ownership of the file descriptors; the caller may close them once this returns.
+pub(crate) unsafe fn create_egl_image(
+    egl_display: EGLDisplay,
+    descriptor: &DmaBufDescriptor,
+) -> Result<EGLImageKHR, Error> {
+    if !display_extension_supported(egl_display, "EGL_EXT_image_dma_buf_import") {
+        return Err(Error::RequiredExtensionUnavailable);
+    }
+    if descriptor.planes.is_empty() || descriptor.planes.len() > MAX_PLANES {
+        return Err(Error::SurfaceImportFailed(
+            WindowingApiError::BadParameter.into(),
+        ));
+    }
+
+    // Explicit modifiers need another extension. Without it, drivers assume a linear or
+    // implicitly-tiled layout.
+    let modifiers_supported =
+        display_extension_supported(egl_display, "EGL_EXT_image_dma_buf_import_modifiers");
+    let explicit_modifier = match descriptor.modifier {
+        DRM_FORMAT_MOD_INVALID => false,
+        DRM_FORMAT_MOD_LINEAR => modifiers_supported,
+        _ if modifiers_supported => true,
+        _ => return Err(Error::RequiredExtensionUnavailable),
+    };
+
+    let mut egl_image_attribs = vec![
+        egl::WIDTH as EGLint,
+        descriptor.size.width,
+        egl::HEIGHT as EGLint,
+        descriptor.size.height,
+        EGL_LINUX_DRM_FOURCC_EXT as EGLint,
+        descriptor.fourcc as EGLint,
+    ];
+    for (plane, attributes) in descriptor.planes.iter().zip(PLANE_ATTRIBUTES.iter()) {
+        egl_image_attribs.extend_from_slice(&[
+            attributes[0] as EGLint,
+            plane.fd,
+            attributes[1] as EGLint,
+            plane.offset as EGLint,
+            attributes[2] as EGLint,
+            plane.stride as EGLint,
+        ]);
+        if explicit_modifier {
+            egl_image_attribs.extend_from_slice(&[
+                attributes[3] as EGLint,
+                descriptor.modifier as u32 as EGLint,
+                attributes[4] as EGLint,
+                (descriptor.modifier >> 32) as u32 as EGLint,
+            ]);
+        }
+    }
+    egl_image_attribs.push(egl::NONE as EGLint);
+
+    let egl_image = (EGL_EXTENSION_FUNCTIONS.CreateImageKHR)(
+        egl_display,
+        egl::NO_CONTEXT,
+        EGL_LINUX_DMA_BUF_EXT,
+        ptr::null_mut(),
+        egl_image_attribs.as_ptr(),
+    );
+    if egl_image == EGL_NO_IMAGE_KHR {
+        let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+        return Err(Error::SurfaceImportFailed(
+            windowing_api_error.to_native_error("eglCreateImageKHR"),
+        ));
+    }
+    Ok(egl_image)
+}
@@ -4,10 +4,14 @@
 
 use crate::egl;
 use crate::egl::types::{EGLenum, EGLint};
-use crate::WindowingApiError;
+use crate::{NativeError, NativeErrorCode, WindowingApiError};
 
 pub(crate) trait ToWindowingApiError {
     fn to_windowing_api_error(self) -> WindowingApiError;
+
+    /// Keeps the raw error code alongside the translated error, along with the name of the EGL
+    /// function that failed.
+    fn to_native_error(self, context: &'static str) -> NativeError;
 }
 
 impl ToWindowingApiError for EGLint {
@@ -30,4 +34,12 @@ impl ToWindowingApiError for EGLint {
             _ => WindowingApiError::Failed,
         }
     }
+
+    fn to_native_error(self, context: &'static str) -> NativeError {
+        NativeError::new(
+            self.to_windowing_api_error(),
+            NativeErrorCode::EGL(self),
+            context,
+        )
+    }
 }
@@ -0,0 +1,304 @@
+// surfman/surfman/src/platform/generic/egl/fence.rs
+//
+//! GPU fences common to backends using EGL.
+
+use super::device::{display_extension_supported, EGL_FUNCTIONS};
+use super::error::ToWindowingApiError;
+use super::ffi::{EGL_EXTENSION_FUNCTIONS, EGL_NO_NATIVE_FENCE_FD_ANDROID};
+use super::ffi::{EGL_SYNC_NATIVE_FENCE_ANDROID, EGL_SYNC_NATIVE_FENCE_FD_ANDROID};
+use crate::egl;
+use crate::egl::types::{EGLDisplay, EGLSyncKHR, EGLenum, EGLint};
+use crate::gl;
+use crate::gl::types::GLsync;
+use crate::leaks::{self, TrackedObject};
+use crate::{ContextID, Error, Gl, NativeError, NativeErrorCode, WindowingApiError};
+
+use std::fmt::{self, Debug, Formatter};
+#[cfg(unix)]
+use std::os::unix::io::RawFd;
+
+pub(crate) struct EGLFence {
+    pub(crate) object: EGLFenceObject,
+    destroyed: bool,
+}
+
+pub(crate) enum EGLFenceObject {
+    // A fence from `EGL_KHR_fence_sync` or, if `native` is set, `EGL_ANDROID_native_fence_sync`.
+    // These can be waited on from any context on the same display.
+    Sync {
+        egl_sync: EGLSyncKHR,
+        native: bool,
+    },
+    // A fence from `glFenceSync`. These can only be waited on from contexts that share objects
+    // with the context that created them. GL can't tell us whether a sync object name belongs to
+    // the current share group, so keep track of it ourselves.
+    GL {
+        gl_sync: GLsync,
+        share_group: ContextID,
+    },
+}
+
+unsafe impl Send for EGLFence {}
+unsafe impl Sync for EGLFence {}
+
+impl Debug for EGLFence {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        write!(f, "Fence({:x})", self.id())
+    }
+}
+
+impl Drop for EGLFence {
+    fn drop(&mut self) {
+        if !self.destroyed {
+            leaks::dropped_without_destroying_no_panic(TrackedObject::Fence(self.id()));
+        }
+    }
+}
+
+impl EGLFence {
+    // Inserts a fence into the command stream of the current context and flushes it, so that
+    // other contexts can wait for the commands issued so far. `share_group` is the share group of
+    // the current context.
+    pub(crate) unsafe fn new(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        share_group: ContextID,
+    ) -> Result<EGLFence, Error> {
+        let object = match create_egl_sync(egl_display)? {
+            Some(object) => object,
+            None => {
+                let gl_sync = gl.FenceSync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0);
+                if gl_sync.is_null() {
+                    return Err(Error::FenceCreationFailed(NativeError::new(
+                        WindowingApiError::Failed,
+                        NativeErrorCode::GL(gl.GetError()),
+                        "glFenceSync",
+                    )));
+                }
+                EGLFenceObject::GL {
+                    gl_sync,
+                    share_group,
+                }
+            }
+        };
+
+        gl.Flush();
+        let fence = EGLFence {
+            object,
+            destroyed: false,
+        };
+        leaks::track(TrackedObject::Fence(fence.id()));
+        Ok(fence)
+    }
+
+    // Makes the current context, whose share group is `share_group`, wait on the GPU for the
+    // fence to signal, without blocking the CPU.
+    //
+    // EGL fences need `EGL_KHR_wait_sync` for this; without it, this returns an
+    // `UnsupportedOnThisPlatform` error rather than blocking the CPU until the fence signals.
+    pub(crate) unsafe fn wait(
+        &self,
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        share_group: ContextID,
+    ) -> Result<(), Error> {
+        match self.object {
+            EGLFenceObject::Sync { egl_sync, .. } => {
+                let wait_sync = match EGL_EXTENSION_FUNCTIONS.WaitSyncKHR {
+                    Some(wait_sync)
+                        if display_extension_supported(egl_display, "EGL_KHR_wait_sync") =>
+                    {
+                        wait_sync
+                    }
+                    _ => return Err(Error::UnsupportedOnThisPlatform),
+                };
+                if wait_sync(egl_display, egl_sync, 0) != egl::FALSE as EGLint {
+                    Ok(())
+                } else {
+                    let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+                    Err(Error::FenceWaitFailed(
+                        windowing_api_error.to_native_error("eglWaitSyncKHR"),
+                    ))
+                }
+            }
+            EGLFenceObject::GL {
+                gl_sync,
+                share_group: fence_share_group,
+            } => {
+                // The fence is invisible to contexts outside the share group that created it.
+                if fence_share_group != share_group {
+                    return Err(Error::IncompatibleContext);
+                }
+                gl.WaitSync(gl_sync, 0, gl::TIMEOUT_IGNORED);
+                Ok(())
+            }
+        }
+    }
+
+    // Wraps a sync_file in a native fence, via `EGL_ANDROID_native_fence_sync`. On success, EGL
+    // takes ownership of the file descriptor.
+    #[cfg(unix)]
+    pub(crate) unsafe fn from_native_fence_fd(
+        egl_display: EGLDisplay,
+        fd: RawFd,
+    ) -> Result<EGLFence, Error> {
+        let create_sync = native_fence_create_sync(egl_display)?;
+        let attributes = [
+            EGL_SYNC_NATIVE_FENCE_FD_ANDROID as EGLint,
+            fd,
+            egl::NONE as EGLint,
+        ];
+        let egl_sync = create_sync(
+            egl_display,
+            EGL_SYNC_NATIVE_FENCE_ANDROID,
+            attributes.as_ptr(),
+        );
+        if egl_sync == egl::NO_SYNC {
+            let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+            return Err(Error::FenceCreationFailed(
+                windowing_api_error.to_native_error("eglCreateSyncKHR"),
+            ));
+        }
+        let fence = EGLFence {
+            object: EGLFenceObject::Sync {
+                egl_sync,
+                native: true,
+            },
+            destroyed: false,
+        };
+        leaks::track(TrackedObject::Fence(fence.id()));
+        Ok(fence)
+    }
+
+    // Inserts a native fence into the command stream of the current context, flushes it, and
+    // returns a sync_file that signals when the commands issued so far complete. The caller owns
+    // the returned file descriptor.
+    #[cfg(unix)]
+    pub(crate) unsafe fn export_native_fence_fd(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+    ) -> Result<RawFd, Error> {
+        let create_sync = native_fence_create_sync(egl_display)?;
+        let dup_native_fence_fd = match EGL_EXTENSION_FUNCTIONS.DupNativeFenceFDANDROID {
+            Some(dup_native_fence_fd) => dup_native_fence_fd,
+            None => return Err(Error::RequiredExtensionUnavailable),
+        };
+
+        let attributes = [egl::NONE as EGLint];
+        let egl_sync = create_sync(
+            egl_display,
+            EGL_SYNC_NATIVE_FENCE_ANDROID,
+            attributes.as_ptr(),
+        );
+        if egl_sync == egl::NO_SYNC {
+            let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+            return Err(Error::FenceCreationFailed(
+                windowing_api_error.to_native_error("eglCreateSyncKHR"),
+            ));
+        }
+
+        // The fence only gets a file descriptor once it's been flushed.
+        gl.Flush();
+        let fd = dup_native_fence_fd(egl_display, egl_sync);
+        let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+
+        let destroy_sync = EGL_EXTENSION_FUNCTIONS.DestroySyncKHR.unwrap();
+        destroy_sync(egl_display, egl_sync);
+
+        if fd == EGL_NO_NATIVE_FENCE_FD_ANDROID {
+            return Err(Error::FenceCreationFailed(
+                windowing_api_error.to_native_error("eglDupNativeFenceFDANDROID"),
+            ));
+        }
+        Ok(fd)
+    }
+
+    // Destroys the fence with the current context, whose share group is `share_group`. Fences
+    // from `glFenceSync` can only be destroyed from their own share group; otherwise, this
+    // returns an `IncompatibleContext` error and leaves the fence alone.
+    pub(crate) unsafe fn destroy(
+        &mut self,
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        share_group: ContextID,
+    ) -> Result<(), Error> {
+        match self.object {
+            EGLFenceObject::Sync { egl_sync, .. } => {
+                let destroy_sync = EGL_EXTENSION_FUNCTIONS.DestroySyncKHR.unwrap();
+                let ok = destroy_sync(egl_display, egl_sync);
+                debug_assert_ne!(ok, egl::FALSE);
+            }
+            EGLFenceObject::GL {
+                gl_sync,
+                share_group: fence_share_group,
+            } => {
+                if fence_share_group != share_group {
+                    return Err(Error::IncompatibleContext);
+                }
+                gl.DeleteSync(gl_sync)
+            }
+        }
+        leaks::untrack(TrackedObject::Fence(self.id()));
+        self.destroyed = true;
+        Ok(())
+    }
+
+    fn id(&self) -> usize {
+        match self.object {
+            EGLFenceObject::Sync { egl_sync, .. } => egl_sync as usize,
+            EGLFenceObject::GL { gl_sync, .. } => gl_sync as usize,
+        }
+    }
+}
+
+// Creates an EGL fence in the current context, preferring native fences. Returns `None` if EGL
+// can't create fences on this display or can't wait for them on the GPU.
+unsafe fn create_egl_sync(egl_display: EGLDisplay) -> Result<Option<EGLFenceObject>, Error> {
+    let create_sync = match (
+        EGL_EXTENSION_FUNCTIONS.CreateSyncKHR,
+        EGL_EXTENSION_FUNCTIONS.DestroySyncKHR,
+        EGL_EXTENSION_FUNCTIONS.WaitSyncKHR,
+    ) {
+        (Some(create_sync), Some(_), Some(_))
+            if display_extension_supported(egl_display, "EGL_KHR_wait_sync") =>
+        {
+            create_sync
+        }
+        _ => return Ok(None),
+    };
+
+    let (sync_type, native): (EGLenum, bool) =
+        if display_extension_supported(egl_display, "EGL_ANDROID_native_fence_sync") {
+            (EGL_SYNC_NATIVE_FENCE_ANDROID, true)
+        } else if display_extension_supported(egl_display, "EGL_KHR_fence_sync") {
+            (egl::SYNC_FENCE, false)
+        } else {
+            return Ok(None);
+        };
+
+    let attributes = [egl::NONE as EGLint];
+    let egl_sync = create_sync(egl_display, sync_type, attributes.as_ptr());
+    if egl_sync == egl::NO_SYNC {
+        let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+        return Err(Error::FenceCreationFailed(
+            windowing_api_error.to_native_error("eglCreateSyncKHR"),
+        ));
+    }
+    Ok(Some(EGLFenceObject::Sync { egl_sync, native }))
+}
+
+#[cfg(unix)]
+unsafe fn native_fence_create_sync(
+    egl_display: EGLDisplay,
+) -> Result<extern "C" fn(EGLDisplay, EGLenum, *const EGLint) -> EGLSyncKHR, Error> {
+    if !display_extension_supported(egl_display, "EGL_ANDROID_native_fence_sync") {
+        return Err(Error::RequiredExtensionUnavailable);
+    }
+    match (
+        EGL_EXTENSION_FUNCTIONS.CreateSyncKHR,
+        EGL_EXTENSION_FUNCTIONS.DestroySyncKHR,
+    ) {
+        (Some(create_sync), Some(_)) => Ok(create_sync),
+        _ => Err(Error::RequiredExtensionUnavailable),
+    }
+}
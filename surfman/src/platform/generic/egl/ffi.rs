@@ -5,9 +5,9 @@
 #![allow(dead_code)]
 
 use crate::egl::types::{EGLAttrib, EGLBoolean, EGLContext, EGLDeviceEXT, EGLDisplay, EGLSurface};
-use crate::egl::types::{EGLenum, EGLint};
+use crate::egl::types::{EGLSyncKHR, EGLenum, EGLint, EGLuint64KHR};
 
-use std::os::raw::c_void;
+use std::os::raw::{c_char, c_int, c_void};
 
 pub enum EGLClientBufferOpaque {}
 pub type EGLClientBuffer = *mut EGLClientBufferOpaque;
@@ -16,26 +16,64 @@ pub enum EGLImageKHROpaque {}
 pub type EGLImageKHR = *mut EGLImageKHROpaque;
 
 pub const EGL_GL_TEXTURE_2D_KHR: EGLenum = 0x30b1;
+pub const EGL_CONTEXT_OPENGL_ROBUST_ACCESS_EXT: EGLenum = 0x30bf;
 pub const EGL_IMAGE_PRESERVED_KHR: EGLenum = 0x30d2;
 pub const EGL_CONTEXT_MINOR_VERSION_KHR: EGLenum = 0x30fb;
+pub const EGL_CONTEXT_FLAGS_KHR: EGLenum = 0x30fc;
 pub const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLenum = 0x30fd;
+pub const EGL_CONTEXT_PRIORITY_LEVEL_IMG: EGLenum = 0x3100;
+pub const EGL_CONTEXT_PRIORITY_HIGH_IMG: EGLenum = 0x3101;
+pub const EGL_CONTEXT_PRIORITY_MEDIUM_IMG: EGLenum = 0x3102;
+pub const EGL_CONTEXT_PRIORITY_LOW_IMG: EGLenum = 0x3103;
+pub const EGL_CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY_EXT: EGLenum = 0x3138;
 pub const EGL_PLATFORM_DEVICE_EXT: EGLenum = 0x313f;
 pub const EGL_NATIVE_BUFFER_ANDROID: EGLenum = 0x3140;
+pub const EGL_SYNC_NATIVE_FENCE_ANDROID: EGLenum = 0x3144;
+pub const EGL_SYNC_NATIVE_FENCE_FD_ANDROID: EGLenum = 0x3145;
+pub const EGL_CONTEXT_OPENGL_NO_ERROR_KHR: EGLenum = 0x31b3;
+pub const EGL_CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY_KHR: EGLenum = 0x31bd;
 pub const EGL_PLATFORM_X11_KHR: EGLenum = 0x31d5;
 pub const EGL_PLATFORM_WAYLAND_KHR: EGLenum = 0x31d8;
 pub const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31dd;
 pub const EGL_D3D_TEXTURE_2D_SHARE_HANDLE_ANGLE: EGLenum = 0x3200;
 pub const EGL_BAD_DEVICE_EXT: EGLenum = 0x322b;
 pub const EGL_DEVICE_EXT: EGLenum = 0x322c;
+pub const EGL_LINUX_DMA_BUF_EXT: EGLenum = 0x3270;
+pub const EGL_LINUX_DRM_FOURCC_EXT: EGLenum = 0x3271;
+pub const EGL_DMA_BUF_PLANE0_FD_EXT: EGLenum = 0x3272;
+pub const EGL_DMA_BUF_PLANE0_OFFSET_EXT: EGLenum = 0x3273;
+pub const EGL_DMA_BUF_PLANE0_PITCH_EXT: EGLenum = 0x3274;
+pub const EGL_DMA_BUF_PLANE1_FD_EXT: EGLenum = 0x3275;
+pub const EGL_DMA_BUF_PLANE1_OFFSET_EXT: EGLenum = 0x3276;
+pub const EGL_DMA_BUF_PLANE1_PITCH_EXT: EGLenum = 0x3277;
+pub const EGL_DMA_BUF_PLANE2_FD_EXT: EGLenum = 0x3278;
+pub const EGL_DMA_BUF_PLANE2_OFFSET_EXT: EGLenum = 0x3279;
+pub const EGL_DMA_BUF_PLANE2_PITCH_EXT: EGLenum = 0x327a;
+pub const EGL_RENDERER_EXT: EGLenum = 0x335f;
+pub const EGL_DRM_RENDER_NODE_FILE_EXT: EGLenum = 0x3377;
 pub const EGL_D3D11_DEVICE_ANGLE: EGLenum = 0x33a1;
 pub const EGL_DXGI_KEYED_MUTEX_ANGLE: EGLenum = 0x33a2;
 pub const EGL_D3D_TEXTURE_ANGLE: EGLenum = 0x33a3;
+pub const EGL_DMA_BUF_PLANE3_FD_EXT: EGLenum = 0x3440;
+pub const EGL_DMA_BUF_PLANE3_OFFSET_EXT: EGLenum = 0x3441;
+pub const EGL_DMA_BUF_PLANE3_PITCH_EXT: EGLenum = 0x3442;
+pub const EGL_DMA_BUF_PLANE0_MODIFIER_LO_EXT: EGLenum = 0x3443;
+pub const EGL_DMA_BUF_PLANE0_MODIFIER_HI_EXT: EGLenum = 0x3444;
+pub const EGL_DMA_BUF_PLANE1_MODIFIER_LO_EXT: EGLenum = 0x3445;
+pub const EGL_DMA_BUF_PLANE1_MODIFIER_HI_EXT: EGLenum = 0x3446;
+pub const EGL_DMA_BUF_PLANE2_MODIFIER_LO_EXT: EGLenum = 0x3447;
+pub const EGL_DMA_BUF_PLANE2_MODIFIER_HI_EXT: EGLenum = 0x3448;
+pub const EGL_DMA_BUF_PLANE3_MODIFIER_LO_EXT: EGLenum = 0x3449;
+pub const EGL_DMA_BUF_PLANE3_MODIFIER_HI_EXT: EGLenum = 0x344a;
 
 pub const EGL_NO_DEVICE_EXT: EGLDeviceEXT = 0 as EGLDeviceEXT;
 pub const EGL_NO_IMAGE_KHR: EGLImageKHR = 0 as EGLImageKHR;
 
 pub const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 1;
+pub const EGL_CONTEXT_OPENGL_DEBUG_BIT_KHR: EGLint = 1;
 pub const EGL_CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT: EGLint = 2;
+pub const EGL_CONTEXT_OPENGL_ROBUST_ACCESS_BIT_KHR: EGLint = 4;
+pub const EGL_NO_NATIVE_FENCE_FD_ANDROID: EGLint = -1;
 
 #[allow(non_snake_case)]
 pub(crate) struct EGLExtensionFunctions {
@@ -58,8 +96,42 @@ pub(crate) struct EGLExtensionFunctions {
             attrib_list: *const EGLAttrib,
         ) -> EGLDeviceEXT,
     >,
+    pub(crate) CreateSyncKHR: Option<
+        extern "C" fn(dpy: EGLDisplay, type_: EGLenum, attrib_list: *const EGLint) -> EGLSyncKHR,
+    >,
+    pub(crate) DestroySyncKHR:
+        Option<extern "C" fn(dpy: EGLDisplay, sync: EGLSyncKHR) -> EGLBoolean>,
+    pub(crate) DupNativeFenceFDANDROID:
+        Option<extern "C" fn(dpy: EGLDisplay, sync: EGLSyncKHR) -> EGLint>,
+    pub(crate) ExportDMABUFImageMESA: Option<
+        extern "C" fn(
+            dpy: EGLDisplay,
+            image: EGLImageKHR,
+            fds: *mut c_int,
+            strides: *mut EGLint,
+            offsets: *mut EGLint,
+        ) -> EGLBoolean,
+    >,
+    pub(crate) ExportDMABUFImageQueryMESA: Option<
+        extern "C" fn(
+            dpy: EGLDisplay,
+            image: EGLImageKHR,
+            fourcc: *mut c_int,
+            num_planes: *mut c_int,
+            modifiers: *mut EGLuint64KHR,
+        ) -> EGLBoolean,
+    >,
     pub(crate) GetNativeClientBufferANDROID:
         Option<extern "C" fn(buffer: *const c_void) -> EGLClientBuffer>,
+    pub(crate) QueryDevicesEXT: Option<
+        extern "C" fn(
+            max_devices: EGLint,
+            devices: *mut EGLDeviceEXT,
+            num_devices: *mut EGLint,
+        ) -> EGLBoolean,
+    >,
+    pub(crate) QueryDeviceStringEXT:
+        Option<extern "C" fn(device: EGLDeviceEXT, name: EGLint) -> *const c_char>,
     pub(crate) QueryDeviceAttribEXT: Option<
         extern "C" fn(device: EGLDeviceEXT, attribute: EGLint, value: *mut EGLAttrib) -> EGLBoolean,
     >,
@@ -74,6 +146,8 @@ pub(crate) struct EGLExtensionFunctions {
             value: *mut *mut c_void,
         ) -> EGLBoolean,
     >,
+    pub(crate) WaitSyncKHR:
+        Option<extern "C" fn(dpy: EGLDisplay, sync: EGLSyncKHR, flags: EGLint) -> EGLint>,
 }
 
 lazy_static! {
@@ -87,10 +161,18 @@ lazy_static! {
                 ImageTargetTexture2DOES: cast(get(b"glEGLImageTargetTexture2DOES\0")),
 
                 CreateDeviceANGLE: cast(get(b"eglCreateDeviceANGLE\0")),
+                CreateSyncKHR: cast(get(b"eglCreateSyncKHR\0")),
+                DestroySyncKHR: cast(get(b"eglDestroySyncKHR\0")),
+                DupNativeFenceFDANDROID: cast(get(b"eglDupNativeFenceFDANDROID\0")),
+                ExportDMABUFImageMESA: cast(get(b"eglExportDMABUFImageMESA\0")),
+                ExportDMABUFImageQueryMESA: cast(get(b"eglExportDMABUFImageQueryMESA\0")),
                 GetNativeClientBufferANDROID: cast(get(b"eglGetNativeClientBufferANDROID\0")),
+                QueryDevicesEXT: cast(get(b"eglQueryDevicesEXT\0")),
+                QueryDeviceStringEXT: cast(get(b"eglQueryDeviceStringEXT\0")),
                 QueryDeviceAttribEXT: cast(get(b"eglQueryDeviceAttribEXT\0")),
                 QueryDisplayAttribEXT: cast(get(b"eglQueryDisplayAttribEXT\0")),
                 QuerySurfacePointerANGLE: cast(get(b"eglQuerySurfacePointerANGLE\0")),
+                WaitSyncKHR: cast(get(b"eglWaitSyncKHR\0")),
             }
         }
     };
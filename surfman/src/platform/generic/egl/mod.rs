@@ -4,8 +4,12 @@
 
 #![allow(dead_code)]
 
+pub(crate) mod capabilities;
 pub(crate) mod context;
 pub(crate) mod device;
+#[cfg(linux)]
+pub(crate) mod dmabuf;
 pub(crate) mod error;
+pub(crate) mod fence;
 pub(crate) mod ffi;
 pub(crate) mod surface;
@@ -4,11 +4,14 @@
 
 use super::context::CurrentContextGuard;
 use super::device::EGL_FUNCTIONS;
+#[cfg(linux)]
+use super::dmabuf::{self, DmaBufDescriptor};
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLConfig, EGLContext, EGLDisplay, EGLSurface, EGLint};
 use crate::gl;
-use crate::gl::types::{GLint, GLuint};
+use crate::gl::types::{GLbitfield, GLenum, GLint, GLsizei, GLsizeiptr, GLuint};
 use crate::gl_utils;
+use crate::leaks::{self, TrackedObject};
 use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::ffi::EGLClientBuffer;
 use crate::platform::generic::egl::ffi::EGLImageKHR;
@@ -16,9 +19,11 @@ use crate::platform::generic::egl::ffi::EGL_EXTENSION_FUNCTIONS;
 use crate::platform::generic::egl::ffi::EGL_GL_TEXTURE_2D_KHR;
 use crate::platform::generic::egl::ffi::EGL_IMAGE_PRESERVED_KHR;
 use crate::platform::generic::egl::ffi::EGL_NO_IMAGE_KHR;
-use crate::renderbuffers::Renderbuffers;
+use crate::renderbuffers::{self, Renderbuffers};
 use crate::Gl;
-use crate::{ContextAttributes, ContextID, Error, SurfaceID, SurfaceInfo};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextID, Error, GLApi, SurfaceAccess};
+use crate::{NativeError, NativeErrorCode, WindowingApiError};
+use crate::{SurfaceFormat, SurfaceID, SurfaceInfo};
 
 use euclid::default::Size2D;
 use std::fmt::{self, Debug, Formatter};
@@ -26,6 +31,11 @@ use std::marker::PhantomData;
 use std::mem;
 use std::os::raw::c_void;
 use std::ptr;
+use std::slice;
+
+// `GL_BGRA_EXT` from `GL_EXT_texture_format_BGRA8888`. This has the same value as `GL_BGRA` on
+// desktop OpenGL.
+const GL_BGRA_EXT: GLenum = 0x80e1;
 
 #[allow(dead_code)]
 #[derive(Clone)]
@@ -37,7 +47,10 @@ pub(crate) struct ExternalEGLSurfaces {
 pub struct EGLBackedSurface {
     pub(crate) context_id: ContextID,
     pub(crate) size: Size2D<i32>,
-    pub(crate) objects: EGLSurfaceObjects,
+    pub(crate) access: SurfaceAccess,
+    pub(crate) texture_format: TextureFormat,
+    // Boxed to keep the errors of methods that hand surfaces back alongside them small.
+    pub(crate) objects: Box<EGLSurfaceObjects>,
     pub(crate) destroyed: bool,
 }
 
@@ -49,6 +62,14 @@ impl Debug for EGLBackedSurface {
 
 unsafe impl Send for EGLBackedSurface {}
 
+impl Drop for EGLBackedSurface {
+    fn drop(&mut self) {
+        if !self.destroyed {
+            leaks::dropped_without_destroying_no_panic(TrackedObject::Surface(self.id()));
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub(crate) enum EGLSurfaceObjects {
     TextureImage {
@@ -56,6 +77,10 @@ pub(crate) enum EGLSurfaceObjects {
         framebuffer_object: GLuint,
         texture_object: GLuint,
         renderbuffers: Renderbuffers,
+        multisample: Option<MultisampleBuffers>,
+        depth_texture: Option<Box<DepthTexture>>,
+        // Whether the image wraps a dma-buf imported from elsewhere, whose storage we don't own.
+        imported: bool,
     },
     Window {
         native_window: *const c_void,
@@ -63,12 +88,62 @@ pub(crate) enum EGLSurfaceObjects {
     },
 }
 
+// The multisampled color buffer of a multisampled generic surface. The surface's framebuffer
+// renders to it, and it gets resolved into the surface's texture through a second framebuffer.
+pub(crate) struct MultisampleBuffers {
+    color_renderbuffer: GLuint,
+    resolve_framebuffer_object: GLuint,
+    samples: GLsizei,
+    // Whether the depth buffer is resolved too, into the surface's depth texture.
+    resolve_depth: bool,
+}
+
+// The depth buffer of a generic surface whose context asked for sampleable depth. It's attached to
+// the surface's framebuffer, or to its resolve framebuffer if the surface is multisampled, and
+// shared with other contexts through an EGL image.
+pub(crate) struct DepthTexture {
+    texture_object: GLuint,
+    egl_image: EGLImageKHR,
+    attachment: GLenum,
+}
+
+// How the color buffer of a surface is stored, in OpenGL terms.
+#[derive(Clone, Copy)]
+pub(crate) struct TextureFormat {
+    surface_format: SurfaceFormat,
+    // The internal format, format, and type that the texture is allocated with.
+    internal_format: GLenum,
+    format: GLenum,
+    type_: GLenum,
+    // The sized internal format of the multisampled color renderbuffer, or 0 if surfaces of this
+    // format can't be multisampled.
+    renderbuffer_format: GLenum,
+    bytes_per_pixel: i32,
+}
+
 pub(crate) struct EGLSurfaceTexture {
     pub(crate) surface: EGLBackedSurface,
     pub(crate) texture_object: GLuint,
+    pub(crate) depth_texture_object: GLuint,
     pub(crate) phantom: PhantomData<*const ()>,
 }
 
+// The CPU view of a generic surface, read back into a mapped pixel buffer object.
+//
+// The pixel buffer object and the temporary texture and framebuffer aliasing the surface's EGL
+// image live in the context that was current when the surface was locked.
+pub(crate) struct EGLSurfaceDataGuard<'a> {
+    surface: &'a mut EGLBackedSurface,
+    egl_display: EGLDisplay,
+    egl_context: EGLContext,
+    texture_object: GLuint,
+    framebuffer_object: GLuint,
+    pixel_buffer_object: GLuint,
+    stride: usize,
+    ptr: *mut u8,
+    len: usize,
+}
+
 impl Debug for EGLSurfaceTexture {
     fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
         write!(f, "SurfaceTexture({:?})", self.surface)
@@ -76,88 +151,105 @@ impl Debug for EGLSurfaceTexture {
 }
 
 impl EGLBackedSurface {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new_generic(
         gl: &Gl,
         egl_display: EGLDisplay,
         egl_context: EGLContext,
         context_id: ContextID,
         context_attributes: &ContextAttributes,
+        gl_api: GLApi,
         size: &Size2D<i32>,
-    ) -> EGLBackedSurface {
-        let egl_image_attribs = [
-            EGL_IMAGE_PRESERVED_KHR as EGLint,
-            egl::FALSE as EGLint,
-            egl::NONE as EGLint,
-            0,
-        ];
-
+        access: SurfaceAccess,
+        format: SurfaceFormat,
+    ) -> Result<EGLBackedSurface, Error> {
+        let texture_format = TextureFormat::new(format, gl_api);
         unsafe {
-            // Create our texture.
-            let mut texture_object = 0;
-            gl.GenTextures(1, &mut texture_object);
-            // Save the current texture binding
-            let mut old_texture_object = 0;
-            gl.GetIntegerv(gl::TEXTURE_BINDING_2D, &mut old_texture_object);
-            gl.BindTexture(gl::TEXTURE_2D, texture_object);
-            // Unbind PIXEL_UNPACK_BUFFER, because if it is bound,
-            // it can cause errors in glTexImage2D.
-            // TODO: should this be inside a check for GL 2.0?
-            let mut unpack_buffer = 0;
-            gl.GetIntegerv(gl::PIXEL_UNPACK_BUFFER_BINDING, &mut unpack_buffer);
-            if unpack_buffer != 0 {
-                gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+            // Flush out any stale errors so that we can tell whether the driver took the format.
+            while gl.GetError() != gl::NO_ERROR {}
+
+            // Create our texture and image.
+            let texture_object = create_texture(gl, &texture_format, size);
+            match gl.GetError() {
+                gl::NO_ERROR => {}
+                gl::OUT_OF_MEMORY => {
+                    gl.DeleteTextures(1, &texture_object);
+                    return Err(Error::SurfaceCreationFailed(NativeError::new(
+                        WindowingApiError::BadAlloc,
+                        NativeErrorCode::GL(gl::OUT_OF_MEMORY),
+                        "glTexImage2D",
+                    )));
+                }
+                gl_error => {
+                    gl.DeleteTextures(1, &texture_object);
+                    return Err(texture_format.unsupported_or(Error::SurfaceCreationFailed(
+                        NativeError::new(
+                            WindowingApiError::BadParameter,
+                            NativeErrorCode::GL(gl_error),
+                            "glTexImage2D",
+                        ),
+                    )));
+                }
             }
-            gl.TexImage2D(
-                gl::TEXTURE_2D,
-                0,
-                gl::RGBA as GLint,
-                size.width,
-                size.height,
-                0,
-                gl::RGBA,
-                gl::UNSIGNED_BYTE,
-                ptr::null(),
-            );
-            // Restore the old bindings
-            gl.BindTexture(gl::TEXTURE_2D, old_texture_object as _);
-            if unpack_buffer != 0 {
-                gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, unpack_buffer as _);
+            let egl_image = create_egl_image_from_texture(egl_display, egl_context, texture_object);
+            if egl_image == EGL_NO_IMAGE_KHR {
+                let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+                gl.DeleteTextures(1, &texture_object);
+                return Err(texture_format.unsupported_or(Error::SurfaceCreationFailed(
+                    windowing_api_error.to_native_error("eglCreateImageKHR"),
+                )));
             }
 
-            // Create our image.
-            let egl_client_buffer = texture_object as usize as EGLClientBuffer;
-            let egl_image = (EGL_EXTENSION_FUNCTIONS.CreateImageKHR)(
-                egl_display,
-                egl_context,
-                EGL_GL_TEXTURE_2D_KHR,
-                egl_client_buffer,
-                egl_image_attribs.as_ptr(),
-            );
-
-            // Create the framebuffer, and bind the texture to it.
-            let framebuffer_object =
-                gl_utils::create_and_bind_framebuffer(gl, gl::TEXTURE_2D, texture_object);
-
-            // Bind renderbuffers as appropriate.
-            let renderbuffers = Renderbuffers::new(gl, size, context_attributes);
-            renderbuffers.bind_to_current_framebuffer(gl);
+            let depth_texture =
+                match DepthTexture::new(gl, egl_display, egl_context, size, context_attributes) {
+                    Ok(depth_texture) => depth_texture,
+                    Err(err) => {
+                        (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, egl_image);
+                        gl.DeleteTextures(1, &texture_object);
+                        return Err(err);
+                    }
+                };
 
-            debug_assert_eq!(
-                gl.CheckFramebufferStatus(gl::FRAMEBUFFER),
-                gl::FRAMEBUFFER_COMPLETE
+            // Create the framebuffer, and bind the texture and renderbuffers to it.
+            let (framebuffer_object, multisample, renderbuffers) = create_framebuffer(
+                gl,
+                texture_object,
+                &texture_format,
+                depth_texture.as_deref(),
+                size,
+                context_attributes,
             );
+            let status = framebuffer_status(gl, framebuffer_object, multisample.as_ref());
 
-            EGLBackedSurface {
+            let mut surface = EGLBackedSurface {
                 context_id,
                 size: *size,
-                objects: EGLSurfaceObjects::TextureImage {
+                access,
+                texture_format,
+                objects: Box::new(EGLSurfaceObjects::TextureImage {
                     egl_image,
                     framebuffer_object,
                     texture_object,
                     renderbuffers,
-                },
+                    multisample,
+                    depth_texture,
+                    imported: false,
+                }),
                 destroyed: false,
+            };
+
+            // Not every format is color-renderable everywhere, which only shows up here.
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                surface.destroy(gl, egl_display, context_id)?;
+                return Err(texture_format.unsupported_or(Error::SurfaceCreationFailed(
+                    NativeError::in_operation(
+                        WindowingApiError::Failed,
+                        "glCheckFramebufferStatus",
+                    ),
+                )));
             }
+            leaks::track(TrackedObject::Surface(surface.id()));
+            Ok(surface)
         }
     }
 
@@ -167,6 +259,7 @@ impl EGLBackedSurface {
         native_window: *mut c_void,
         context_id: ContextID,
         size: &Size2D<i32>,
+        access: SurfaceAccess,
     ) -> EGLBackedSurface {
         EGL_FUNCTIONS.with(|egl| unsafe {
             let window_surface_attribs = [egl::NONE as EGLAttrib];
@@ -177,32 +270,333 @@ impl EGLBackedSurface {
                 window_surface_attribs.as_ptr(),
             );
             assert_ne!(egl_surface, egl::NO_SURFACE);
+            leaks::track(TrackedObject::Surface(SurfaceID(egl_surface as usize)));
 
             EGLBackedSurface {
                 context_id,
                 size: *size,
-                objects: EGLSurfaceObjects::Window {
+                access,
+                texture_format: TextureFormat::new(SurfaceFormat::RGBA8, GLApi::GL),
+                objects: Box::new(EGLSurfaceObjects::Window {
                     native_window,
                     egl_surface,
-                },
+                }),
                 destroyed: false,
             }
         })
     }
 
+    // Creates a surface that renders to an imported dma-buf.
+    #[cfg(linux)]
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new_from_dmabuf(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        context_id: ContextID,
+        context_attributes: &ContextAttributes,
+        gl_api: GLApi,
+        descriptor: &DmaBufDescriptor,
+        access: SurfaceAccess,
+    ) -> Result<EGLBackedSurface, Error> {
+        let texture_format = TextureFormat::new(dmabuf::surface_format(descriptor.fourcc)?, gl_api);
+        unsafe {
+            let egl_image = dmabuf::create_egl_image(egl_display, descriptor)?;
+
+            // Flush out any stale errors so that we can detect allocation failures.
+            while gl.GetError() != gl::NO_ERROR {}
+
+            // Wrap the image in a texture and attach it to a framebuffer, as generic surfaces do.
+            let depth_texture = match DepthTexture::new(
+                gl,
+                egl_display,
+                egl_context,
+                &descriptor.size,
+                context_attributes,
+            ) {
+                Ok(depth_texture) => depth_texture,
+                Err(err) => {
+                    (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, egl_image);
+                    return Err(err);
+                }
+            };
+            let texture_object = bind_egl_image_to_gl_texture(gl, egl_image);
+            let (framebuffer_object, multisample, renderbuffers) = create_framebuffer(
+                gl,
+                texture_object,
+                &texture_format,
+                depth_texture.as_deref(),
+                &descriptor.size,
+                context_attributes,
+            );
+            let out_of_memory = gl.GetError() == gl::OUT_OF_MEMORY;
+            let status = framebuffer_status(gl, framebuffer_object, multisample.as_ref());
+
+            let mut surface = EGLBackedSurface {
+                context_id,
+                size: descriptor.size,
+                access,
+                texture_format,
+                objects: Box::new(EGLSurfaceObjects::TextureImage {
+                    egl_image,
+                    framebuffer_object,
+                    texture_object,
+                    renderbuffers,
+                    multisample,
+                    depth_texture,
+                    imported: true,
+                }),
+                destroyed: false,
+            };
+
+            // Drivers can import a layout that they can't render to, which only shows up here.
+            if out_of_memory || status != gl::FRAMEBUFFER_COMPLETE {
+                surface.destroy(gl, egl_display, context_id)?;
+                let native_error = if out_of_memory {
+                    NativeError::new(
+                        WindowingApiError::BadAlloc,
+                        NativeErrorCode::GL(gl::OUT_OF_MEMORY),
+                        "glRenderbufferStorage",
+                    )
+                } else {
+                    NativeError::in_operation(WindowingApiError::Failed, "glCheckFramebufferStatus")
+                };
+                return Err(Error::SurfaceImportFailed(native_error));
+            }
+            leaks::track(TrackedObject::Surface(surface.id()));
+            Ok(surface)
+        }
+    }
+
+    // Reallocates the texture, EGL images, and renderbuffers of a generic surface at a new size,
+    // keeping its framebuffer objects. The contents of the surface become undefined.
+    //
+    // Surfaces imported from dma-bufs can't be resized, since their storage belongs to whoever
+    // exported it; this returns `UnsupportedOnThisPlatform` for them.
+    //
+    // The context that the surface belongs to must be current.
+    pub(crate) fn resize(
+        &mut self,
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        context_id: ContextID,
+        context_attributes: &ContextAttributes,
+        size: Size2D<i32>,
+    ) -> Result<(), Error> {
+        if context_id != self.context_id {
+            return Err(Error::IncompatibleSurface);
+        }
+
+        let (
+            egl_image,
+            framebuffer_object,
+            texture_object,
+            renderbuffers,
+            multisample,
+            depth_texture,
+        ) = match *self.objects {
+            EGLSurfaceObjects::TextureImage {
+                ref mut egl_image,
+                framebuffer_object,
+                ref mut texture_object,
+                ref mut renderbuffers,
+                ref mut multisample,
+                ref mut depth_texture,
+                imported: false,
+            } => (
+                egl_image,
+                framebuffer_object,
+                texture_object,
+                renderbuffers,
+                multisample,
+                depth_texture,
+            ),
+            EGLSurfaceObjects::TextureImage { imported: true, .. } => {
+                return Err(Error::UnsupportedOnThisPlatform);
+            }
+            EGLSurfaceObjects::Window { .. } => {
+                self.size = size;
+                return Ok(());
+            }
+        };
+
+        unsafe {
+            let mut max_texture_size = 0;
+            gl.GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_texture_size);
+            if size.width <= 0
+                || size.height <= 0
+                || size.width > max_texture_size
+                || size.height > max_texture_size
+            {
+                return Err(Error::SurfaceCreationFailed(
+                    WindowingApiError::BadValue.into(),
+                ));
+            }
+
+            // Flush out any stale errors so that we can detect allocation failures.
+            while gl.GetError() != gl::NO_ERROR {}
+
+            let new_texture_object = create_texture(gl, &self.texture_format, &size);
+            if gl.GetError() == gl::OUT_OF_MEMORY {
+                gl.DeleteTextures(1, &new_texture_object);
+                return Err(Error::SurfaceCreationFailed(NativeError::new(
+                    WindowingApiError::BadAlloc,
+                    NativeErrorCode::GL(gl::OUT_OF_MEMORY),
+                    "glTexImage2D",
+                )));
+            }
+
+            let new_egl_image =
+                create_egl_image_from_texture(egl_display, egl_context, new_texture_object);
+            if new_egl_image == EGL_NO_IMAGE_KHR {
+                let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+                gl.DeleteTextures(1, &new_texture_object);
+                return Err(Error::SurfaceCreationFailed(
+                    windowing_api_error.to_native_error("eglCreateImageKHR"),
+                ));
+            }
+
+            let mut new_depth_texture = if depth_texture.is_some() {
+                match DepthTexture::new(gl, egl_display, egl_context, &size, context_attributes) {
+                    Ok(new_depth_texture) => new_depth_texture,
+                    Err(err) => {
+                        (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, new_egl_image);
+                        gl.DeleteTextures(1, &new_texture_object);
+                        return Err(err);
+                    }
+                }
+            } else {
+                None
+            };
+
+            // Point the existing framebuffers at the new storage.
+            let mut old_framebuffer_object = 0;
+            gl.GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut old_framebuffer_object);
+            let samples = multisample
+                .as_ref()
+                .map_or(0, |multisample| multisample.samples);
+            let new_color_renderbuffer = if samples > 0 {
+                create_color_renderbuffer(gl, samples, &self.texture_format, &size)
+            } else {
+                0
+            };
+            attach_color_buffers(
+                gl,
+                framebuffer_object,
+                multisample.as_ref(),
+                new_texture_object,
+                new_color_renderbuffer,
+                new_depth_texture.as_deref(),
+            );
+            let mut new_renderbuffers = create_renderbuffers(
+                gl,
+                &size,
+                context_attributes,
+                samples,
+                new_depth_texture.as_deref(),
+            );
+            new_renderbuffers.bind_to_current_framebuffer(gl);
+
+            let status = framebuffer_status(gl, framebuffer_object, multisample.as_ref());
+            let out_of_memory = gl.GetError() == gl::OUT_OF_MEMORY;
+            if status != gl::FRAMEBUFFER_COMPLETE || out_of_memory {
+                // Put the old storage back so that the surface stays usable.
+                let old_color_renderbuffer = multisample
+                    .as_ref()
+                    .map_or(0, |multisample| multisample.color_renderbuffer);
+                attach_color_buffers(
+                    gl,
+                    framebuffer_object,
+                    multisample.as_ref(),
+                    *texture_object,
+                    old_color_renderbuffer,
+                    depth_texture.as_deref(),
+                );
+                renderbuffers.bind_to_current_framebuffer(gl);
+                gl.BindFramebuffer(gl::FRAMEBUFFER, old_framebuffer_object as GLuint);
+
+                new_renderbuffers.destroy(gl);
+                if new_color_renderbuffer != 0 {
+                    gl.DeleteRenderbuffers(1, &new_color_renderbuffer);
+                }
+                if let Some(ref mut new_depth_texture) = new_depth_texture {
+                    new_depth_texture.destroy(gl, egl_display);
+                }
+                (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, new_egl_image);
+                gl.DeleteTextures(1, &new_texture_object);
+
+                let native_error = if out_of_memory {
+                    NativeError::new(
+                        WindowingApiError::BadAlloc,
+                        NativeErrorCode::GL(gl::OUT_OF_MEMORY),
+                        "glRenderbufferStorage",
+                    )
+                } else {
+                    NativeError::in_operation(WindowingApiError::Failed, "glCheckFramebufferStatus")
+                };
+                return Err(Error::SurfaceCreationFailed(native_error));
+            }
+            gl.BindFramebuffer(gl::FRAMEBUFFER, old_framebuffer_object as GLuint);
+
+            // Release the old storage.
+            renderbuffers.destroy(gl);
+            *renderbuffers = new_renderbuffers;
+            if let Some(ref mut multisample) = *multisample {
+                gl.DeleteRenderbuffers(1, &multisample.color_renderbuffer);
+                multisample.color_renderbuffer = new_color_renderbuffer;
+            }
+            if let Some(ref mut depth_texture) = *depth_texture {
+                depth_texture.destroy(gl, egl_display);
+            }
+            *depth_texture = new_depth_texture;
+            let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, *egl_image);
+            assert_ne!(result, egl::FALSE);
+            leaks::retrack(
+                TrackedObject::Surface(SurfaceID(*egl_image as usize)),
+                TrackedObject::Surface(SurfaceID(new_egl_image as usize)),
+            );
+            *egl_image = new_egl_image;
+            gl.DeleteTextures(1, texture_object);
+            *texture_object = new_texture_object;
+        }
+
+        self.size = size;
+        Ok(())
+    }
+
     pub(crate) fn to_surface_texture(
         self,
         gl: &Gl,
     ) -> Result<EGLSurfaceTexture, (Error, EGLBackedSurface)> {
         unsafe {
-            let egl_image = match self.objects {
-                EGLSurfaceObjects::TextureImage { egl_image, .. } => egl_image,
+            let (egl_image, depth_egl_image) = match *self.objects {
+                EGLSurfaceObjects::TextureImage {
+                    egl_image,
+                    ref depth_texture,
+                    ..
+                } => (
+                    egl_image,
+                    depth_texture
+                        .as_ref()
+                        .map(|depth_texture| depth_texture.egl_image),
+                ),
                 EGLSurfaceObjects::Window { .. } => return Err((Error::WidgetAttached, self)),
             };
             let texture_object = bind_egl_image_to_gl_texture(gl, egl_image);
+            let depth_texture_object = match depth_egl_image {
+                Some(depth_egl_image) => {
+                    let depth_texture_object = bind_egl_image_to_gl_texture(gl, depth_egl_image);
+                    set_nearest_filtering(gl, depth_texture_object);
+                    depth_texture_object
+                }
+                None => 0,
+            };
+            leaks::track(TrackedObject::SurfaceTexture(self.id()));
             Ok(EGLSurfaceTexture {
                 surface: self,
                 texture_object,
+                depth_texture_object,
                 phantom: PhantomData,
             })
         }
@@ -218,18 +612,28 @@ impl EGLBackedSurface {
             return Err(Error::IncompatibleSurface);
         }
 
+        leaks::untrack(TrackedObject::Surface(self.id()));
         unsafe {
-            match self.objects {
+            match *self.objects {
                 EGLSurfaceObjects::TextureImage {
                     ref mut egl_image,
                     ref mut framebuffer_object,
                     ref mut texture_object,
                     ref mut renderbuffers,
+                    ref mut multisample,
+                    ref mut depth_texture,
+                    ..
                 } => {
                     gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
                     gl.DeleteFramebuffers(1, framebuffer_object);
                     *framebuffer_object = 0;
                     renderbuffers.destroy(gl);
+                    if let Some(mut multisample) = multisample.take() {
+                        multisample.destroy(gl);
+                    }
+                    if let Some(mut depth_texture) = depth_texture.take() {
+                        depth_texture.destroy(gl, egl_display);
+                    }
 
                     let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, *egl_image);
                     assert_ne!(result, egl::FALSE);
@@ -264,7 +668,7 @@ impl EGLBackedSurface {
         egl_context: EGLContext,
     ) -> Result<(), Error> {
         unsafe {
-            match self.objects {
+            match *self.objects {
                 EGLSurfaceObjects::Window { egl_surface, .. } => {
                     // The surface must be bound to the current context in EGL 1.4. Temporarily
                     // make this surface current to enforce this.
@@ -278,7 +682,7 @@ impl EGLBackedSurface {
                             Ok(())
                         } else {
                             Err(Error::PresentFailed(
-                                egl.GetError().to_windowing_api_error(),
+                                egl.GetError().to_native_error("eglSwapBuffers"),
                             ))
                         }
                     })
@@ -293,24 +697,26 @@ impl EGLBackedSurface {
             size: self.size,
             id: self.id(),
             context_id: self.context_id,
-            framebuffer_object: match self.objects {
+            framebuffer_object: match *self.objects {
                 EGLSurfaceObjects::TextureImage {
                     framebuffer_object, ..
                 } => framebuffer_object,
                 EGLSurfaceObjects::Window { .. } => 0,
             },
+            format: self.texture_format.surface_format,
+            access: self.access,
         }
     }
 
     pub(crate) fn id(&self) -> SurfaceID {
-        match self.objects {
+        match *self.objects {
             EGLSurfaceObjects::TextureImage { egl_image, .. } => SurfaceID(egl_image as usize),
             EGLSurfaceObjects::Window { egl_surface, .. } => SurfaceID(egl_surface as usize),
         }
     }
 
     pub(crate) fn native_window(&self) -> Result<*const c_void, Error> {
-        match self.objects {
+        match *self.objects {
             EGLSurfaceObjects::TextureImage { .. } => Err(Error::NoWidgetAttached),
             EGLSurfaceObjects::Window { native_window, .. } => Ok(native_window),
         }
@@ -318,15 +724,28 @@ impl EGLBackedSurface {
 
     pub(crate) fn unbind(&self, gl: &Gl, egl_display: EGLDisplay, egl_context: EGLContext) {
         // If we're current, we stay current, but with no surface attached.
+        //
+        // Either way, flush our context, so that other contexts can read from the surface as soon
+        // as this returns. (If another context is current, making it current already flushed
+        // ours.)
         unsafe {
             EGL_FUNCTIONS.with(|egl| {
                 if egl.GetCurrentContext() != egl_context {
+                    // Resolving needs our context, so make it current for the duration.
+                    if self.is_multisampled() {
+                        let _guard = CurrentContextGuard::new();
+                        egl.MakeCurrent(egl_display, egl::NO_SURFACE, egl::NO_SURFACE, egl_context);
+                        self.resolve(gl);
+                        gl.Flush();
+                    }
                     return;
                 }
 
                 egl.MakeCurrent(egl_display, egl::NO_SURFACE, egl::NO_SURFACE, egl_context);
+                self.resolve(gl);
+                gl.Flush();
 
-                match self.objects {
+                match *self.objects {
                     EGLSurfaceObjects::TextureImage {
                         framebuffer_object, ..
                     } => {
@@ -338,8 +757,140 @@ impl EGLBackedSurface {
         }
     }
 
+    fn is_multisampled(&self) -> bool {
+        match *self.objects {
+            EGLSurfaceObjects::TextureImage {
+                multisample: Some(_),
+                ..
+            } => true,
+            EGLSurfaceObjects::TextureImage { .. } | EGLSurfaceObjects::Window { .. } => false,
+        }
+    }
+
+    // Resolves the multisampled color buffer of the surface, if it has one, into its texture, so
+    // that other contexts see what was rendered.
+    //
+    // The context that the surface belongs to must be current.
+    unsafe fn resolve(&self, gl: &Gl) {
+        if let EGLSurfaceObjects::TextureImage {
+            framebuffer_object,
+            multisample: Some(ref multisample),
+            ..
+        } = *self.objects
+        {
+            multisample.resolve(gl, framebuffer_object, &self.size);
+        }
+    }
+
+    // Reads the surface back into a pixel buffer object owned by the current context and maps it.
+    //
+    // The surface must not be bound to a context, and some context on the surface's display must
+    // be current.
+    pub(crate) fn lock_data(
+        &mut self,
+        gl: &Gl,
+        egl_display: EGLDisplay,
+    ) -> Result<EGLSurfaceDataGuard<'_>, Error> {
+        if !self.access.cpu_access_allowed() {
+            return Err(Error::SurfaceDataInaccessible);
+        }
+
+        let egl_image = match *self.objects {
+            EGLSurfaceObjects::TextureImage { egl_image, .. } => egl_image,
+            EGLSurfaceObjects::Window { .. } => return Err(Error::WidgetAttached),
+        };
+
+        unsafe {
+            let egl_context = EGL_FUNCTIONS.with(|egl| egl.GetCurrentContext());
+            if egl_context == egl::NO_CONTEXT {
+                return Err(Error::NoCurrentContext);
+            }
+
+            let texture_format = self.texture_format;
+            let stride = (self.size.width * texture_format.bytes_per_pixel) as usize;
+            let len = stride * self.size.height as usize;
+
+            // Save the current bindings.
+            let (mut old_read_framebuffer, mut old_pack_buffer) = (0, 0);
+            gl.GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut old_read_framebuffer);
+            gl.GetIntegerv(gl::PIXEL_PACK_BUFFER_BINDING, &mut old_pack_buffer);
+
+            // Alias the surface's image with a texture and framebuffer local to this context, so
+            // that it doesn't matter which context the surface was created with.
+            let texture_object = bind_egl_image_to_gl_texture(gl, egl_image);
+            let mut framebuffer_object = 0;
+            gl.GenFramebuffers(1, &mut framebuffer_object);
+            gl.BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer_object);
+            gl.FramebufferTexture2D(
+                gl::READ_FRAMEBUFFER,
+                gl::COLOR_ATTACHMENT0,
+                gl::TEXTURE_2D,
+                texture_object,
+                0,
+            );
+
+            // Read the pixels back into a pixel buffer object and map it.
+            let mut pixel_buffer_object = 0;
+            gl.GenBuffers(1, &mut pixel_buffer_object);
+            gl.BindBuffer(gl::PIXEL_PACK_BUFFER, pixel_buffer_object);
+            gl.BufferData(
+                gl::PIXEL_PACK_BUFFER,
+                len as GLsizeiptr,
+                ptr::null(),
+                gl::DYNAMIC_READ,
+            );
+            gl.ReadPixels(
+                0,
+                0,
+                self.size.width,
+                self.size.height,
+                texture_format.format,
+                texture_format.type_,
+                ptr::null_mut(),
+            );
+            let ptr = gl.MapBufferRange(
+                gl::PIXEL_PACK_BUFFER,
+                0,
+                len as GLsizeiptr,
+                gl::MAP_READ_BIT | gl::MAP_WRITE_BIT,
+            ) as *mut u8;
+
+            // Restore the old bindings.
+            gl.BindBuffer(gl::PIXEL_PACK_BUFFER, old_pack_buffer as GLuint);
+            gl.BindFramebuffer(gl::READ_FRAMEBUFFER, old_read_framebuffer as GLuint);
+
+            let mut guard = EGLSurfaceDataGuard {
+                surface: &mut *self,
+                egl_display,
+                egl_context,
+                texture_object,
+                framebuffer_object,
+                pixel_buffer_object,
+                stride,
+                ptr,
+                len,
+            };
+            if guard.ptr.is_null() {
+                guard.unlock(gl);
+                return Err(Error::SurfaceLockFailed);
+            }
+            Ok(guard)
+        }
+    }
+
+    // Exports the surface's EGL image as a dma-buf. The caller owns the returned file descriptors.
+    #[cfg(linux)]
+    pub(crate) fn export_dmabuf(&self, egl_display: EGLDisplay) -> Result<DmaBufDescriptor, Error> {
+        match *self.objects {
+            EGLSurfaceObjects::TextureImage { egl_image, .. } => unsafe {
+                dmabuf::export_egl_image(egl_display, egl_image, &self.size)
+            },
+            EGLSurfaceObjects::Window { .. } => Err(Error::WidgetAttached),
+        }
+    }
+
     pub(crate) fn egl_surfaces(&self) -> ExternalEGLSurfaces {
-        match self.objects {
+        match *self.objects {
             EGLSurfaceObjects::Window { egl_surface, .. } => ExternalEGLSurfaces {
                 draw: egl_surface,
                 read: egl_surface,
@@ -349,16 +900,241 @@ impl EGLBackedSurface {
     }
 }
 
+impl MultisampleBuffers {
+    unsafe fn resolve(&self, gl: &Gl, framebuffer_object: GLuint, size: &Size2D<i32>) {
+        // Save the current state. The scissor test applies to blits, so turn it off.
+        let (mut old_read_framebuffer, mut old_draw_framebuffer) = (0, 0);
+        gl.GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut old_read_framebuffer);
+        gl.GetIntegerv(gl::DRAW_FRAMEBUFFER_BINDING, &mut old_draw_framebuffer);
+        let scissor_test_enabled = gl.IsEnabled(gl::SCISSOR_TEST) != gl::FALSE;
+        if scissor_test_enabled {
+            gl.Disable(gl::SCISSOR_TEST);
+        }
+
+        let mut mask: GLbitfield = gl::COLOR_BUFFER_BIT;
+        if self.resolve_depth {
+            mask |= gl::DEPTH_BUFFER_BIT;
+        }
+        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer_object);
+        gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, self.resolve_framebuffer_object);
+        gl.BlitFramebuffer(
+            0,
+            0,
+            size.width,
+            size.height,
+            0,
+            0,
+            size.width,
+            size.height,
+            mask,
+            gl::NEAREST,
+        );
+
+        // Restore the old state.
+        if scissor_test_enabled {
+            gl.Enable(gl::SCISSOR_TEST);
+        }
+        gl.BindFramebuffer(gl::READ_FRAMEBUFFER, old_read_framebuffer as GLuint);
+        gl.BindFramebuffer(gl::DRAW_FRAMEBUFFER, old_draw_framebuffer as GLuint);
+    }
+
+    unsafe fn destroy(&mut self, gl: &Gl) {
+        gl.DeleteRenderbuffers(1, &self.color_renderbuffer);
+        self.color_renderbuffer = 0;
+        gl_utils::destroy_framebuffer(gl, self.resolve_framebuffer_object);
+        self.resolve_framebuffer_object = 0;
+    }
+}
+
+impl TextureFormat {
+    pub(crate) fn new(surface_format: SurfaceFormat, gl_api: GLApi) -> TextureFormat {
+        let (internal_format, format, type_, renderbuffer_format, bytes_per_pixel) =
+            match (surface_format, gl_api) {
+                (SurfaceFormat::RGBA8, _) => (gl::RGBA, gl::RGBA, gl::UNSIGNED_BYTE, gl::RGBA8, 4),
+                // Desktop OpenGL can only store BGRA data as RGBA, but it converts on transfer.
+                (SurfaceFormat::BGRA8, GLApi::GL) => {
+                    (gl::RGBA8, GL_BGRA_EXT, gl::UNSIGNED_BYTE, gl::RGBA8, 4)
+                }
+                // OpenGL ES has no multisampled BGRA renderbuffers to resolve from, since a
+                // resolve requires identical formats.
+                (SurfaceFormat::BGRA8, GLApi::GLES) => {
+                    (GL_BGRA_EXT, GL_BGRA_EXT, gl::UNSIGNED_BYTE, 0, 4)
+                }
+                (SurfaceFormat::RGBA16F, _) => {
+                    (gl::RGBA16F, gl::RGBA, gl::HALF_FLOAT, gl::RGBA16F, 8)
+                }
+                (SurfaceFormat::RGB10A2, _) => (
+                    gl::RGB10_A2,
+                    gl::RGBA,
+                    gl::UNSIGNED_INT_2_10_10_10_REV,
+                    gl::RGB10_A2,
+                    4,
+                ),
+                (SurfaceFormat::SRGB8A8, _) => (
+                    gl::SRGB8_ALPHA8,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    gl::SRGB8_ALPHA8,
+                    4,
+                ),
+            };
+        TextureFormat {
+            surface_format,
+            internal_format,
+            format,
+            type_,
+            renderbuffer_format,
+            bytes_per_pixel,
+        }
+    }
+
+    // Failures to allocate a surface in one of the optional formats mean that the driver doesn't
+    // support that format. RGBA8 is always supported, so failures there are reported as is.
+    fn unsupported_or(&self, error: Error) -> Error {
+        match self.surface_format {
+            SurfaceFormat::RGBA8 => error,
+            _ => Error::UnsupportedSurfaceFormat,
+        }
+    }
+}
+
+impl DepthTexture {
+    // Creates the depth texture of a generic surface with the given attributes, or returns `None`
+    // if the attributes don't ask for sampleable depth.
+    unsafe fn new(
+        gl: &Gl,
+        egl_display: EGLDisplay,
+        egl_context: EGLContext,
+        size: &Size2D<i32>,
+        context_attributes: &ContextAttributes,
+    ) -> Result<Option<Box<DepthTexture>>, Error> {
+        if !context_attributes
+            .flags
+            .contains(ContextAttributeFlags::SAMPLEABLE_DEPTH)
+        {
+            return Ok(None);
+        }
+        let (texture_object, attachment) =
+            match renderbuffers::create_depth_texture(gl, size, context_attributes) {
+                None => return Ok(None),
+                Some(depth_texture) => depth_texture,
+            };
+
+        let egl_image = create_egl_image_from_texture(egl_display, egl_context, texture_object);
+        if egl_image == EGL_NO_IMAGE_KHR {
+            let windowing_api_error = EGL_FUNCTIONS.with(|egl| egl.GetError());
+            gl.DeleteTextures(1, &texture_object);
+            return Err(Error::SurfaceCreationFailed(
+                windowing_api_error.to_native_error("eglCreateImageKHR"),
+            ));
+        }
+
+        Ok(Some(Box::new(DepthTexture {
+            texture_object,
+            egl_image,
+            attachment,
+        })))
+    }
+
+    unsafe fn destroy(&mut self, gl: &Gl, egl_display: EGLDisplay) {
+        let result = (EGL_EXTENSION_FUNCTIONS.DestroyImageKHR)(egl_display, self.egl_image);
+        assert_ne!(result, egl::FALSE);
+        self.egl_image = EGL_NO_IMAGE_KHR;
+        gl.DeleteTextures(1, &self.texture_object);
+        self.texture_object = 0;
+    }
+}
+
 impl EGLSurfaceTexture {
     pub(crate) fn destroy(mut self, gl: &Gl) -> EGLBackedSurface {
+        leaks::untrack(TrackedObject::SurfaceTexture(self.surface.id()));
         unsafe {
             gl.DeleteTextures(1, &self.texture_object);
             self.texture_object = 0;
+            if self.depth_texture_object != 0 {
+                gl.DeleteTextures(1, &self.depth_texture_object);
+                self.depth_texture_object = 0;
+            }
             self.surface
         }
     }
 }
 
+impl<'a> EGLSurfaceDataGuard<'a> {
+    #[inline]
+    pub(crate) fn stride(&self) -> usize {
+        self.stride
+    }
+
+    #[inline]
+    pub(crate) fn data(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+
+    // Unmaps the pixel buffer object, uploads its contents back to the surface, and releases the
+    // temporary objects. Calling this more than once is harmless.
+    pub(crate) fn unlock(&mut self, gl: &Gl) {
+        if self.pixel_buffer_object == 0 {
+            return;
+        }
+
+        unsafe {
+            // The temporary objects belong to the context that was current when we locked.
+            let _guard = CurrentContextGuard::new();
+            EGL_FUNCTIONS.with(|egl| {
+                if egl.GetCurrentContext() != self.egl_context {
+                    egl.MakeCurrent(
+                        self.egl_display,
+                        egl::NO_SURFACE,
+                        egl::NO_SURFACE,
+                        self.egl_context,
+                    );
+                }
+            });
+
+            let (mut old_texture_object, mut old_unpack_buffer) = (0, 0);
+            gl.GetIntegerv(gl::TEXTURE_BINDING_2D, &mut old_texture_object);
+            gl.GetIntegerv(gl::PIXEL_UNPACK_BUFFER_BINDING, &mut old_unpack_buffer);
+
+            gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, self.pixel_buffer_object);
+            let mapped = !self.ptr.is_null();
+            if mapped {
+                gl.UnmapBuffer(gl::PIXEL_UNPACK_BUFFER);
+                self.ptr = ptr::null_mut();
+
+                // Write the pixels back to the surface.
+                gl.BindTexture(gl::TEXTURE_2D, self.texture_object);
+                gl.TexSubImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    0,
+                    0,
+                    self.surface.size.width,
+                    self.surface.size.height,
+                    self.surface.texture_format.format,
+                    self.surface.texture_format.type_,
+                    ptr::null(),
+                );
+            }
+
+            gl.BindTexture(gl::TEXTURE_2D, old_texture_object as GLuint);
+            gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, old_unpack_buffer as GLuint);
+
+            gl.DeleteBuffers(1, &self.pixel_buffer_object);
+            self.pixel_buffer_object = 0;
+            gl_utils::destroy_framebuffer(gl, self.framebuffer_object);
+            self.framebuffer_object = 0;
+            gl.DeleteTextures(1, &self.texture_object);
+            self.texture_object = 0;
+
+            // Make sure the writes are visible to the context that renders to the surface.
+            if mapped {
+                gl.Flush();
+            }
+        }
+    }
+}
+
 impl Default for ExternalEGLSurfaces {
     #[inline]
     fn default() -> ExternalEGLSurfaces {
@@ -369,6 +1145,222 @@ impl Default for ExternalEGLSurfaces {
     }
 }
 
+// Creates the framebuffer that renders to a generic surface's texture, along with its depth and
+// stencil renderbuffers. If the context attributes ask for multisampling and the GL implementation
+// supports it, the framebuffer renders to a multisampled color renderbuffer instead, to be
+// resolved into the texture. The depth texture, if there is one, is attached the same way as the
+// color texture. Leaves the framebuffer bound.
+unsafe fn create_framebuffer(
+    gl: &Gl,
+    texture_object: GLuint,
+    texture_format: &TextureFormat,
+    depth_texture: Option<&DepthTexture>,
+    size: &Size2D<i32>,
+    context_attributes: &ContextAttributes,
+) -> (GLuint, Option<MultisampleBuffers>, Renderbuffers) {
+    let samples = if texture_format.renderbuffer_format != 0 {
+        supported_samples(gl, context_attributes)
+    } else {
+        0
+    };
+    let multisample = if samples > 0 {
+        let mut resolve_framebuffer_object = 0;
+        gl.GenFramebuffers(1, &mut resolve_framebuffer_object);
+        Some(MultisampleBuffers {
+            color_renderbuffer: create_color_renderbuffer(gl, samples, texture_format, size),
+            resolve_framebuffer_object,
+            samples,
+            resolve_depth: depth_texture.is_some(),
+        })
+    } else {
+        None
+    };
+
+    let color_renderbuffer = multisample
+        .as_ref()
+        .map_or(0, |multisample| multisample.color_renderbuffer);
+    let mut framebuffer_object = 0;
+    gl.GenFramebuffers(1, &mut framebuffer_object);
+    attach_color_buffers(
+        gl,
+        framebuffer_object,
+        multisample.as_ref(),
+        texture_object,
+        color_renderbuffer,
+        depth_texture,
+    );
+
+    let renderbuffers = create_renderbuffers(gl, size, context_attributes, samples, depth_texture);
+    renderbuffers.bind_to_current_framebuffer(gl);
+    (framebuffer_object, multisample, renderbuffers)
+}
+
+// Creates the depth and stencil renderbuffers of a generic surface. Single-sampled surfaces with a
+// depth texture render to it directly, and it holds their stencil buffer too, if they have one,
+// so they need no renderbuffers.
+unsafe fn create_renderbuffers(
+    gl: &Gl,
+    size: &Size2D<i32>,
+    context_attributes: &ContextAttributes,
+    samples: GLsizei,
+    depth_texture: Option<&DepthTexture>,
+) -> Renderbuffers {
+    match depth_texture {
+        Some(_) if samples == 0 => Renderbuffers::IndividualDepthStencil {
+            depth: 0,
+            stencil: 0,
+        },
+        _ => Renderbuffers::new_multisampled(gl, size, context_attributes, samples),
+    }
+}
+
+// Returns the number of samples that surfaces with the given attributes should have, or 0 if they
+// should be single-sampled.
+unsafe fn supported_samples(gl: &Gl, context_attributes: &ContextAttributes) -> GLsizei {
+    if context_attributes.samples <= 1
+        || !gl.RenderbufferStorageMultisample.is_loaded()
+        || !gl.BlitFramebuffer.is_loaded()
+    {
+        return 0;
+    }
+
+    let mut max_samples = 0;
+    gl.GetIntegerv(gl::MAX_SAMPLES, &mut max_samples);
+    let samples = (context_attributes.samples as GLsizei).min(max_samples);
+    if samples > 1 {
+        samples
+    } else {
+        0
+    }
+}
+
+unsafe fn create_color_renderbuffer(
+    gl: &Gl,
+    samples: GLsizei,
+    texture_format: &TextureFormat,
+    size: &Size2D<i32>,
+) -> GLuint {
+    renderbuffers::create_renderbuffer(gl, samples, texture_format.renderbuffer_format, size)
+}
+
+// Attaches the color storage of a generic surface: the texture goes on the resolve framebuffer if
+// the surface is multisampled, and on its main framebuffer otherwise. The depth texture, if any,
+// goes alongside the color texture. Leaves the main framebuffer bound.
+unsafe fn attach_color_buffers(
+    gl: &Gl,
+    framebuffer_object: GLuint,
+    multisample: Option<&MultisampleBuffers>,
+    texture_object: GLuint,
+    color_renderbuffer: GLuint,
+    depth_texture: Option<&DepthTexture>,
+) {
+    let texture_framebuffer_object = match multisample {
+        Some(multisample) => multisample.resolve_framebuffer_object,
+        None => framebuffer_object,
+    };
+    gl.BindFramebuffer(gl::FRAMEBUFFER, texture_framebuffer_object);
+    gl.FramebufferTexture2D(
+        gl::FRAMEBUFFER,
+        gl::COLOR_ATTACHMENT0,
+        gl::TEXTURE_2D,
+        texture_object,
+        0,
+    );
+    if let Some(depth_texture) = depth_texture {
+        gl.FramebufferTexture2D(
+            gl::FRAMEBUFFER,
+            depth_texture.attachment,
+            gl::TEXTURE_2D,
+            depth_texture.texture_object,
+            0,
+        );
+    }
+
+    gl.BindFramebuffer(gl::FRAMEBUFFER, framebuffer_object);
+    if multisample.is_some() {
+        gl.FramebufferRenderbuffer(
+            gl::FRAMEBUFFER,
+            gl::COLOR_ATTACHMENT0,
+            gl::RENDERBUFFER,
+            color_renderbuffer,
+        );
+    }
+}
+
+// Checks the completeness of the framebuffers of a generic surface. Leaves the main framebuffer
+// bound.
+unsafe fn framebuffer_status(
+    gl: &Gl,
+    framebuffer_object: GLuint,
+    multisample: Option<&MultisampleBuffers>,
+) -> GLenum {
+    if let Some(multisample) = multisample {
+        gl.BindFramebuffer(gl::FRAMEBUFFER, multisample.resolve_framebuffer_object);
+        let status = gl.CheckFramebufferStatus(gl::FRAMEBUFFER);
+        gl.BindFramebuffer(gl::FRAMEBUFFER, framebuffer_object);
+        if status != gl::FRAMEBUFFER_COMPLETE {
+            return status;
+        }
+    }
+    gl.CheckFramebufferStatus(gl::FRAMEBUFFER)
+}
+
+// Creates a texture with uninitialized storage of the given format and size.
+unsafe fn create_texture(gl: &Gl, texture_format: &TextureFormat, size: &Size2D<i32>) -> GLuint {
+    let mut texture_object = 0;
+    gl.GenTextures(1, &mut texture_object);
+    // Save the current texture binding
+    let mut old_texture_object = 0;
+    gl.GetIntegerv(gl::TEXTURE_BINDING_2D, &mut old_texture_object);
+    gl.BindTexture(gl::TEXTURE_2D, texture_object);
+    // Unbind PIXEL_UNPACK_BUFFER, because if it is bound,
+    // it can cause errors in glTexImage2D.
+    // TODO: should this be inside a check for GL 2.0?
+    let mut unpack_buffer = 0;
+    gl.GetIntegerv(gl::PIXEL_UNPACK_BUFFER_BINDING, &mut unpack_buffer);
+    if unpack_buffer != 0 {
+        gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, 0);
+    }
+    gl.TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        texture_format.internal_format as GLint,
+        size.width,
+        size.height,
+        0,
+        texture_format.format,
+        texture_format.type_,
+        ptr::null(),
+    );
+    // Restore the old bindings
+    gl.BindTexture(gl::TEXTURE_2D, old_texture_object as _);
+    if unpack_buffer != 0 {
+        gl.BindBuffer(gl::PIXEL_UNPACK_BUFFER, unpack_buffer as _);
+    }
+    texture_object
+}
+
+unsafe fn create_egl_image_from_texture(
+    egl_display: EGLDisplay,
+    egl_context: EGLContext,
+    texture_object: GLuint,
+) -> EGLImageKHR {
+    let egl_image_attribs = [
+        EGL_IMAGE_PRESERVED_KHR as EGLint,
+        egl::FALSE as EGLint,
+        egl::NONE as EGLint,
+        0,
+    ];
+    let egl_client_buffer = texture_object as usize as EGLClientBuffer;
+    (EGL_EXTENSION_FUNCTIONS.CreateImageKHR)(
+        egl_display,
+        egl_context,
+        EGL_GL_TEXTURE_2D_KHR,
+        egl_client_buffer,
+        egl_image_attribs.as_ptr(),
+    )
+}
+
 #[allow(dead_code)]
 pub(crate) unsafe fn create_pbuffer_surface(
     egl_display: EGLDisplay,
@@ -426,3 +1418,13 @@ pub(crate) unsafe fn bind_egl_image_to_gl_texture(gl: &Gl, egl_image: EGLImageKH
     debug_assert_eq!(gl.GetError(), gl::NO_ERROR);
     texture
 }
+
+// Switches a texture to nearest-neighbor filtering. Depth textures can't be filtered on OpenGL ES.
+unsafe fn set_nearest_filtering(gl: &Gl, texture_object: GLuint) {
+    let mut texture_binding = 0;
+    gl.GetIntegerv(gl::TEXTURE_BINDING_2D, &mut texture_binding);
+    gl.BindTexture(gl::TEXTURE_2D, texture_object);
+    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+    gl.BindTexture(gl::TEXTURE_2D, texture_binding as GLuint);
+}
@@ -95,6 +95,14 @@ where
         }
     }
 
+    /// Chooses whether devices opened from now on create OpenGL or OpenGL ES contexts.
+    pub fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        match *self {
+            Connection::Default(ref mut connection) => connection.set_gl_api(gl_api),
+            Connection::Alternate(ref mut connection) => connection.set_gl_api(gl_api),
+        }
+    }
+
     /// Returns the "best" adapter on this system.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
@@ -270,6 +278,11 @@ where
         Connection::gl_api(self)
     }
 
+    #[inline]
+    fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        Connection::set_gl_api(self, gl_api)
+    }
+
     #[inline]
     fn create_adapter(&self) -> Result<Adapter<Def, Alt>, Error> {
         Connection::create_adapter(self)
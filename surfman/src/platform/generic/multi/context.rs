@@ -5,7 +5,8 @@
 use super::device::Device;
 use super::surface::Surface;
 use crate::device::Device as DeviceInterface;
-use crate::{ContextAttributes, ContextID, Error, SurfaceInfo};
+use crate::{ContextAttributes, ContextDescriptorInfo, ContextID, ContextResetStatus};
+use crate::{Error, GLInfo, SurfaceInfo};
 
 use std::os::raw::c_void;
 
@@ -24,7 +25,8 @@ use std::os::raw::c_void;
 /// OpenGL objects may not be shared across contexts directly, but surface textures effectively
 /// allow for sharing of texture data. Contexts are local to a single thread and device.
 ///
-/// A context must be explicitly destroyed with `destroy_context()`, or a panic will occur.
+/// A context must be explicitly destroyed with `destroy_context()`. Dropping it without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub enum Context<Def, Alt>
 where
     Def: DeviceInterface,
@@ -51,6 +53,18 @@ where
     Alternate(Alt::ContextDescriptor),
 }
 
+/// A point in the command stream of a context that other contexts can wait for.
+pub enum Fence<Def, Alt>
+where
+    Def: DeviceInterface,
+    Alt: DeviceInterface,
+{
+    /// The default fence type.
+    Default(Def::Fence),
+    /// The alternate fence type.
+    Alternate(Alt::Fence),
+}
+
 /// Wraps a platform-specific native context.
 pub enum NativeContext<Def, Alt>
 where
@@ -85,6 +99,27 @@ where
         }
     }
 
+    /// Returns a context descriptor for every pixel format of the device, whether or not it
+    /// matches the given attributes, starting with the one that `create_context_descriptor()`
+    /// chooses if there is one.
+    pub fn enumerate_context_descriptors(
+        &self,
+        attributes: &ContextAttributes,
+    ) -> Result<Vec<ContextDescriptor<Def, Alt>>, Error> {
+        match *self {
+            Device::Default(ref device) => Ok(device
+                .enumerate_context_descriptors(attributes)?
+                .into_iter()
+                .map(ContextDescriptor::Default)
+                .collect()),
+            Device::Alternate(ref device) => Ok(device
+                .enumerate_context_descriptors(attributes)?
+                .into_iter()
+                .map(ContextDescriptor::Alternate)
+                .collect()),
+        }
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -285,6 +320,23 @@ where
         }
     }
 
+    /// Describes the pixel format behind the given context descriptor.
+    pub fn context_descriptor_info(
+        &self,
+        context_descriptor: &ContextDescriptor<Def, Alt>,
+    ) -> Result<ContextDescriptorInfo, Error> {
+        match (self, context_descriptor) {
+            (&Device::Default(ref device), &ContextDescriptor::Default(ref context_descriptor)) => {
+                device.context_descriptor_info(context_descriptor)
+            }
+            (
+                &Device::Alternate(ref device),
+                &ContextDescriptor::Alternate(ref context_descriptor),
+            ) => device.context_descriptor_info(context_descriptor),
+            _ => Err(Error::IncompatibleContextDescriptor),
+        }
+    }
+
     /// Fetches the address of an OpenGL function associated with this context.
     ///
     /// OpenGL functions are local to a context. You should not use OpenGL functions on one context
@@ -341,4 +393,107 @@ where
             _ => Err(Error::IncompatibleContext),
         }
     }
+
+    /// Returns whether the context has been lost to a GPU reset since it was created.
+    ///
+    /// The context need not be current when calling this.
+    pub fn context_reset_status(
+        &self,
+        context: &Context<Def, Alt>,
+    ) -> Result<ContextResetStatus, Error> {
+        match (self, context) {
+            (&Device::Default(ref device), &Context::Default(ref context)) => {
+                device.context_reset_status(context)
+            }
+            (&Device::Alternate(ref device), &Context::Alternate(ref context)) => {
+                device.context_reset_status(context)
+            }
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
+    /// Returns the vendor, renderer, and version information of the given context.
+    ///
+    /// The context is temporarily made current if it isn't already.
+    /// If the version string is missing or can't be parsed, this returns an `UnknownGLVersion`
+    /// error.
+    pub fn context_gl_info(&self, context: &Context<Def, Alt>) -> Result<GLInfo, Error> {
+        match (self, context) {
+            (&Device::Default(ref device), &Context::Default(ref context)) => {
+                device.context_gl_info(context)
+            }
+            (&Device::Alternate(ref device), &Context::Alternate(ref context)) => {
+                device.context_gl_info(context)
+            }
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
+    /// Inserts a fence into the command stream of the given context and flushes the context.
+    ///
+    /// Waiting on the fence with `wait_fence()` makes another context wait for all the commands
+    /// issued so far. Backends that don't support fences return `UnsupportedOnThisPlatform`.
+    ///
+    /// A fence must be explicitly destroyed with `destroy_fence()`.
+    pub fn create_fence(&self, context: &Context<Def, Alt>) -> Result<Fence<Def, Alt>, Error> {
+        match (self, context) {
+            (&Device::Default(ref device), &Context::Default(ref context)) => {
+                device.create_fence(context).map(Fence::Default)
+            }
+            (&Device::Alternate(ref device), &Context::Alternate(ref context)) => {
+                device.create_fence(context).map(Fence::Alternate)
+            }
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
+    /// Makes the given context wait for a fence to be signaled before executing any further
+    /// commands.
+    ///
+    /// Fences from the other backend can't be waited on, and return an `IncompatibleContext`
+    /// error.
+    pub fn wait_fence(
+        &self,
+        context: &Context<Def, Alt>,
+        fence: &Fence<Def, Alt>,
+    ) -> Result<(), Error> {
+        match (self, context, fence) {
+            (
+                &Device::Default(ref device),
+                &Context::Default(ref context),
+                &Fence::Default(ref fence),
+            ) => device.wait_fence(context, fence),
+            (
+                &Device::Alternate(ref device),
+                &Context::Alternate(ref context),
+                &Fence::Alternate(ref fence),
+            ) => device.wait_fence(context, fence),
+            _ => Err(Error::IncompatibleContext),
+        }
+    }
+
+    /// Destroys a fence.
+    ///
+    /// If an error is returned, the fence is returned alongside it.
+    pub fn destroy_fence(
+        &self,
+        context: &Context<Def, Alt>,
+        fence: Fence<Def, Alt>,
+    ) -> Result<(), (Error, Fence<Def, Alt>)> {
+        match (self, context) {
+            (&Device::Default(ref device), &Context::Default(ref context)) => match fence {
+                Fence::Default(fence) => device
+                    .destroy_fence(context, fence)
+                    .map_err(|(err, fence)| (err, Fence::Default(fence))),
+                _ => Err((Error::IncompatibleContext, fence)),
+            },
+            (&Device::Alternate(ref device), &Context::Alternate(ref context)) => match fence {
+                Fence::Alternate(fence) => device
+                    .destroy_fence(context, fence)
+                    .map_err(|(err, fence)| (err, Fence::Alternate(fence))),
+                _ => Err((Error::IncompatibleContext, fence)),
+            },
+            _ => Err((Error::IncompatibleContext, fence)),
+        }
+    }
 }
@@ -3,13 +3,14 @@
 //! A device abstraction that allows the choice of backends dynamically.
 
 use super::connection::Connection;
-use super::context::{Context, ContextDescriptor, NativeContext};
+use super::context::{Context, ContextDescriptor, Fence, NativeContext};
 use super::surface::{NativeWidget, Surface, SurfaceTexture};
 use crate::connection::Connection as ConnectionInterface;
-use crate::context::ContextAttributes;
+use crate::context::{ContextAttributes, ContextDescriptorInfo};
 use crate::device::Device as DeviceInterface;
 use crate::gl::types::{GLenum, GLuint};
-use crate::{ContextID, Error, GLApi, SurfaceAccess, SurfaceInfo, SurfaceType};
+use crate::{ContextID, ContextResetStatus, DeviceCapabilities, Error, GLApi, GLInfo};
+use crate::{SurfaceAccess, SurfaceInfo, SurfaceType};
 use euclid::default::Size2D;
 
 use std::os::raw::c_void;
@@ -107,6 +108,17 @@ where
             Device::Alternate(ref device) => device.gl_api(),
         }
     }
+
+    /// Describes what this device supports.
+    ///
+    /// The first call may create a temporary context and surfaces to find out, so it can be
+    /// relatively expensive. The underlying device keeps the result, so later calls are cheap.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        match *self {
+            Device::Default(ref mut device) => device.capabilities(),
+            Device::Alternate(ref mut device) => device.capabilities(),
+        }
+    }
 }
 
 impl<Def, Alt> DeviceInterface for Device<Def, Alt>
@@ -122,6 +134,7 @@ where
     type NativeContext = NativeContext<Def, Alt>;
     type Surface = Surface<Def, Alt>;
     type SurfaceTexture = SurfaceTexture<Def, Alt>;
+    type Fence = Fence<Def, Alt>;
 
     // device.rs
 
@@ -145,6 +158,11 @@ where
         Device::gl_api(self)
     }
 
+    #[inline]
+    fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        Device::capabilities(self)
+    }
+
     // context.rs
 
     #[inline]
@@ -155,6 +173,14 @@ where
         Device::create_context_descriptor(self, attributes)
     }
 
+    #[inline]
+    fn enumerate_context_descriptors(
+        &self,
+        attributes: &ContextAttributes,
+    ) -> Result<Vec<Self::ContextDescriptor>, Error> {
+        Device::enumerate_context_descriptors(self, attributes)
+    }
+
     #[inline]
     fn create_context(
         &mut self,
@@ -205,6 +231,14 @@ where
         Device::context_descriptor_attributes(self, context_descriptor)
     }
 
+    #[inline]
+    fn context_descriptor_info(
+        &self,
+        context_descriptor: &ContextDescriptor<Def, Alt>,
+    ) -> Result<ContextDescriptorInfo, Error> {
+        Device::context_descriptor_info(self, context_descriptor)
+    }
+
     #[inline]
     fn get_proc_address(&self, context: &Context<Def, Alt>, symbol_name: &str) -> *const c_void {
         Device::get_proc_address(self, context, symbol_name)
@@ -240,6 +274,42 @@ where
         Device::context_surface_info(self, context)
     }
 
+    #[inline]
+    fn context_reset_status(
+        &self,
+        context: &Context<Def, Alt>,
+    ) -> Result<ContextResetStatus, Error> {
+        Device::context_reset_status(self, context)
+    }
+
+    #[inline]
+    fn context_gl_info(&self, context: &Context<Def, Alt>) -> Result<GLInfo, Error> {
+        Device::context_gl_info(self, context)
+    }
+
+    #[inline]
+    fn create_fence(&self, context: &Context<Def, Alt>) -> Result<Fence<Def, Alt>, Error> {
+        Device::create_fence(self, context)
+    }
+
+    #[inline]
+    fn wait_fence(
+        &self,
+        context: &Context<Def, Alt>,
+        fence: &Fence<Def, Alt>,
+    ) -> Result<(), Error> {
+        Device::wait_fence(self, context, fence)
+    }
+
+    #[inline]
+    fn destroy_fence(
+        &self,
+        context: &Context<Def, Alt>,
+        fence: Fence<Def, Alt>,
+    ) -> Result<(), (Error, Fence<Def, Alt>)> {
+        Device::destroy_fence(self, context, fence)
+    }
+
     // surface.rs
 
     #[inline]
@@ -27,7 +27,8 @@ use std::fmt::{self, Debug, Formatter};
 ///
 /// Depending on the platform, each surface may be internally double-buffered.
 ///
-/// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
+/// Surfaces must be destroyed with the `destroy_surface()` method. Dropping one without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub enum Surface<Def, Alt>
 where
     Def: DeviceInterface,
@@ -185,8 +186,8 @@ where
     /// The supplied context must be the context the surface is associated with, or this returns
     /// an `IncompatibleSurface` error.
     ///
-    /// You must explicitly call this method to dispose of a surface. Otherwise, a panic occurs in
-    /// the `drop` method.
+    /// You must explicitly call this method to dispose of a surface. Otherwise, the surface leaks
+    /// when it's dropped, and the leak is reported as chosen with `set_leak_policy()`.
     pub fn destroy_surface(
         &self,
         context: &mut Context<Def, Alt>,
@@ -57,6 +57,18 @@ impl Connection {
         GLApi::GL
     }
 
+    /// Chooses the OpenGL API flavor for devices opened from now on.
+    ///
+    /// This backend only supports OpenGL, so any other choice returns `UnsupportedGLType`.
+    #[inline]
+    pub fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        if gl_api == GLApi::GL {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedGLType)
+        }
+    }
+
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
@@ -7,9 +7,12 @@ use super::error::ToWindowingApiError;
 use super::ffi::{CGLReleaseContext, CGLRetainContext};
 use super::surface::Surface;
 use crate::context::{ContextID, CREATE_CONTEXT_MUTEX};
+use crate::debug;
 use crate::gl_utils;
+use crate::leaks::{self, TrackedObject};
 use crate::surface::Framebuffer;
-use crate::{ContextAttributeFlags, ContextAttributes, Error, GLVersion, Gl, SurfaceInfo};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextDescriptorInfo, ContextPriority};
+use crate::{ContextResetStatus, DepthFormat, Error, GLInfo, GLVersion, Gl, SurfaceInfo};
 
 use cgl::{kCGLPFAAllowOfflineRenderers, kCGLPFAAlphaSize, kCGLPFADepthSize};
 use cgl::{kCGLPFAOpenGLProfile, kCGLPFAStencilSize};
@@ -27,7 +30,6 @@ use std::mem;
 use std::os::raw::c_void;
 use std::ptr;
 use std::str::FromStr;
-use std::thread;
 
 // No CGL error occurred.
 #[allow(non_upper_case_globals)]
@@ -78,7 +80,8 @@ thread_local! {
 /// OpenGL objects may not be shared across contexts directly, but surface textures effectively
 /// allow for sharing of texture data. Contexts are local to a single thread and device.
 ///
-/// A context must be explicitly destroyed with `destroy_context()`, or a panic will occur.
+/// A context must be explicitly destroyed with `destroy_context()`. Dropping it without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Context {
     pub(crate) cgl_context: CGLContextObj,
     pub(crate) id: ContextID,
@@ -91,8 +94,8 @@ pub struct NativeContext(pub CGLContextObj);
 impl Drop for Context {
     #[inline]
     fn drop(&mut self) {
-        if !self.cgl_context.is_null() && !thread::panicking() {
-            panic!("Contexts must be destroyed explicitly with `destroy_context`!")
+        if !self.cgl_context.is_null() {
+            leaks::dropped_without_destroying(TrackedObject::Context(self.id));
         }
     }
 }
@@ -127,6 +130,12 @@ impl Clone for ContextDescriptor {
 
 unsafe impl Send for ContextDescriptor {}
 
+/// A point in the command stream of a context that other contexts can wait for.
+///
+/// This backend doesn't support fences, so none can be created.
+#[derive(Debug)]
+pub enum Fence {}
+
 impl Device {
     /// Creates a context descriptor with the given attributes.
     ///
@@ -197,7 +206,7 @@ impl Device {
             );
             if err != kCGLNoError {
                 return Err(Error::PixelFormatSelectionFailed(
-                    err.to_windowing_api_error(),
+                    err.to_native_error("CGLChoosePixelFormat"),
                 ));
             }
             if cgl_pixel_format_count == 0 {
@@ -208,6 +217,18 @@ impl Device {
         }
     }
 
+    /// Returns a context descriptor for every pixel format of the device.
+    ///
+    /// This backend can't enumerate pixel formats, so this always returns
+    /// `UnsupportedOnThisPlatform`.
+    #[inline]
+    pub fn enumerate_context_descriptors(
+        &self,
+        _: &ContextAttributes,
+    ) -> Result<Vec<ContextDescriptor>, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -231,7 +252,9 @@ impl Device {
                 &mut cgl_context,
             );
             if err != kCGLNoError {
-                return Err(Error::ContextCreationFailed(err.to_windowing_api_error()));
+                return Err(Error::ContextCreationFailed(
+                    err.to_native_error("CGLCreateContext"),
+                ));
             }
             debug_assert_ne!(cgl_context, ptr::null_mut());
 
@@ -241,6 +264,7 @@ impl Device {
                 id: *next_context_id,
                 framebuffer: Framebuffer::None,
             };
+            leaks::track(TrackedObject::Context(context.id));
             next_context_id.0 += 1;
             Ok(context)
         }
@@ -260,6 +284,7 @@ impl Device {
             id: *next_context_id,
             framebuffer: Framebuffer::None,
         };
+        leaks::track(TrackedObject::Context(context.id));
         next_context_id.0 += 1;
         mem::forget(native_context);
         Ok(context)
@@ -285,6 +310,8 @@ impl Device {
             context.cgl_context = ptr::null_mut();
         }
 
+        debug::set_debug_callback(context.id, None);
+        leaks::untrack(TrackedObject::Context(context.id));
         Ok(())
     }
 
@@ -305,7 +332,9 @@ impl Device {
         unsafe {
             let err = CGLSetCurrentContext(context.cgl_context);
             if err != kCGLNoError {
-                return Err(Error::MakeCurrentFailed(err.to_windowing_api_error()));
+                return Err(Error::MakeCurrentFailed(
+                    err.to_native_error("CGLSetCurrentContext"),
+                ));
             }
             Ok(())
         }
@@ -319,7 +348,9 @@ impl Device {
         unsafe {
             let err = CGLSetCurrentContext(ptr::null_mut());
             if err != kCGLNoError {
-                return Err(Error::MakeCurrentFailed(err.to_windowing_api_error()));
+                return Err(Error::MakeCurrentFailed(
+                    err.to_native_error("CGLSetCurrentContext"),
+                ));
             }
             Ok(())
         }
@@ -397,6 +428,30 @@ impl Device {
         }
     }
 
+    /// Inserts a fence into the command stream of the given context.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn create_fence(&self, _: &Context) -> Result<Fence, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
+    /// Makes the given context wait for a fence to be signaled.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn wait_fence(&self, _: &Context, _: &Fence) -> Result<(), Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
+    /// Destroys a fence.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn destroy_fence(&self, _: &Context, fence: Fence) -> Result<(), (Error, Fence)> {
+        Err((Error::UnsupportedOnThisPlatform, fence))
+    }
+
     /// Returns the attributes that the context descriptor was created with.
     pub fn context_descriptor_attributes(
         &self,
@@ -426,6 +481,9 @@ impl Device {
             return ContextAttributes {
                 flags: attribute_flags,
                 version,
+                samples: 0,
+                depth_format: DepthFormat::Depth24,
+                priority: ContextPriority::Medium,
             };
         }
 
@@ -445,6 +503,18 @@ impl Device {
         }
     }
 
+    /// Describes the pixel format behind the given context descriptor.
+    ///
+    /// This backend doesn't support pixel format introspection, so this always returns
+    /// `UnsupportedOnThisPlatform`.
+    #[inline]
+    pub fn context_descriptor_info(
+        &self,
+        _: &ContextDescriptor,
+    ) -> Result<ContextDescriptorInfo, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
     /// Fetches the address of an OpenGL function associated with this context.
     ///
     /// OpenGL functions are local to a context. You should not use OpenGL functions on one context
@@ -468,6 +538,24 @@ impl Device {
         }
     }
 
+    /// Returns whether the context has been lost to a GPU reset since it was created.
+    ///
+    /// This backend doesn't create robust contexts, so this always returns `NoReset`.
+    #[inline]
+    pub fn context_reset_status(&self, _: &Context) -> Result<ContextResetStatus, Error> {
+        Ok(ContextResetStatus::NoReset)
+    }
+
+    /// Returns the vendor, renderer, and version information of the given context.
+    ///
+    /// The context is temporarily made current if it isn't already.
+    /// If the version string is missing or can't be parsed, this returns an `UnknownGLVersion`
+    /// error.
+    pub fn context_gl_info(&self, context: &Context) -> Result<GLInfo, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        GL_FUNCTIONS.with(GLInfo::current)
+    }
+
     /// Returns a unique ID representing a context.
     ///
     /// This ID is unique to all currently-allocated contexts. If you destroy a context and create
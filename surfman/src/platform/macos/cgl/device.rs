@@ -4,7 +4,7 @@
 
 use super::connection::Connection;
 use crate::platform::macos::system::device::{Adapter as SystemAdapter, Device as SystemDevice};
-use crate::GLApi;
+use crate::{DeviceCapabilities, Error, GLApi};
 
 pub use crate::platform::macos::system::device::NativeDevice;
 
@@ -44,4 +44,13 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GL
     }
+
+    /// Describes what this device supports.
+    ///
+    /// This backend doesn't support capability queries yet, so this always returns
+    /// `UnsupportedOnThisPlatform`.
+    #[inline]
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
 }
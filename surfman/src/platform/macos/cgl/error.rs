@@ -2,11 +2,15 @@
 //
 //! Translation of errors from the CGL API to `surfman` errors.
 
-use crate::WindowingApiError;
+use crate::{NativeError, NativeErrorCode, WindowingApiError};
 use cgl::CGLError;
 
 pub(crate) trait ToWindowingApiError {
     fn to_windowing_api_error(self) -> WindowingApiError;
+
+    /// Keeps the raw error code alongside the translated error, along with the name of the CGL
+    /// function that failed.
+    fn to_native_error(self, context: &'static str) -> NativeError;
 }
 
 impl ToWindowingApiError for CGLError {
@@ -33,4 +37,12 @@ impl ToWindowingApiError for CGLError {
             _ => WindowingApiError::Failed,
         }
     }
+
+    fn to_native_error(self, context: &'static str) -> NativeError {
+        NativeError::new(
+            self.to_windowing_api_error(),
+            NativeErrorCode::CGL(self),
+            context,
+        )
+    }
 }
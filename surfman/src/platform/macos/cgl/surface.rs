@@ -7,9 +7,11 @@ use super::device::Device;
 use crate::context::ContextID;
 use crate::gl::types::{GLenum, GLint, GLuint};
 use crate::gl_utils;
+use crate::leaks::{self, TrackedObject};
 use crate::platform::macos::system::surface::Surface as SystemSurface;
 use crate::renderbuffers::Renderbuffers;
-use crate::{gl, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType, WindowingApiError};
+use crate::{gl, Error, NativeError, SurfaceAccess, SurfaceFormat, SurfaceID, SurfaceInfo};
+use crate::{SurfaceType, WindowingApiError};
 
 use core_foundation::base::TCFType;
 use euclid::default::Size2D;
@@ -36,7 +38,8 @@ const SURFACE_GL_TEXTURE_TARGET: GLenum = gl::TEXTURE_RECTANGLE;
 ///
 /// Depending on the platform, each surface may be internally double-buffered.
 ///
-/// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
+/// Surfaces must be destroyed with the `destroy_surface()` method. Dropping one without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Surface {
     pub(crate) system_surface: SystemSurface,
     pub(crate) context_id: ContextID,
@@ -128,7 +131,10 @@ impl Device {
                     }
                     let _ = self.0.destroy_surface(&mut system_surface);
                     // TODO: convert the GL error into a surfman error?
-                    return Err(Error::SurfaceCreationFailed(WindowingApiError::Failed));
+                    return Err(Error::SurfaceCreationFailed(NativeError::in_operation(
+                        WindowingApiError::Failed,
+                        "glCheckFramebufferStatus",
+                    )));
                 }
 
                 Ok(Surface {
@@ -167,6 +173,7 @@ impl Device {
             &surface.system_surface.io_surface,
             &surface.system_surface.size,
         );
+        leaks::track(TrackedObject::SurfaceTexture(surface.id()));
         Ok(SurfaceTexture {
             surface,
             texture_object,
@@ -217,8 +224,8 @@ impl Device {
     /// The supplied context must be the context the surface is associated with, or this returns
     /// an `IncompatibleSurface` error.
     ///
-    /// You must explicitly call this method to dispose of a surface. Otherwise, a panic occurs in
-    /// the `drop` method.
+    /// You must explicitly call this method to dispose of a surface. Otherwise, the surface leaks
+    /// when it's dropped, and the leak is reported as chosen with `set_leak_policy()`.
     pub fn destroy_surface(
         &self,
         context: &mut Context,
@@ -260,6 +267,7 @@ impl Device {
                 surface_texture.texture_object = 0;
             }
 
+            leaks::untrack(TrackedObject::SurfaceTexture(surface_texture.surface.id()));
             Ok(surface_texture.surface)
         })
     }
@@ -384,6 +392,9 @@ impl Device {
             id: system_surface_info.id,
             context_id: surface.context_id,
             framebuffer_object: surface.framebuffer_object,
+            // IOSurfaces are always allocated in BGRA order.
+            format: SurfaceFormat::BGRA8,
+            access: surface.system_surface.access,
         }
     }
 
@@ -6,6 +6,7 @@ use super::device::Device;
 use super::ffi::{kCVPixelFormatType_32BGRA, kIOMapDefaultCache, IOSurfaceLock, IOSurfaceUnlock};
 use super::ffi::{kCVReturnSuccess, kIOMapWriteCombineCache};
 use super::ffi::{IOSurfaceGetAllocSize, IOSurfaceGetBaseAddress, IOSurfaceGetBytesPerRow};
+use crate::leaks::{self, TrackedObject};
 use crate::{Error, SurfaceAccess, SurfaceID, SurfaceType, SystemSurfaceInfo};
 
 use cocoa::appkit::{NSScreen, NSView as NSViewMethods, NSWindow};
@@ -27,7 +28,6 @@ use std::mem;
 use std::os::raw::c_void;
 use std::slice;
 use std::sync::{Arc, Condvar, Mutex};
-use std::thread;
 
 const BYTES_PER_PIXEL: i32 = 4;
 
@@ -41,11 +41,12 @@ const BYTES_PER_PIXEL: i32 = 4;
 ///
 /// Depending on the platform, each surface may be internally double-buffered.
 ///
-/// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
+/// Surfaces must be destroyed with the `destroy_surface()` method. Dropping one without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Surface {
     pub(crate) io_surface: IOSurface,
     pub(crate) size: Size2D<i32>,
-    access: SurfaceAccess,
+    pub(crate) access: SurfaceAccess,
     pub(crate) destroyed: bool,
     pub(crate) view_info: Option<ViewInfo>,
 }
@@ -64,8 +65,8 @@ impl Debug for Surface {
 
 impl Drop for Surface {
     fn drop(&mut self) {
-        if !self.destroyed && !thread::panicking() {
-            panic!("Should have destroyed the surface first with `destroy_surface()`!")
+        if !self.destroyed {
+            leaks::dropped_without_destroying(TrackedObject::Surface(self.id()));
         }
     }
 }
@@ -139,13 +140,15 @@ impl Device {
                 } => Some(self.create_view_info(&size, access, native_widget)),
             };
 
-            Ok(Surface {
+            let surface = Surface {
                 io_surface,
                 size,
                 access,
                 destroyed: false,
                 view_info,
-            })
+            };
+            leaks::track(TrackedObject::Surface(surface.id()));
+            Ok(surface)
         }
     }
 
@@ -228,10 +231,11 @@ impl Device {
 
     /// Destroys a surface.
     ///
-    /// You must explicitly call this method to dispose of a surface. Otherwise, a panic occurs in
-    /// the `drop` method.
+    /// You must explicitly call this method to dispose of a surface. Otherwise, the surface leaks
+    /// when it's dropped, and the leak is reported as chosen with `set_leak_policy()`.
     pub fn destroy_surface(&self, surface: &mut Surface) -> Result<(), Error> {
         surface.destroyed = true;
+        leaks::untrack(TrackedObject::Surface(surface.id()));
         Ok(())
     }
 
@@ -286,8 +290,13 @@ impl Device {
                 .set_contents(view_info.front_surface.obj as id);
             view_info.layer.set_opaque(true);
             view_info.layer.set_contents_opaque(true);
+            let old_surface_id = surface.id();
             surface.io_surface = self.create_io_surface(&size, surface.access);
             surface.size = size;
+            leaks::retrack(
+                TrackedObject::Surface(old_surface_id),
+                TrackedObject::Surface(surface.id()),
+            );
         }
 
         transaction::commit();
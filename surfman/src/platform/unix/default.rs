@@ -23,6 +23,7 @@ pub mod connection {
 pub mod context {
     use crate::platform::generic::multi::context::Context as MultiContext;
     use crate::platform::generic::multi::context::ContextDescriptor as MultiContextDescriptor;
+    use crate::platform::generic::multi::context::Fence as MultiFence;
     use crate::platform::generic::multi::context::NativeContext as MultiNativeContext;
     use crate::platform::generic::multi::device::Device as MultiDevice;
     use crate::platform::unix::generic::device::Device as SWDevice;
@@ -46,7 +47,8 @@ pub mod context {
     /// OpenGL objects may not be shared across contexts directly, but surface textures effectively
     /// allow for sharing of texture data. Contexts are local to a single thread and device.
     ///
-    /// A context must be explicitly destroyed with `destroy_context()`, or a panic will occur.
+    /// A context must be explicitly destroyed with `destroy_context()`. Dropping it without
+    /// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
     pub type Context = MultiContext<HWDevice, SWDevice>;
 
     /// Information needed to create a context. Some APIs call this a "config" or a "pixel format".
@@ -56,6 +58,9 @@ pub mod context {
 
     /// Either a Wayland or an X11 native context
     pub type NativeContext = MultiNativeContext<HWDevice, SWDevice>;
+
+    /// A point in the command stream of a context that other contexts can wait for.
+    pub type Fence = MultiFence<HWDevice, SWDevice>;
 }
 
 /// Thread-local handles to devices.
@@ -113,7 +118,8 @@ pub mod surface {
     ///
     /// Depending on the platform, each surface may be internally double-buffered.
     ///
-    /// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
+    /// Surfaces must be destroyed with the `destroy_surface()` method. Dropping one without
+    /// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
     pub type Surface = MultiSurface<HWDevice, SWDevice>;
 
     /// Represents an OpenGL texture that wraps a surface.
@@ -2,12 +2,12 @@
 //
 //! Represents a connection to a display server.
 
-use super::device::{Adapter, Device, NativeDevice};
+use super::device::{Adapter, AdapterDescriptor, Device, NativeDevice};
 use super::surface::NativeWidget;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::info::GLApi;
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::device::{self, EGLDevice, EGL_FUNCTIONS};
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_SURFACELESS_MESA;
 use crate::Error;
 
@@ -23,6 +23,7 @@ use winit::window::Window;
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 /// Native connections.
@@ -41,6 +42,7 @@ impl Connection {
     /// Opens a surfaceless Mesa display.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        device::load_egl_library()?;
         unsafe {
             EGL_FUNCTIONS.with(|egl| {
                 let egl_display_attributes = [egl::NONE as EGLAttrib];
@@ -73,8 +75,10 @@ impl Connection {
     pub unsafe fn from_native_connection(
         native_connection: NativeConnection,
     ) -> Result<Connection, Error> {
+        device::load_egl_library()?;
         Ok(Connection {
             native_connection: native_connection.0,
+            gl_api: GLApi::GL,
         })
     }
 
@@ -85,22 +89,40 @@ impl Connection {
     }
 
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
+    ///
+    /// This is OpenGL unless OpenGL ES was chosen with `set_gl_api()`.
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Chooses whether devices opened from now on create OpenGL or OpenGL ES contexts.
+    ///
+    /// Mesa supports both, so this is useful to exercise OpenGL ES code paths on the desktop.
+    /// Devices that were already opened are unaffected.
+    #[inline]
+    pub fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        self.gl_api = gl_api;
+        Ok(())
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
-    /// This is an alias for `Connection::create_hardware_adapter()`.
+    /// If EGL reports no hardware device, this returns the low-power adapter, which renders with
+    /// the connection's default device.
     #[inline]
     pub fn create_adapter(&self) -> Result<Adapter, Error> {
-        self.create_hardware_adapter()
+        if Adapter::hardware_device_available() {
+            self.create_hardware_adapter()
+        } else {
+            self.create_low_power_adapter()
+        }
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
-    /// On the OSMesa backend, this returns a software adapter.
+    /// Opening a device with this adapter fails with `NoAdapterFound` if EGL reports no hardware
+    /// device.
     #[inline]
     pub fn create_hardware_adapter(&self) -> Result<Adapter, Error> {
         Ok(Adapter::hardware())
@@ -115,11 +137,31 @@ impl Connection {
     }
 
     /// Returns the "best" adapter on this system, preferring software adapters.
+    ///
+    /// Opening a device with this adapter fails with `NoAdapterFound` if EGL reports no software
+    /// device.
     #[inline]
     pub fn create_software_adapter(&self) -> Result<Adapter, Error> {
         Ok(Adapter::software())
     }
 
+    /// Returns descriptors for all the rendering devices on this system, including software ones.
+    ///
+    /// This requires the `EGL_EXT_device_enumeration` and `EGL_EXT_device_query` extensions;
+    /// if they're unavailable, this returns a `RequiredExtensionUnavailable` error. Opening the
+    /// devices also requires `EGL_EXT_platform_device`; without it, this returns an
+    /// `UnsupportedOnThisPlatform` error. Each device opens its own EGL display, so devices created
+    /// from different descriptors never interfere with one another.
+    pub fn enumerate_adapters(&self) -> Result<Vec<AdapterDescriptor>, Error> {
+        if !device::client_extension_supported("EGL_EXT_platform_device") {
+            return Err(Error::UnsupportedOnThisPlatform);
+        }
+        Ok(EGLDevice::enumerate()?
+            .into_iter()
+            .map(AdapterDescriptor::from_egl_device)
+            .collect())
+    }
+
     /// Opens the hardware device corresponding to the given adapter.
     ///
     /// Device handles are local to a single thread.
@@ -3,18 +3,22 @@
 //! A wrapper around surfaceless Mesa `EGLDisplay`s.
 
 use super::connection::{Connection, NativeConnectionWrapper};
-use crate::{Error, GLApi};
+use super::context::GL_FUNCTIONS;
+use crate::egl;
+use crate::egl::types::{EGLAttrib, EGLint};
+use crate::platform::generic::egl::capabilities;
+use crate::platform::generic::egl::device::{self, EGLDevice, EGL_FUNCTIONS};
+use crate::platform::generic::egl::ffi::EGL_RENDERER_EXT;
+use crate::platform::generic::egl::ffi::{EGL_DRM_RENDER_NODE_FILE_EXT, EGL_PLATFORM_DEVICE_EXT};
+use crate::{DeviceCapabilities, Error, GLApi};
 
-use std::env;
+use std::path::PathBuf;
 use std::sync::Arc;
 
-static MESA_SOFTWARE_RENDERING_ENV_VAR: &'static str = "LIBGL_ALWAYS_SOFTWARE";
-static MESA_DRI_PRIME_ENV_VAR: &'static str = "DRI_PRIME";
-
 /// Represents a hardware display adapter that can be used for rendering (including the CPU).
 ///
 /// Adapters can be sent between threads. To render with an adapter, open a thread-local `Device`.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Adapter {
     #[doc(hidden)]
     Hardware,
@@ -22,6 +26,26 @@ pub enum Adapter {
     HardwarePrime,
     #[doc(hidden)]
     Software,
+    #[doc(hidden)]
+    Device(EGLDevice),
+}
+
+/// Describes a rendering device that EGL knows about.
+///
+/// These are returned by `Connection::enumerate_adapters()`. To render with the device, pass the
+/// `adapter` field to `Connection::create_device()`.
+#[derive(Clone, Debug)]
+pub struct AdapterDescriptor {
+    /// The adapter that opens this device.
+    pub adapter: Adapter,
+    /// The vendor of the device, if the driver reports one.
+    pub vendor: Option<String>,
+    /// The name of the renderer, if the driver reports one.
+    pub renderer: Option<String>,
+    /// The path to the DRM render node of the device (e.g. `/dev/dri/renderD128`), if it has one.
+    pub render_node: Option<PathBuf>,
+    /// Whether this device renders on the CPU.
+    pub software: bool,
 }
 
 impl Adapter {
@@ -40,24 +64,83 @@ impl Adapter {
         Adapter::Software
     }
 
-    pub(crate) fn set_environment_variables(&self) {
+    // Chooses the EGL device that this adapter renders with on the surfaceless backend.
+    //
+    // Software adapters pick the device that Mesa reports as rendering on the CPU. Hardware
+    // adapters pick among the other devices: low-power adapters take the first one, which is
+    // normally the boot GPU, and high-performance adapters take the last one, which is the
+    // discrete GPU on hybrid systems.
+    //
+    // Software and high-performance adapters ask for a particular kind of device, so if EGL can't
+    // enumerate devices or there's no such device, this returns a `NoAdapterFound` error.
+    // Low-power adapters return `None` instead, in which case the connection's own display should
+    // be used.
+    pub(crate) fn egl_device(&self) -> Result<Option<EGLDevice>, Error> {
+        if let Adapter::Device(egl_device) = *self {
+            return Ok(Some(egl_device));
+        }
+
+        let egl_devices = match EGLDevice::enumerate() {
+            Ok(egl_devices) => egl_devices,
+            Err(_) if *self == Adapter::Hardware => return Ok(None),
+            Err(_) => return Err(Error::NoAdapterFound),
+        };
+        let mut hardware_devices = egl_devices
+            .iter()
+            .filter(|egl_device| !egl_device.extension_supported("EGL_MESA_device_software"));
+
         match *self {
-            Adapter::Hardware | Adapter::HardwarePrime => {
-                env::remove_var(MESA_SOFTWARE_RENDERING_ENV_VAR);
-            }
-            Adapter::Software => {
-                env::set_var(MESA_SOFTWARE_RENDERING_ENV_VAR, "1");
-            }
+            Adapter::Software => egl_devices
+                .iter()
+                .find(|egl_device| egl_device.extension_supported("EGL_MESA_device_software"))
+                .cloned()
+                .map(Some)
+                .ok_or(Error::NoAdapterFound),
+            Adapter::Hardware => Ok(hardware_devices.next().cloned()),
+            Adapter::HardwarePrime => hardware_devices
+                .next_back()
+                .cloned()
+                .map(Some)
+                .ok_or(Error::NoAdapterFound),
+            Adapter::Device(_) => unreachable!(),
         }
+    }
 
+    // Chooses the EGL device that this adapter renders with on the X11 and Wayland backends, where
+    // the connection's display renders with the device that the display server picks.
+    //
+    // This is the device from `egl_device()`, except that low-power adapters always return `None`,
+    // meaning that the connection's display should be used. So do high-performance adapters if
+    // there's no hardware device to pick, since the display server's is then the best there is.
+    pub(crate) fn display_server_egl_device(&self) -> Result<Option<EGLDevice>, Error> {
         match *self {
-            Adapter::Software => {}
-            Adapter::Hardware => {
-                env::remove_var(MESA_DRI_PRIME_ENV_VAR);
-            }
-            Adapter::HardwarePrime => {
-                env::set_var(MESA_DRI_PRIME_ENV_VAR, "1");
-            }
+            Adapter::Hardware => Ok(None),
+            Adapter::HardwarePrime => Ok(self.egl_device().unwrap_or(None)),
+            Adapter::Software | Adapter::Device(_) => self.egl_device(),
+        }
+    }
+
+    // Returns true if EGL reports at least one device that doesn't render on the CPU.
+    pub(crate) fn hardware_device_available() -> bool {
+        match EGLDevice::enumerate() {
+            Ok(egl_devices) => egl_devices
+                .iter()
+                .any(|egl_device| !egl_device.extension_supported("EGL_MESA_device_software")),
+            Err(_) => false,
+        }
+    }
+}
+
+impl AdapterDescriptor {
+    pub(crate) fn from_egl_device(egl_device: EGLDevice) -> AdapterDescriptor {
+        AdapterDescriptor {
+            adapter: Adapter::Device(egl_device),
+            vendor: egl_device.query_string(egl::VENDOR as EGLint),
+            renderer: egl_device.query_string(EGL_RENDERER_EXT as EGLint),
+            render_node: egl_device
+                .query_string(EGL_DRM_RENDER_NODE_FILE_EXT as EGLint)
+                .map(PathBuf::from),
+            software: egl_device.extension_supported("EGL_MESA_device_software"),
         }
     }
 }
@@ -68,6 +151,8 @@ impl Adapter {
 pub struct Device {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
     pub(crate) adapter: Adapter,
+    pub(crate) gl_api: GLApi,
+    pub(crate) capabilities: Option<DeviceCapabilities>,
 }
 
 /// Wraps an adapter.
@@ -82,9 +167,16 @@ pub struct NativeDevice {
 impl Device {
     #[inline]
     pub(crate) fn new(connection: &Connection, adapter: &Adapter) -> Result<Device, Error> {
+        // Each adapter gets its own display, so that devices on different adapters can coexist.
+        let native_connection = match adapter.egl_device()? {
+            Some(egl_device) => unsafe { open_device_display(egl_device)? },
+            None => connection.native_connection.clone(),
+        };
         Ok(Device {
-            native_connection: connection.native_connection.clone(),
+            native_connection,
             adapter: (*adapter).clone(),
+            gl_api: connection.gl_api,
+            capabilities: None,
         })
     }
 
@@ -104,6 +196,7 @@ impl Device {
     pub fn connection(&self) -> Connection {
         Connection {
             native_connection: self.native_connection.clone(),
+            gl_api: self.gl_api,
         }
     }
 
@@ -116,6 +209,62 @@ impl Device {
     /// Returns the OpenGL API flavor that this device supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Describes what this device supports.
+    ///
+    /// The first call creates a temporary context, and a surface of each format, to find out, so
+    /// it's relatively expensive. The result is kept, so later calls are cheap. The limits
+    /// reported are those of an OpenGL or OpenGL ES 3.0 context, or of the highest version below
+    /// that if the implementation doesn't support 3.0; contexts of other versions may have
+    /// different ones.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        if let Some(ref capabilities) = self.capabilities {
+            return Ok(capabilities.clone());
+        }
+
+        let context_attributes = DeviceCapabilities::probe_context_attributes();
+        let context_descriptor = self.create_context_descriptor(&context_attributes)?;
+        let capabilities = GL_FUNCTIONS.with(|gl| unsafe {
+            capabilities::probe(
+                gl,
+                self.native_connection.egl_display,
+                &context_descriptor,
+                self.gl_api(),
+                false,
+            )
+        })?;
+        self.capabilities = Some(capabilities.clone());
+        Ok(capabilities)
     }
 }
+
+// Opens and initializes an EGL display that renders with the given device.
+unsafe fn open_device_display(
+    egl_device: EGLDevice,
+) -> Result<Arc<NativeConnectionWrapper>, Error> {
+    if !device::client_extension_supported("EGL_EXT_platform_device") {
+        return Err(Error::UnsupportedOnThisPlatform);
+    }
+
+    EGL_FUNCTIONS.with(|egl| {
+        let egl_display_attributes = [egl::NONE as EGLAttrib];
+        let egl_display = egl.GetPlatformDisplay(
+            EGL_PLATFORM_DEVICE_EXT,
+            egl_device.0 as *mut _,
+            egl_display_attributes.as_ptr(),
+        );
+        if egl_display == egl::NO_DISPLAY {
+            return Err(Error::DeviceOpenFailed);
+        }
+
+        let (mut egl_major_version, mut egl_minor_version) = (0, 0);
+        let ok = egl.Initialize(egl_display, &mut egl_major_version, &mut egl_minor_version);
+        if ok == egl::FALSE {
+            return Err(Error::DeviceOpenFailed);
+        }
+
+        Ok(Arc::new(NativeConnectionWrapper { egl_display }))
+    })
+}
@@ -0,0 +1,341 @@
+// surfman/surfman/src/platform/unix/generic/generic_tests.rs
+//
+//! Unit tests specific to the surfaceless backend, and to the code it shares with the other Unix
+//! backends.
+
+#![allow(missing_docs)]
+
+use super::connection::Connection;
+use super::device::{Adapter, Device};
+use super::share::SurfaceShareHandle;
+use crate::platform::generic::egl::device;
+use crate::platform::generic::egl::dmabuf::{DmaBufDescriptor, DmaBufPlane};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextPriority, DepthFormat, Error};
+use crate::{GLVersion, SurfaceAccess};
+
+use euclid::default::Size2D;
+use std::io::{ErrorKind, Write};
+use std::os::raw::c_void;
+use std::os::unix::io::RawFd;
+use std::os::unix::net::UnixStream;
+
+// Tests that every enumerated device can be opened, gets its own display, and renders.
+#[test]
+pub fn test_enumerate_adapters() {
+    let connection = Connection::new().unwrap();
+    let adapter_descriptors = match connection.enumerate_adapters() {
+        Ok(adapter_descriptors) => adapter_descriptors,
+        Err(Error::RequiredExtensionUnavailable) | Err(Error::UnsupportedOnThisPlatform) => {
+            println!("skipping test_enumerate_adapters: EGL can't enumerate devices here");
+            return;
+        }
+        Err(err) => panic!("Failed to enumerate adapters: {:?}", err),
+    };
+    assert!(!adapter_descriptors.is_empty());
+
+    for adapter_descriptor in adapter_descriptors {
+        let egl_device = match adapter_descriptor.adapter {
+            Adapter::Device(egl_device) => egl_device,
+            ref adapter => panic!("Enumerated a non-device adapter: {:?}", adapter),
+        };
+        if let Some(ref render_node) = adapter_descriptor.render_node {
+            assert!(render_node.starts_with("/dev/dri"));
+        }
+
+        let mut device = connection
+            .create_device(&adapter_descriptor.adapter)
+            .unwrap();
+        assert_ne!(
+            device.native_connection.egl_display,
+            connection.native_connection.egl_display
+        );
+        match device.adapter() {
+            Adapter::Device(device_egl_device) => assert_eq!(device_egl_device, egl_device),
+            adapter => panic!("Device has the wrong adapter: {:?}", adapter),
+        }
+        create_and_destroy_context(&mut device);
+    }
+}
+
+fn create_and_destroy_context(device: &mut Device) {
+    let context_descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::empty(),
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    let mut context = device.create_context(&context_descriptor, None).unwrap();
+    device.make_context_current(&context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that software and high-performance adapters open the kind of device they ask for, or
+// fail with `NoAdapterFound` if there's no such device.
+#[test]
+pub fn test_adapter_selection() {
+    let connection = Connection::new().unwrap();
+    let adapter_descriptors = match connection.enumerate_adapters() {
+        Ok(adapter_descriptors) => adapter_descriptors,
+        Err(_) => {
+            // Without device enumeration, only the low-power adapter can be honored.
+            for adapter in &[
+                connection.create_software_adapter().unwrap(),
+                connection.create_hardware_adapter().unwrap(),
+            ] {
+                match connection.create_device(adapter) {
+                    Err(Error::NoAdapterFound) => {}
+                    Ok(_) => panic!("Opened {:?} without device enumeration!", adapter),
+                    Err(err) => panic!("Failed to create device: {:?}", err),
+                }
+            }
+            let mut device = connection
+                .create_device(&connection.create_low_power_adapter().unwrap())
+                .unwrap();
+            create_and_destroy_context(&mut device);
+            return;
+        }
+    };
+
+    let software_device = adapter_descriptors
+        .iter()
+        .find(|adapter_descriptor| adapter_descriptor.software);
+    let software_adapter = connection.create_software_adapter().unwrap();
+    match (connection.create_device(&software_adapter), software_device) {
+        (Ok(mut device), Some(_)) => {
+            assert_eq!(device.adapter(), Adapter::Software);
+            assert_ne!(
+                device.native_connection.egl_display,
+                connection.native_connection.egl_display
+            );
+            create_and_destroy_context(&mut device);
+        }
+        (Err(Error::NoAdapterFound), None) => {}
+        (Ok(_), None) => panic!("Opened a software device that wasn't enumerated!"),
+        (Err(err), _) => panic!("Failed to create software device: {:?}", err),
+    }
+
+    let hardware_device = adapter_descriptors
+        .iter()
+        .any(|adapter_descriptor| !adapter_descriptor.software);
+    let hardware_adapter = connection.create_hardware_adapter().unwrap();
+    match (connection.create_device(&hardware_adapter), hardware_device) {
+        (Ok(mut device), true) => create_and_destroy_context(&mut device),
+        (Err(Error::NoAdapterFound), false) => {}
+        (Ok(_), false) => panic!("Opened a hardware device that wasn't enumerated!"),
+        (Err(err), _) => panic!("Failed to create hardware device: {:?}", err),
+    }
+
+    // The default adapter always opens, falling back to the low-power one if need be.
+    let adapter = connection.create_adapter().unwrap();
+    assert_eq!(
+        adapter == Adapter::HardwarePrime,
+        hardware_device,
+        "Chose {:?}",
+        adapter
+    );
+    let mut device = connection.create_device(&adapter).unwrap();
+    create_and_destroy_context(&mut device);
+}
+
+// Tests that the EGL loader falls back to later library names, and reports the loader's error for
+// each name when none of them loads.
+#[test]
+pub fn test_egl_library_fallback() {
+    assert!(device::open_first_library(&["libEGL-missing.so.1", "libEGL.so.1"]).is_ok());
+
+    match device::open_first_library(&["libEGL-missing.so.1", "libEGL-missing.so"]) {
+        Err(err @ Error::NoGLLibraryFound(_)) => {
+            let message = err.to_string();
+            assert!(message.starts_with("the system OpenGL library couldn't be loaded: "));
+            assert!(message.contains("libEGL-missing.so.1"), "{}", message);
+            assert!(message.contains("; "), "{}", message);
+            assert!(message.contains("libEGL-missing.so:"), "{}", message);
+        }
+        Err(err) => panic!("Expected `NoGLLibraryFound` but got {:?}!", err),
+        Ok(_) => panic!("Loaded a library that doesn't exist!"),
+    }
+}
+
+// Tests that a share handle survives the trip through a socket, with working copies of its file
+// descriptors. This uses pipes instead of dma-bufs, so it runs without a GPU.
+#[test]
+pub fn test_surface_share_handle_round_trip() {
+    let (sender, receiver) = UnixStream::pair().unwrap();
+    let (read_fds, write_fds): (Vec<RawFd>, Vec<RawFd>) = (0..2).map(|_| make_pipe()).unzip();
+
+    let handle = SurfaceShareHandle::from_descriptor(DmaBufDescriptor {
+        size: Size2D::new(640, 480),
+        fourcc: 0x3432_4241,
+        modifier: 0x0100_0000_0000_0002,
+        planes: write_fds
+            .iter()
+            .enumerate()
+            .map(|(plane_index, &fd)| DmaBufPlane {
+                fd,
+                offset: plane_index as u32 * 1024,
+                stride: 640 * 4,
+            })
+            .collect(),
+    });
+    handle.send(&sender).unwrap();
+    drop(handle);
+
+    let received_handle = SurfaceShareHandle::recv(&receiver).unwrap();
+    let descriptor = received_handle.descriptor();
+    assert_eq!(descriptor.size, Size2D::new(640, 480));
+    assert_eq!(descriptor.fourcc, 0x3432_4241);
+    assert_eq!(descriptor.modifier, 0x0100_0000_0000_0002);
+    assert_eq!(descriptor.planes.len(), 2);
+    for (plane_index, plane) in descriptor.planes.iter().enumerate() {
+        assert_eq!(plane.offset, plane_index as u32 * 1024);
+        assert_eq!(plane.stride, 640 * 4);
+
+        // The received file descriptor refers to the same pipe.
+        unsafe {
+            let byte = [plane_index as u8 + 1];
+            assert_eq!(libc::write(plane.fd, byte.as_ptr() as *const c_void, 1), 1);
+            let mut read_byte = [0u8];
+            let read = libc::read(
+                read_fds[plane_index],
+                read_byte.as_mut_ptr() as *mut c_void,
+                1,
+            );
+            assert_eq!(read, 1);
+            assert_eq!(read_byte, byte);
+        }
+    }
+
+    // Dropping the handle closes its file descriptors, which leaves the pipes without writers.
+    drop(received_handle);
+    for &read_fd in &read_fds {
+        assert!(pipe_has_no_writers(read_fd));
+        unsafe {
+            libc::close(read_fd);
+        }
+    }
+}
+
+// Tests that malformed and truncated messages are rejected.
+#[test]
+pub fn test_surface_share_handle_malformed_messages() {
+    let mut valid_header = vec![];
+    for value in &[640u32, 480, 0x3432_4241, 0, 0, 1] {
+        valid_header.extend_from_slice(&value.to_le_bytes());
+    }
+    let one_plane = [0u8; 8];
+
+    let messages: Vec<Vec<u8>> = vec![
+        // Truncated header.
+        valid_header[..10].to_vec(),
+        // A header without its plane.
+        valid_header.clone(),
+        // A plane without its file descriptor.
+        [&valid_header[..], &one_plane[..]].concat(),
+        // A message longer than any valid one.
+        vec![0xff; 256],
+    ];
+    for message in messages {
+        let (mut sender, receiver) = UnixStream::pair().unwrap();
+        sender.write_all(&message).unwrap();
+        let err = SurfaceShareHandle::recv(&receiver).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData, "{:?}", message);
+    }
+
+    // A closed socket isn't a handle either.
+    let (sender, receiver) = UnixStream::pair().unwrap();
+    drop(sender);
+    let err = SurfaceShareHandle::recv(&receiver).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+}
+
+// Tests that the file descriptors that come with a malformed message are closed.
+#[test]
+pub fn test_surface_share_handle_closes_fds_on_error() {
+    let (sender, receiver) = UnixStream::pair().unwrap();
+    let (read_fd, write_fd) = make_pipe();
+
+    // A zero-sized surface is malformed.
+    let handle = SurfaceShareHandle::from_descriptor(DmaBufDescriptor {
+        size: Size2D::new(0, 0),
+        fourcc: 0x3432_4241,
+        modifier: 0,
+        planes: vec![DmaBufPlane {
+            fd: write_fd,
+            offset: 0,
+            stride: 0,
+        }],
+    });
+    handle.send(&sender).unwrap();
+    drop(handle);
+
+    let err = SurfaceShareHandle::recv(&receiver).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(pipe_has_no_writers(read_fd));
+    unsafe {
+        libc::close(read_fd);
+    }
+}
+
+// Tests that dma-bufs in formats that no surface format matches are rejected instead of being
+// imported as RGBA8.
+#[test]
+pub fn test_import_dmabuf_unknown_format() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection.create_adapter().unwrap();
+    let mut device = connection.create_device(&adapter).unwrap();
+    let context_descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::empty(),
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    let mut context = device.create_context(&context_descriptor, None).unwrap();
+
+    let (read_fd, write_fd) = make_pipe();
+    let descriptor = DmaBufDescriptor {
+        size: Size2D::new(640, 480),
+        // `DRM_FORMAT_NV12`, a two-plane YUV format.
+        fourcc: 0x3231_564e,
+        modifier: 0,
+        planes: vec![DmaBufPlane {
+            fd: read_fd,
+            offset: 0,
+            stride: 640,
+        }],
+    };
+    match device.create_surface_from_dmabuf(&context, SurfaceAccess::GPUOnly, &descriptor) {
+        Err(Error::UnsupportedOnThisPlatform) => {}
+        Err(err) => panic!("Expected `UnsupportedOnThisPlatform` but got {:?}!", err),
+        Ok(_) => panic!("Imported a dma-buf in an unknown format!"),
+    }
+    unsafe {
+        libc::close(read_fd);
+        libc::close(write_fd);
+    }
+
+    device.destroy_context(&mut context).unwrap();
+}
+
+fn make_pipe() -> (RawFd, RawFd) {
+    let mut fds = [-1; 2];
+    unsafe {
+        assert_eq!(libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC), 0);
+    }
+    (fds[0], fds[1])
+}
+
+// Returns true if reading from the pipe hits end-of-file, meaning that every write end is closed.
+fn pipe_has_no_writers(read_fd: RawFd) -> bool {
+    unsafe {
+        let flags = libc::fcntl(read_fd, libc::F_GETFL);
+        libc::fcntl(read_fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+        let mut byte = [0u8];
+        libc::read(read_fd, byte.as_mut_ptr() as *mut c_void, 1) == 0
+    }
+}
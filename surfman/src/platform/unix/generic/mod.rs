@@ -6,6 +6,7 @@
 pub mod connection;
 pub mod context;
 pub mod device;
+pub(crate) mod share;
 pub mod surface;
 
 #[path = "../../../implementation/mod.rs"]
@@ -14,3 +15,6 @@ mod implementation;
 #[cfg(test)]
 #[path = "../../../tests.rs"]
 mod tests;
+
+#[cfg(test)]
+mod generic_tests;
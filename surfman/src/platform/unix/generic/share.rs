@@ -0,0 +1,237 @@
+// surfman/surfman/src/platform/unix/generic/share.rs
+//
+//! Sharing surfaces between processes by sending dma-bufs over Unix sockets.
+
+use crate::platform::generic::egl::dmabuf::{DmaBufDescriptor, DmaBufPlane};
+
+use euclid::default::Size2D;
+use std::convert::TryInto;
+use std::io::{self, Error as IOError, ErrorKind};
+use std::mem;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::ptr;
+
+// The most planes that a shared surface can have.
+const MAX_PLANES: usize = 4;
+
+// The size of the serialized metadata: width, height, fourcc, modifier, and plane count, followed
+// by the offset and stride of each plane.
+const HEADER_SIZE: usize = 4 + 4 + 4 + 8 + 4;
+const PLANE_SIZE: usize = 4 + 4;
+const MAX_MESSAGE_SIZE: usize = HEADER_SIZE + PLANE_SIZE * MAX_PLANES;
+
+/// A handle to a surface that can be sent to another process.
+///
+/// The handle owns the dma-buf file descriptors behind the surface and closes them when dropped.
+/// Send it over a Unix socket with `send()`, receive it in the other process with `recv()`, and
+/// turn it into a surface texture there with `Device::import_shared_surface()`.
+#[derive(Debug)]
+pub struct SurfaceShareHandle {
+    descriptor: DmaBufDescriptor,
+}
+
+impl SurfaceShareHandle {
+    // Takes ownership of the file descriptors in the given descriptor.
+    pub(crate) fn from_descriptor(descriptor: DmaBufDescriptor) -> SurfaceShareHandle {
+        SurfaceShareHandle { descriptor }
+    }
+
+    /// Returns the dma-buf that this handle refers to.
+    ///
+    /// The file descriptors remain owned by this handle.
+    #[inline]
+    pub fn descriptor(&self) -> &DmaBufDescriptor {
+        &self.descriptor
+    }
+
+    /// Sends this handle over a Unix socket, passing the file descriptors with `SCM_RIGHTS`.
+    ///
+    /// The receiving process gets its own copies of the file descriptors, so this handle remains
+    /// valid.
+    pub fn send(&self, socket: &UnixStream) -> io::Result<()> {
+        let planes = &self.descriptor.planes;
+        if planes.len() > MAX_PLANES {
+            return Err(IOError::new(ErrorKind::InvalidInput, "too many planes"));
+        }
+
+        let mut message = Vec::with_capacity(MAX_MESSAGE_SIZE);
+        message.extend_from_slice(&self.descriptor.size.width.to_le_bytes());
+        message.extend_from_slice(&self.descriptor.size.height.to_le_bytes());
+        message.extend_from_slice(&self.descriptor.fourcc.to_le_bytes());
+        message.extend_from_slice(&self.descriptor.modifier.to_le_bytes());
+        message.extend_from_slice(&(planes.len() as u32).to_le_bytes());
+        for plane in planes {
+            message.extend_from_slice(&plane.offset.to_le_bytes());
+            message.extend_from_slice(&plane.stride.to_le_bytes());
+        }
+
+        let fds: Vec<RawFd> = planes.iter().map(|plane| plane.fd).collect();
+        unsafe {
+            let mut iov = libc::iovec {
+                iov_base: message.as_ptr() as *mut _,
+                iov_len: message.len(),
+            };
+            let fds_size = mem::size_of::<RawFd>() * fds.len();
+            let mut control = vec![0u8; libc::CMSG_SPACE(fds_size as u32) as usize];
+
+            let mut msghdr: libc::msghdr = mem::zeroed();
+            msghdr.msg_iov = &mut iov;
+            msghdr.msg_iovlen = 1;
+            msghdr.msg_control = control.as_mut_ptr() as *mut _;
+            msghdr.msg_controllen = control.len() as _;
+
+            let cmsghdr = libc::CMSG_FIRSTHDR(&msghdr);
+            (*cmsghdr).cmsg_level = libc::SOL_SOCKET;
+            (*cmsghdr).cmsg_type = libc::SCM_RIGHTS;
+            (*cmsghdr).cmsg_len = libc::CMSG_LEN(fds_size as u32) as _;
+            ptr::copy_nonoverlapping(
+                fds.as_ptr() as *const u8,
+                libc::CMSG_DATA(cmsghdr),
+                fds_size,
+            );
+
+            let sent = libc::sendmsg(socket.as_raw_fd(), &msghdr, 0);
+            if sent < 0 {
+                return Err(IOError::last_os_error());
+            }
+            if sent as usize != message.len() {
+                return Err(IOError::new(ErrorKind::WriteZero, "short write"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Receives a handle sent with `send()` from a Unix socket.
+    pub fn recv(socket: &UnixStream) -> io::Result<SurfaceShareHandle> {
+        let mut message = [0u8; MAX_MESSAGE_SIZE];
+        let mut fds: Vec<RawFd> = vec![];
+        let received = unsafe {
+            let mut iov = libc::iovec {
+                iov_base: message.as_mut_ptr() as *mut _,
+                iov_len: message.len(),
+            };
+            let max_fds_size = mem::size_of::<RawFd>() * MAX_PLANES;
+            let mut control = vec![0u8; libc::CMSG_SPACE(max_fds_size as u32) as usize];
+
+            let mut msghdr: libc::msghdr = mem::zeroed();
+            msghdr.msg_iov = &mut iov;
+            msghdr.msg_iovlen = 1;
+            msghdr.msg_control = control.as_mut_ptr() as *mut _;
+            msghdr.msg_controllen = control.len() as _;
+
+            let received = libc::recvmsg(socket.as_raw_fd(), &mut msghdr, libc::MSG_CMSG_CLOEXEC);
+            if received < 0 {
+                return Err(IOError::last_os_error());
+            }
+
+            // Take ownership of any file descriptors we got, even if the message turns out to be
+            // malformed, so that we don't leak them.
+            let mut cmsghdr = libc::CMSG_FIRSTHDR(&msghdr);
+            while !cmsghdr.is_null() {
+                if (*cmsghdr).cmsg_level == libc::SOL_SOCKET
+                    && (*cmsghdr).cmsg_type == libc::SCM_RIGHTS
+                {
+                    let data_size = (*cmsghdr).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                    let data = libc::CMSG_DATA(cmsghdr) as *const RawFd;
+                    for fd_index in 0..(data_size / mem::size_of::<RawFd>()) {
+                        fds.push(ptr::read_unaligned(data.add(fd_index)));
+                    }
+                }
+                cmsghdr = libc::CMSG_NXTHDR(&msghdr, cmsghdr);
+            }
+
+            let error = if received == 0 {
+                Some(IOError::new(ErrorKind::UnexpectedEof, "socket closed"))
+            } else if msghdr.msg_flags & libc::MSG_TRUNC != 0 {
+                Some(IOError::new(ErrorKind::InvalidData, "message too long"))
+            } else if msghdr.msg_flags & libc::MSG_CTRUNC != 0 {
+                Some(IOError::new(
+                    ErrorKind::InvalidData,
+                    "too many file descriptors",
+                ))
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                close_all(&fds);
+                return Err(error);
+            }
+            received as usize
+        };
+
+        match parse_message(&message[..received], &fds) {
+            Ok(descriptor) => Ok(SurfaceShareHandle { descriptor }),
+            Err(err) => {
+                close_all(&fds);
+                Err(err)
+            }
+        }
+    }
+}
+
+impl Drop for SurfaceShareHandle {
+    fn drop(&mut self) {
+        let fds: Vec<RawFd> = self
+            .descriptor
+            .planes
+            .iter()
+            .map(|plane| plane.fd)
+            .collect();
+        close_all(&fds);
+    }
+}
+
+fn parse_message(message: &[u8], fds: &[RawFd]) -> io::Result<DmaBufDescriptor> {
+    let malformed = || IOError::new(ErrorKind::InvalidData, "malformed surface share handle");
+    if message.len() < HEADER_SIZE {
+        return Err(malformed());
+    }
+
+    let read_u32 =
+        |offset: usize| u32::from_le_bytes(message[offset..offset + 4].try_into().unwrap());
+    let width = read_u32(0) as i32;
+    let height = read_u32(4) as i32;
+    let fourcc = read_u32(8);
+    let modifier = u64::from_le_bytes(message[12..20].try_into().unwrap());
+    let plane_count = read_u32(20) as usize;
+    if width <= 0
+        || height <= 0
+        || plane_count == 0
+        || plane_count > MAX_PLANES
+        || plane_count != fds.len()
+        || message.len() != HEADER_SIZE + PLANE_SIZE * plane_count
+    {
+        return Err(malformed());
+    }
+
+    let planes = fds
+        .iter()
+        .enumerate()
+        .map(|(plane_index, &fd)| {
+            let plane_offset = HEADER_SIZE + PLANE_SIZE * plane_index;
+            DmaBufPlane {
+                fd,
+                offset: read_u32(plane_offset),
+                stride: read_u32(plane_offset + 4),
+            }
+        })
+        .collect();
+    Ok(DmaBufDescriptor {
+        size: Size2D::new(width, height),
+        fourcc,
+        modifier,
+        planes,
+    })
+}
+
+fn close_all(fds: &[RawFd]) {
+    // Planes often share a buffer, so only close each file descriptor once.
+    for (fd_index, &fd) in fds.iter().enumerate() {
+        if fd >= 0 && !fds[..fd_index].contains(&fd) {
+            unsafe {
+                libc::close(fd);
+            }
+        }
+    }
+}
@@ -6,11 +6,14 @@ use super::context::{Context, GL_FUNCTIONS};
 use super::device::Device;
 use crate::gl;
 use crate::gl::types::{GLenum, GLuint};
-use crate::platform::generic::egl::surface::{EGLBackedSurface, EGLSurfaceTexture};
-use crate::{Error, SurfaceAccess, SurfaceInfo, SurfaceType};
+use crate::platform::generic::egl::surface::EGLSurfaceTexture;
+use crate::platform::generic::egl::surface::{EGLBackedSurface, EGLSurfaceDataGuard};
+use crate::{Error, SurfaceAccess, SurfaceFormat, SurfaceInfo, SurfaceType};
 
 use euclid::default::Size2D;
-use std::marker::PhantomData;
+
+pub use crate::platform::generic::egl::dmabuf::{DmaBufDescriptor, DmaBufPlane};
+pub use crate::platform::unix::generic::share::SurfaceShareHandle;
 
 // FIXME(pcwalton): Is this right, or should it be `TEXTURE_EXTERNAL_OES`?
 const SURFACE_GL_TEXTURE_TARGET: GLenum = gl::TEXTURE_2D;
@@ -30,7 +33,8 @@ const SURFACE_GL_TEXTURE_TARGET: GLenum = gl::TEXTURE_2D;
 ///
 /// Depending on the platform, each surface may be internally double-buffered.
 ///
-/// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
+/// Surfaces must be destroyed with the `destroy_surface()` method. Dropping one without destroying
+/// it leaks it and logs an error, unless leaks are ignored with `set_leak_policy()`.
 #[derive(Debug)]
 pub struct Surface(pub(crate) EGLBackedSurface);
 
@@ -60,11 +64,27 @@ impl Device {
     pub fn create_surface(
         &mut self,
         context: &Context,
-        _: SurfaceAccess,
+        access: SurfaceAccess,
+        surface_type: SurfaceType<NativeWidget>,
+    ) -> Result<Surface, Error> {
+        self.create_surface_with_format(context, access, surface_type, SurfaceFormat::RGBA8)
+    }
+
+    /// Creates a surface whose color buffer has the given format.
+    ///
+    /// If the driver can't allocate or render to a surface of the given format, this returns an
+    /// `UnsupportedSurfaceFormat` error.
+    pub fn create_surface_with_format(
+        &mut self,
+        context: &Context,
+        access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
+        format: SurfaceFormat,
     ) -> Result<Surface, Error> {
         match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+            SurfaceType::Generic { size } => {
+                self.create_generic_surface(context, &size, access, format)
+            }
             SurfaceType::Widget { .. } => Err(Error::UnsupportedOnThisPlatform),
         }
     }
@@ -73,22 +93,82 @@ impl Device {
         &mut self,
         context: &Context,
         size: &Size2D<i32>,
+        access: SurfaceAccess,
+        format: SurfaceFormat,
     ) -> Result<Surface, Error> {
         let _guard = self.temporarily_make_context_current(context)?;
         let context_descriptor = self.context_descriptor(context);
         let context_attributes = self.context_descriptor_attributes(&context_descriptor);
         GL_FUNCTIONS.with(|gl| {
-            Ok(Surface(EGLBackedSurface::new_generic(
+            EGLBackedSurface::new_generic(
                 gl,
                 self.native_connection.egl_display,
                 context.0.egl_context,
                 context.0.id,
                 &context_attributes,
+                self.gl_api(),
                 size,
-            )))
+                access,
+                format,
+            )
+            .map(Surface)
+        })
+    }
+
+    /// Creates a generic surface that renders to an imported dma-buf.
+    ///
+    /// The surface belongs to the given context and behaves like any other generic surface, except
+    /// that its pixels live in the dma-buf. This requires the `EGL_EXT_image_dma_buf_import`
+    /// extension, plus `EGL_EXT_image_dma_buf_import_modifiers` if the descriptor specifies an
+    /// explicit format modifier; if they're unavailable, this returns a
+    /// `RequiredExtensionUnavailable` error. Dma-bufs whose fourcc format has no `SurfaceFormat`
+    /// counterpart can't be imported, and return an `UnsupportedOnThisPlatform` error. The file
+    /// descriptors are not consumed and may be closed once this method returns.
+    pub fn create_surface_from_dmabuf(
+        &mut self,
+        context: &Context,
+        access: SurfaceAccess,
+        descriptor: &DmaBufDescriptor,
+    ) -> Result<Surface, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        let context_descriptor = self.context_descriptor(context);
+        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+        GL_FUNCTIONS.with(|gl| {
+            EGLBackedSurface::new_from_dmabuf(
+                gl,
+                self.native_connection.egl_display,
+                context.0.egl_context,
+                context.0.id,
+                &context_attributes,
+                self.gl_api(),
+                descriptor,
+                access,
+            )
+            .map(Surface)
         })
     }
 
+    /// Creates a surface texture that samples from an imported dma-buf.
+    ///
+    /// This is a shortcut for `create_surface_from_dmabuf()` followed by
+    /// `create_surface_texture()`. Destroying the surface texture returns a surface that must then
+    /// be destroyed with `destroy_surface()` on the same context.
+    pub fn create_surface_texture_from_dmabuf(
+        &mut self,
+        context: &mut Context,
+        descriptor: &DmaBufDescriptor,
+    ) -> Result<SurfaceTexture, Error> {
+        let surface =
+            self.create_surface_from_dmabuf(context, SurfaceAccess::GPUOnly, descriptor)?;
+        match self.create_surface_texture(context, surface) {
+            Ok(surface_texture) => Ok(surface_texture),
+            Err((err, mut surface)) => {
+                self.destroy_surface(context, &mut surface)?;
+                Err(err)
+            }
+        }
+    }
+
     /// Creates a surface texture from an existing generic surface for use with the given context.
     ///
     /// The surface texture is local to the supplied context and takes ownership of the surface.
@@ -120,8 +200,8 @@ impl Device {
     /// The supplied context must be the context the surface is associated with, or this returns
     /// an `IncompatibleSurface` error.
     ///
-    /// You must explicitly call this method to dispose of a surface. Otherwise, a panic occurs in
-    /// the `drop` method.
+    /// You must explicitly call this method to dispose of a surface. Otherwise, the surface leaks
+    /// when it's dropped, and the leak is reported as chosen with `set_leak_policy()`.
     pub fn destroy_surface(
         &self,
         context: &mut Context,
@@ -166,21 +246,88 @@ impl Device {
             .present(self.native_connection.egl_display, context.0.egl_context)
     }
 
-    /// Resizes a widget surface.
+    /// Resizes a surface.
+    ///
+    /// Generic surfaces are given new storage at the new size, keeping their framebuffer object;
+    /// their contents become undefined, and their surface ID changes. If the new storage can't be
+    /// allocated, a `SurfaceCreationFailed` error is returned and the surface is left unchanged.
+    /// Surfaces imported from dma-bufs don't own their storage and can't be resized; this returns
+    /// an `UnsupportedOnThisPlatform` error for them.
+    ///
+    /// The supplied context must be the context the surface is associated with, or this returns
+    /// an `IncompatibleSurface` error.
     pub fn resize_surface(
         &self,
-        _context: &Context,
+        context: &Context,
         surface: &mut Surface,
         size: Size2D<i32>,
     ) -> Result<(), Error> {
-        surface.0.size = size;
-        Ok(())
+        let _guard = self.temporarily_make_context_current(context)?;
+        let context_descriptor = self.context_descriptor(context);
+        let context_attributes = self.context_descriptor_attributes(&context_descriptor);
+        GL_FUNCTIONS.with(|gl| {
+            surface.0.resize(
+                gl,
+                self.native_connection.egl_display,
+                context.0.egl_context,
+                context.0.id,
+                &context_attributes,
+                size,
+            )
+        })
     }
 
     /// Returns a pointer to the underlying surface data for reading or writing by the CPU.
-    #[inline]
-    pub fn lock_surface_data<'s>(&self, _: &'s mut Surface) -> Result<SurfaceDataGuard<'s>, Error> {
-        Err(Error::Unimplemented)
+    ///
+    /// The surface must be a generic surface created with CPU access, and it must not be bound to
+    /// a context. A context from this device must be current on this thread; the surface contents
+    /// are read back through that context and written back when the guard is dropped.
+    pub fn lock_surface_data<'s>(
+        &self,
+        surface: &'s mut Surface,
+    ) -> Result<SurfaceDataGuard<'s>, Error> {
+        GL_FUNCTIONS.with(move |gl| {
+            surface
+                .0
+                .lock_data(gl, self.native_connection.egl_display)
+                .map(SurfaceDataGuard)
+        })
+    }
+
+    /// Exports the buffer behind a generic surface as a dma-buf, so that it can be handed to a
+    /// video encoder or another process without a readback.
+    ///
+    /// This requires the `EGL_MESA_image_dma_buf_export` extension; if it's unavailable, this
+    /// returns a `RequiredExtensionUnavailable` error. Calling this method on a widget surface
+    /// returns a `WidgetAttached` error. The caller owns the returned file descriptors and must
+    /// close them. Rendering to the surface must be flushed before another device reads from the
+    /// buffer.
+    pub fn export_surface_dmabuf(&self, surface: &Surface) -> Result<DmaBufDescriptor, Error> {
+        surface.0.export_dmabuf(self.native_connection.egl_display)
+    }
+
+    /// Creates a handle that shares the buffer behind a generic surface with another process.
+    ///
+    /// The buffer is exported as a dma-buf, so the requirements of `export_surface_dmabuf()`
+    /// apply. Rendering to the surface must be flushed before the other process reads from it.
+    pub fn create_surface_share_handle(
+        &self,
+        surface: &Surface,
+    ) -> Result<SurfaceShareHandle, Error> {
+        self.export_surface_dmabuf(surface)
+            .map(SurfaceShareHandle::from_descriptor)
+    }
+
+    /// Creates a surface texture that samples from a surface shared by another process.
+    ///
+    /// The share handle is typically received with `SurfaceShareHandle::recv()`. See
+    /// `create_surface_texture_from_dmabuf()` for the requirements and how to destroy the result.
+    pub fn import_shared_surface(
+        &mut self,
+        context: &mut Context,
+        share_handle: &SurfaceShareHandle,
+    ) -> Result<SurfaceTexture, Error> {
+        self.create_surface_texture_from_dmabuf(context, share_handle.descriptor())
     }
 
     /// Returns the OpenGL texture target needed to read from this surface texture.
@@ -208,9 +355,40 @@ impl Device {
     pub fn surface_texture_object(&self, surface_texture: &SurfaceTexture) -> GLuint {
         surface_texture.0.texture_object
     }
+
+    /// Returns the OpenGL texture object containing the depth buffer of this surface, or 0 if the
+    /// surface has no sampleable depth buffer.
+    ///
+    /// Surfaces have sampleable depth buffers if their context was created with both the `DEPTH`
+    /// and `SAMPLEABLE_DEPTH` flags. The texture uses nearest-neighbor filtering, and it is only
+    /// legal to read from it.
+    #[inline]
+    pub fn surface_texture_depth_object(&self, surface_texture: &SurfaceTexture) -> GLuint {
+        surface_texture.0.depth_texture_object
+    }
 }
 
 /// Represents the CPU view of the pixel data of this surface.
-pub struct SurfaceDataGuard<'a> {
-    phantom: PhantomData<&'a ()>,
+pub struct SurfaceDataGuard<'a>(EGLSurfaceDataGuard<'a>);
+
+impl<'a> SurfaceDataGuard<'a> {
+    /// Returns the number of bytes per row of the surface.
+    #[inline]
+    pub fn stride(&self) -> usize {
+        self.0.stride()
+    }
+
+    /// Returns a mutable slice of the pixel data in this surface, in the format of the surface.
+    ///
+    /// Rows are stored bottom to top, following OpenGL conventions.
+    #[inline]
+    pub fn data(&mut self) -> &mut [u8] {
+        self.0.data()
+    }
+}
+
+impl<'a> Drop for SurfaceDataGuard<'a> {
+    fn drop(&mut self) {
+        GL_FUNCTIONS.with(|gl| self.0.unlock(gl))
+    }
 }
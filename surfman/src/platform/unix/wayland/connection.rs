@@ -7,7 +7,7 @@ use super::surface::NativeWidget;
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::info::GLApi;
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::device::{self, EGLDevice, EGL_FUNCTIONS};
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_WAYLAND_KHR;
 use crate::Error;
 
@@ -26,11 +26,17 @@ use winit::window::Window;
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 pub(crate) struct NativeConnectionWrapper {
     pub(crate) egl_display: EGLDisplay,
+    // The Wayland display, if we know it. Connections wrapping an EGL display don't.
     wayland_display: Option<*mut wl_display>,
+    wayland_display_is_owned: bool,
+    // The connection whose Wayland display this one borrows, if it renders with a device other
+    // than the one the display server picked.
+    _parent: Option<Arc<NativeConnectionWrapper>>,
 }
 
 /// An EGL display wrapping a Wayland display.
@@ -42,6 +48,7 @@ impl Connection {
     /// Connects to the default Wayland server.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        device::load_egl_library()?;
         unsafe {
             let wayland_display = (WAYLAND_CLIENT_HANDLE.wl_display_connect)(ptr::null());
             Connection::from_wayland_display(wayland_display, true)
@@ -56,7 +63,8 @@ impl Connection {
     pub unsafe fn from_native_connection(
         native_connection: NativeConnection,
     ) -> Result<Connection, Error> {
-        Connection::from_egl_display(native_connection.0, None)
+        device::load_egl_library()?;
+        Connection::from_egl_display(native_connection.0, None, false)
     }
 
     /// Returns the underlying native connection.
@@ -66,9 +74,21 @@ impl Connection {
     }
 
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
+    ///
+    /// This is OpenGL unless OpenGL ES was chosen with `set_gl_api()`.
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Chooses whether devices opened from now on create OpenGL or OpenGL ES contexts.
+    ///
+    /// Mesa supports both, so this is useful to exercise OpenGL ES code paths on the desktop.
+    /// Devices that were already opened are unaffected.
+    #[inline]
+    pub fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        self.gl_api = gl_api;
+        Ok(())
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
@@ -140,24 +160,23 @@ impl Connection {
             let ok = egl.Initialize(egl_display, &mut egl_major_version, &mut egl_minor_version);
             assert_ne!(ok, egl::FALSE);
 
-            let owned_display = if is_owned {
-                Some(wayland_display)
-            } else {
-                None
-            };
-            Connection::from_egl_display(egl_display, owned_display)
+            Connection::from_egl_display(egl_display, Some(wayland_display), is_owned)
         })
     }
 
     fn from_egl_display(
         egl_display: EGLDisplay,
         wayland_display: Option<*mut wl_display>,
+        wayland_display_is_owned: bool,
     ) -> Result<Connection, Error> {
         Ok(Connection {
             native_connection: Arc::new(NativeConnectionWrapper {
                 egl_display,
                 wayland_display,
+                wayland_display_is_owned,
+                _parent: None,
             }),
+            gl_api: GLApi::GL,
         })
     }
 
@@ -239,13 +258,43 @@ impl Connection {
 impl Drop for NativeConnectionWrapper {
     fn drop(&mut self) {
         unsafe {
-            if let Some(wayland_display) = self.wayland_display {
-                (WAYLAND_CLIENT_HANDLE.wl_display_disconnect)(wayland_display);
+            match self.wayland_display {
+                Some(wayland_display) if self.wayland_display_is_owned => {
+                    (WAYLAND_CLIENT_HANDLE.wl_display_disconnect)(wayland_display);
+                }
+                _ => {}
             }
         }
     }
 }
 
+impl NativeConnectionWrapper {
+    // Opens another EGL display on the same Wayland display that renders with the given device.
+    // Returns `None` if EGL can't choose the device of a Wayland display, or if the connection
+    // was created from an EGL display and so the Wayland display is unknown.
+    pub(crate) unsafe fn open_with_egl_device(
+        native_connection: &Arc<NativeConnectionWrapper>,
+        egl_device: EGLDevice,
+    ) -> Result<Option<Arc<NativeConnectionWrapper>>, Error> {
+        let wayland_display = match native_connection.wayland_display {
+            Some(wayland_display) => wayland_display,
+            None => return Ok(None),
+        };
+        let egl_display = match egl_device
+            .open_platform_display(EGL_PLATFORM_WAYLAND_KHR, wayland_display as *mut c_void)?
+        {
+            Some(egl_display) => egl_display,
+            None => return Ok(None),
+        };
+        Ok(Some(Arc::new(NativeConnectionWrapper {
+            egl_display,
+            wayland_display: Some(wayland_display),
+            wayland_display_is_owned: false,
+            _parent: Some(native_connection.clone()),
+        })))
+    }
+}
+
 impl NativeConnection {
     /// Returns the current native connection, if applicable.
     #[inline]
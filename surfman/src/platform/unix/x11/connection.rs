@@ -8,7 +8,7 @@ use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay};
 use crate::error::Error;
 use crate::info::GLApi;
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::device::{self, EGLDevice, EGL_FUNCTIONS};
 use crate::platform::generic::egl::ffi::EGL_PLATFORM_X11_KHR;
 use crate::platform::unix::generic::device::Adapter;
 
@@ -37,6 +37,7 @@ lazy_static! {
 #[derive(Clone)]
 pub struct Connection {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
+    pub(crate) gl_api: GLApi,
 }
 
 unsafe impl Send for Connection {}
@@ -45,6 +46,9 @@ pub(crate) struct NativeConnectionWrapper {
     pub(crate) egl_display: EGLDisplay,
     x11_display: *mut Display,
     x11_display_is_owned: bool,
+    // The connection whose X11 display this one borrows, if it renders with a device other than
+    // the one the display server picked.
+    _parent: Option<Arc<NativeConnectionWrapper>>,
 }
 
 /// Wrapper for an X11 and EGL display.
@@ -76,6 +80,7 @@ impl Connection {
     /// Connects to the default display.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        device::load_egl_library()?;
         unsafe {
             *X_THREADS_INIT;
 
@@ -91,7 +96,9 @@ impl Connection {
                     x11_display,
                     x11_display_is_owned: true,
                     egl_display,
+                    _parent: None,
                 }),
+                gl_api: GLApi::GL,
             })
         }
     }
@@ -109,12 +116,15 @@ impl Connection {
     pub unsafe fn from_native_connection(
         native_connection: NativeConnection,
     ) -> Result<Connection, Error> {
+        device::load_egl_library()?;
         Ok(Connection {
             native_connection: Arc::new(NativeConnectionWrapper {
                 egl_display: native_connection.egl_display,
                 x11_display: native_connection.x11_display,
                 x11_display_is_owned: false,
+                _parent: None,
             }),
+            gl_api: GLApi::GL,
         })
     }
 
@@ -126,7 +136,9 @@ impl Connection {
                     egl_display,
                     x11_display,
                     x11_display_is_owned: is_owned,
+                    _parent: None,
                 }),
+                gl_api: GLApi::GL,
             })
         }
     }
@@ -141,9 +153,21 @@ impl Connection {
     }
 
     /// Returns the OpenGL API flavor that this connection supports (OpenGL or OpenGL ES).
+    ///
+    /// This is OpenGL unless OpenGL ES was chosen with `set_gl_api()`.
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Chooses whether devices opened from now on create OpenGL or OpenGL ES contexts.
+    ///
+    /// Mesa supports both, so this is useful to exercise OpenGL ES code paths on the desktop.
+    /// Devices that were already opened are unaffected.
+    #[inline]
+    pub fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        self.gl_api = gl_api;
+        Ok(())
     }
 
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
@@ -245,6 +269,26 @@ impl Connection {
 }
 
 impl NativeConnectionWrapper {
+    // Opens another EGL display on the same X11 display that renders with the given device.
+    // Returns `None` if EGL can't choose the device of an X11 display.
+    pub(crate) unsafe fn open_with_egl_device(
+        native_connection: &Arc<NativeConnectionWrapper>,
+        egl_device: EGLDevice,
+    ) -> Result<Option<Arc<NativeConnectionWrapper>>, Error> {
+        let x11_display = native_connection.x11_display;
+        let egl_display =
+            match egl_device.open_platform_display(EGL_PLATFORM_X11_KHR, x11_display as *mut _)? {
+                Some(egl_display) => egl_display,
+                None => return Ok(None),
+            };
+        Ok(Some(Arc::new(NativeConnectionWrapper {
+            egl_display,
+            x11_display,
+            x11_display_is_owned: false,
+            _parent: Some(native_connection.clone()),
+        })))
+    }
+
     #[inline]
     pub(crate) fn lock_display(&self) -> DisplayGuard {
         unsafe {
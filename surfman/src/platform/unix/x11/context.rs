@@ -8,11 +8,15 @@ use crate::context::ContextID;
 use crate::egl;
 use crate::egl::types::EGLint;
 use crate::platform::generic::egl::context::{self, CurrentContextGuard, EGLBackedContext};
-use crate::{ContextAttributes, Error, Gl, SurfaceInfo};
+use crate::platform::generic::egl::fence::EGLFence;
+use crate::{ContextAttributes, ContextDescriptorInfo, ContextResetStatus, Error};
+use crate::{GLInfo, Gl, SurfaceInfo};
 
 use std::os::raw::c_void;
+use std::os::unix::io::RawFd;
 
 pub use crate::platform::generic::egl::context::{ContextDescriptor, NativeContext};
+pub use crate::platform::unix::generic::context::Fence;
 
 thread_local! {
     #[doc(hidden)]
@@ -34,7 +38,8 @@ thread_local! {
 /// OpenGL objects may not be shared across contexts directly, but surface textures effectively
 /// allow for sharing of texture data. Contexts are local to a single thread and device.
 ///
-/// A context must be explicitly destroyed with `destroy_context()`, or a panic will occur.
+/// A context must be explicitly destroyed with `destroy_context()`. Dropping it without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Context(pub(crate) EGLBackedContext);
 
 impl Device {
@@ -46,23 +51,54 @@ impl Device {
         &self,
         attributes: &ContextAttributes,
     ) -> Result<ContextDescriptor, Error> {
-        // Set environment variables as appropriate.
-        self.adapter.set_environment_variables();
-
+        let config_attributes = self.context_config_attributes(attributes)?;
         unsafe {
             ContextDescriptor::new(
                 self.native_connection.egl_display,
                 attributes,
-                &[
-                    egl::SURFACE_TYPE as EGLint,
-                    egl::WINDOW_BIT as EGLint,
-                    egl::RENDERABLE_TYPE as EGLint,
-                    egl::OPENGL_BIT as EGLint,
-                ],
+                &config_attributes,
+                self.gl_api(),
+            )
+        }
+    }
+
+    /// Returns a context descriptor for every EGL config of the display, whether or not it
+    /// matches the given attributes, starting with the one that `create_context_descriptor()`
+    /// chooses if there is one.
+    ///
+    /// Contexts created from them get the version and flags in the attributes. Use
+    /// `context_descriptor_info()` to tell them apart; configs that don't support the surface
+    /// types or client API of this backend fail to create contexts.
+    pub fn enumerate_context_descriptors(
+        &self,
+        attributes: &ContextAttributes,
+    ) -> Result<Vec<ContextDescriptor>, Error> {
+        let config_attributes = self.context_config_attributes(attributes)?;
+        unsafe {
+            ContextDescriptor::enumerate(
+                self.native_connection.egl_display,
+                attributes,
+                &config_attributes,
+                self.gl_api(),
             )
         }
     }
 
+    // The EGL config attributes that contexts on this backend need, on top of the ones that
+    // follow from the context attributes.
+    fn context_config_attributes(
+        &self,
+        attributes: &ContextAttributes,
+    ) -> Result<[EGLint; 4], Error> {
+        let renderable_type = context::renderable_type(self.gl_api(), attributes)?;
+        Ok([
+            egl::SURFACE_TYPE as EGLint,
+            egl::WINDOW_BIT as EGLint,
+            egl::RENDERABLE_TYPE as EGLint,
+            renderable_type,
+        ])
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -73,15 +109,16 @@ impl Device {
         descriptor: &ContextDescriptor,
         share_with: Option<&Context>,
     ) -> Result<Context, Error> {
-        unsafe {
+        GL_FUNCTIONS.with(|gl| unsafe {
             EGLBackedContext::new(
+                gl,
                 self.native_connection.egl_display,
                 descriptor,
                 share_with.map(|ctx| &ctx.0),
                 self.gl_api(),
             )
             .map(Context)
-        }
+        })
     }
 
     /// Wraps an `EGLContext` in a native context and returns it.
@@ -122,13 +159,8 @@ impl Device {
     /// Returns the descriptor that this context was created with.
     #[inline]
     pub fn context_descriptor(&self, context: &Context) -> ContextDescriptor {
-        GL_FUNCTIONS.with(|gl| unsafe {
-            ContextDescriptor::from_egl_context(
-                gl,
-                self.native_connection.egl_display,
-                context.0.egl_context,
-            )
-        })
+        GL_FUNCTIONS
+            .with(|gl| unsafe { context.0.descriptor(gl, self.native_connection.egl_display) })
     }
 
     /// Makes the context the current OpenGL context for this thread.
@@ -158,6 +190,103 @@ impl Device {
         Ok(guard)
     }
 
+    /// Inserts a fence into the command stream of the given context and flushes the context.
+    ///
+    /// Waiting on the fence with `wait_fence()` makes another context wait for all the commands
+    /// issued so far, such as rendering to a surface that is then handed to another thread.
+    /// Fences can be sent between threads. They use `EGL_KHR_fence_sync` or
+    /// `EGL_ANDROID_native_fence_sync` if available along with `EGL_KHR_wait_sync`; otherwise they
+    /// fall back to `glFenceSync`, and can then only be waited on by contexts sharing objects with
+    /// the given context.
+    ///
+    /// A fence must be explicitly destroyed with `destroy_fence()`. Dropping it without destroying
+    /// it leaks it and logs an error, unless leaks are ignored with `set_leak_policy()`.
+    pub fn create_fence(&self, context: &Context) -> Result<Fence, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        GL_FUNCTIONS.with(|gl| unsafe {
+            EGLFence::new(
+                gl,
+                self.native_connection.egl_display,
+                context.0.share_group,
+            )
+            .map(Fence)
+        })
+    }
+
+    /// Makes the given context wait for a fence to be signaled before executing any further
+    /// commands.
+    ///
+    /// The wait happens on the GPU, so this returns immediately. Fences imported with
+    /// `import_fence_fd()` need the `EGL_KHR_wait_sync` extension for this; if it's unavailable,
+    /// this returns an `UnsupportedOnThisPlatform` error instead of blocking the CPU.
+    pub fn wait_fence(&self, context: &Context, fence: &Fence) -> Result<(), Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        GL_FUNCTIONS.with(|gl| unsafe {
+            fence.0.wait(
+                gl,
+                self.native_connection.egl_display,
+                context.0.share_group,
+            )
+        })
+    }
+
+    /// Returns a sync_file that signals once the rendering that the given context has done to a
+    /// surface completes.
+    ///
+    /// Send the file descriptor along with the surface's dma-buf so that the consumer can wait for
+    /// the rendering with explicit synchronization. The surface must have been created with the
+    /// given context, or an `IncompatibleSurface` error is returned. This requires the
+    /// `EGL_ANDROID_native_fence_sync` extension; if it's unavailable, this returns a
+    /// `RequiredExtensionUnavailable` error. The caller owns the returned file descriptor.
+    pub fn export_surface_fence_fd(
+        &self,
+        context: &Context,
+        surface: &Surface,
+    ) -> Result<RawFd, Error> {
+        if surface.0.context_id != context.0.id {
+            return Err(Error::IncompatibleSurface);
+        }
+
+        let _guard = self.temporarily_make_context_current(context)?;
+        GL_FUNCTIONS.with(|gl| unsafe {
+            EGLFence::export_native_fence_fd(gl, self.native_connection.egl_display)
+        })
+    }
+
+    /// Wraps a sync_file received from another process or device in a fence.
+    ///
+    /// Wait on the fence with `wait_fence()` before reading from the buffer it guards, and destroy
+    /// it with `destroy_fence()` afterward. On success, the fence takes ownership of the file
+    /// descriptor. This requires the `EGL_ANDROID_native_fence_sync` extension; if it's
+    /// unavailable, this returns a `RequiredExtensionUnavailable` error.
+    pub fn import_fence_fd(&self, context: &Context, fd: RawFd) -> Result<Fence, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        unsafe { EGLFence::from_native_fence_fd(self.native_connection.egl_display, fd).map(Fence) }
+    }
+
+    /// Destroys a fence.
+    ///
+    /// Fences that fell back to `glFenceSync` must be destroyed with a context that can wait on
+    /// them, or an `IncompatibleContext` error is returned. If an error is returned, the fence is
+    /// returned alongside it.
+    pub fn destroy_fence(&self, context: &Context, mut fence: Fence) -> Result<(), (Error, Fence)> {
+        let _guard = match self.temporarily_make_context_current(context) {
+            Ok(guard) => guard,
+            Err(err) => return Err((err, fence)),
+        };
+        let result = GL_FUNCTIONS.with(|gl| unsafe {
+            fence.0.destroy(
+                gl,
+                self.native_connection.egl_display,
+                context.0.share_group,
+            )
+        });
+        match result {
+            Ok(()) => Ok(()),
+            Err(err) => Err((err, fence)),
+        }
+    }
+
     /// Returns the attributes that the context descriptor was created with.
     #[inline]
     pub fn context_descriptor_attributes(
@@ -167,6 +296,15 @@ impl Device {
         unsafe { context_descriptor.attributes(self.native_connection.egl_display) }
     }
 
+    /// Describes the EGL config behind the given context descriptor.
+    #[inline]
+    pub fn context_descriptor_info(
+        &self,
+        context_descriptor: &ContextDescriptor,
+    ) -> Result<ContextDescriptorInfo, Error> {
+        unsafe { Ok(context_descriptor.info(self.native_connection.egl_display)) }
+    }
+
     /// Fetches the address of an OpenGL function associated with this context.
     ///
     /// OpenGL functions are local to a context. You should not use OpenGL functions on one context
@@ -240,4 +378,27 @@ impl Device {
     pub fn context_surface_info(&self, context: &Context) -> Result<Option<SurfaceInfo>, Error> {
         context.0.surface_info()
     }
+
+    /// Returns whether the context has been lost to a GPU reset since it was created.
+    ///
+    /// Resets are only reported for contexts created with `ContextAttributeFlags::ROBUST_ACCESS`,
+    /// which requires `EGL_KHR_create_context` for OpenGL contexts and
+    /// `EGL_EXT_create_context_robustness` for OpenGL ES contexts. The context need not be current.
+    pub fn context_reset_status(&self, context: &Context) -> Result<ContextResetStatus, Error> {
+        GL_FUNCTIONS.with(|gl| unsafe {
+            context
+                .0
+                .reset_status(gl, self.native_connection.egl_display)
+        })
+    }
+
+    /// Returns the vendor, renderer, and version information of the given context.
+    ///
+    /// The context is temporarily made current if it isn't already.
+    /// If the version string is missing or can't be parsed, this returns an `UnknownGLVersion`
+    /// error.
+    pub fn context_gl_info(&self, context: &Context) -> Result<GLInfo, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        GL_FUNCTIONS.with(GLInfo::current)
+    }
 }
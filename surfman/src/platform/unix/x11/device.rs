@@ -3,7 +3,9 @@
 //! A wrapper around X11 `EGLDisplay`s.
 
 use super::connection::{Connection, NativeConnectionWrapper};
-use crate::{Error, GLApi};
+use super::context::GL_FUNCTIONS;
+use crate::platform::generic::egl::capabilities;
+use crate::{DeviceCapabilities, Error, GLApi};
 
 use std::sync::Arc;
 
@@ -15,6 +17,8 @@ pub use crate::platform::unix::generic::device::Adapter;
 pub struct Device {
     pub(crate) native_connection: Arc<NativeConnectionWrapper>,
     pub(crate) adapter: Adapter,
+    pub(crate) gl_api: GLApi,
+    pub(crate) capabilities: Option<DeviceCapabilities>,
 }
 
 /// Wraps an adapter.
@@ -29,9 +33,32 @@ pub struct NativeDevice {
 impl Device {
     #[inline]
     pub(crate) fn new(connection: &Connection, adapter: &Adapter) -> Result<Device, Error> {
+        // The connection's display renders with the device that the display server picks. Other
+        // devices get a display of their own on the same X11 connection, so that devices on
+        // different adapters can coexist.
+        let egl_device = match adapter.display_server_egl_device()? {
+            None => None,
+            Some(egl_device) => unsafe {
+                NativeConnectionWrapper::open_with_egl_device(
+                    &connection.native_connection,
+                    egl_device,
+                )?
+            },
+        };
+        let native_connection = match (egl_device, adapter) {
+            (Some(native_connection), _) => native_connection,
+            (None, &Adapter::Hardware) | (None, &Adapter::HardwarePrime) => {
+                connection.native_connection.clone()
+            }
+            (None, &Adapter::Software) => return Err(Error::NoAdapterFound),
+            (None, &Adapter::Device(_)) => return Err(Error::IncompatibleAdapter),
+        };
+
         Ok(Device {
-            native_connection: connection.native_connection.clone(),
+            native_connection,
             adapter: (*adapter).clone(),
+            gl_api: connection.gl_api,
+            capabilities: None,
         })
     }
 
@@ -51,6 +78,7 @@ impl Device {
     pub fn connection(&self) -> Connection {
         Connection {
             native_connection: self.native_connection.clone(),
+            gl_api: self.gl_api,
         }
     }
 
@@ -63,6 +91,33 @@ impl Device {
     /// Returns the OpenGL API flavor that this device supports (OpenGL or OpenGL ES).
     #[inline]
     pub fn gl_api(&self) -> GLApi {
-        GLApi::GL
+        self.gl_api
+    }
+
+    /// Describes what this device supports.
+    ///
+    /// The first call creates a temporary context, and a surface of each format, to find out, so
+    /// it's relatively expensive. The result is kept, so later calls are cheap. The limits
+    /// reported are those of an OpenGL or OpenGL ES 3.0 context, or of the highest version below
+    /// that if the implementation doesn't support 3.0; contexts of other versions may have
+    /// different ones.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        if let Some(ref capabilities) = self.capabilities {
+            return Ok(capabilities.clone());
+        }
+
+        let context_attributes = DeviceCapabilities::probe_context_attributes();
+        let context_descriptor = self.create_context_descriptor(&context_attributes)?;
+        let capabilities = GL_FUNCTIONS.with(|gl| unsafe {
+            capabilities::probe(
+                gl,
+                self.native_connection.egl_display,
+                &context_descriptor,
+                self.gl_api(),
+                true,
+            )
+        })?;
+        self.capabilities = Some(capabilities.clone());
+        Ok(capabilities)
     }
 }
@@ -10,6 +10,7 @@
 use super::device::{Adapter, Device, NativeDevice, VendorPreference};
 use super::surface::NativeWidget;
 use crate::egl::types::{EGLDisplay, EGLNativeWindowType};
+use crate::platform::generic::egl::device;
 use crate::Error;
 use crate::GLApi;
 
@@ -49,6 +50,7 @@ impl Connection {
     /// Connects to the default display.
     #[inline]
     pub fn new() -> Result<Connection, Error> {
+        device::load_egl_library()?;
         Ok(Connection)
     }
 
@@ -70,6 +72,18 @@ impl Connection {
         GLApi::GLES
     }
 
+    /// Chooses the OpenGL API flavor for devices opened from now on.
+    ///
+    /// This backend only supports OpenGL ES, so any other choice returns `UnsupportedGLType`.
+    #[inline]
+    pub fn set_gl_api(&mut self, gl_api: GLApi) -> Result<(), Error> {
+        if gl_api == GLApi::GLES {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedGLType)
+        }
+    }
+
     /// Returns the "best" adapter on this system, preferring high-performance hardware adapters.
     ///
     /// This is an alias for `Connection::create_hardware_adapter()`.
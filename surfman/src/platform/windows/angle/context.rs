@@ -5,18 +5,20 @@
 use super::device::Device;
 use super::surface::{Surface, Synchronization, Win32Objects};
 use crate::context::{ContextID, CREATE_CONTEXT_MUTEX};
+use crate::debug;
 use crate::egl;
 use crate::egl::types::{EGLConfig, EGLContext, EGLint};
+use crate::leaks::{self, TrackedObject};
 use crate::platform::generic::egl::context::{self, CurrentContextGuard};
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::surface::ExternalEGLSurfaces;
 use crate::surface::Framebuffer;
-use crate::{ContextAttributes, Error, Gl, SurfaceInfo};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextDescriptorInfo, ContextResetStatus};
+use crate::{DepthFormat, Error, GLInfo, Gl, NativeError, SurfaceInfo, WindowingApiError};
 
 use std::mem;
 use std::os::raw::c_void;
-use std::thread;
 use winapi::shared::winerror::S_OK;
 use winapi::um::winbase::INFINITE;
 
@@ -27,6 +29,17 @@ thread_local! {
     pub static GL_FUNCTIONS: Gl = Gl::load_with(context::get_proc_address);
 }
 
+// The EGL config attributes that contexts on this backend need, on top of the ones that follow
+// from the context attributes.
+const CONTEXT_CONFIG_ATTRIBUTES: [EGLint; 6] = [
+    egl::BIND_TO_TEXTURE_RGBA as EGLint,
+    1 as EGLint,
+    egl::SURFACE_TYPE as EGLint,
+    egl::PBUFFER_BIT as EGLint,
+    egl::RENDERABLE_TYPE as EGLint,
+    egl::OPENGL_ES2_BIT as EGLint,
+];
+
 /// Represents an OpenGL rendering context.
 ///
 /// A context allows you to issue rendering commands to a surface. When initially created, a
@@ -42,7 +55,8 @@ thread_local! {
 /// OpenGL objects may not be shared across contexts directly, but surface textures effectively
 /// allow for sharing of texture data. Contexts are local to a single thread and device.
 ///
-/// A context must be explicitly destroyed with `destroy_context()`, or a panic will occur.
+/// A context must be explicitly destroyed with `destroy_context()`. Dropping it without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Context {
     pub(crate) egl_context: EGLContext,
     pub(crate) id: ContextID,
@@ -53,12 +67,18 @@ pub struct Context {
 impl Drop for Context {
     #[inline]
     fn drop(&mut self) {
-        if self.egl_context != egl::NO_CONTEXT && !thread::panicking() {
-            panic!("Contexts must be destroyed explicitly with `destroy_context`!")
+        if self.egl_context != egl::NO_CONTEXT {
+            leaks::dropped_without_destroying(TrackedObject::Context(self.id));
         }
     }
 }
 
+/// A point in the command stream of a context that other contexts can wait for.
+///
+/// This backend doesn't support fences, so none can be created.
+#[derive(Debug)]
+pub enum Fence {}
+
 impl Device {
     /// Creates a context descriptor with the given attributes.
     ///
@@ -72,14 +92,29 @@ impl Device {
             ContextDescriptor::new(
                 self.egl_display,
                 attributes,
-                &[
-                    egl::BIND_TO_TEXTURE_RGBA as EGLint,
-                    1 as EGLint,
-                    egl::SURFACE_TYPE as EGLint,
-                    egl::PBUFFER_BIT as EGLint,
-                    egl::RENDERABLE_TYPE as EGLint,
-                    egl::OPENGL_ES2_BIT as EGLint,
-                ],
+                &CONTEXT_CONFIG_ATTRIBUTES,
+                self.gl_api(),
+            )
+        }
+    }
+
+    /// Returns a context descriptor for every EGL config of the display, whether or not it
+    /// matches the given attributes, starting with the one that `create_context_descriptor()`
+    /// chooses if there is one.
+    ///
+    /// Contexts created from them get the version and flags in the attributes. Use
+    /// `context_descriptor_info()` to tell them apart; configs that don't support the surface
+    /// types or client API of this backend fail to create contexts.
+    pub fn enumerate_context_descriptors(
+        &self,
+        attributes: &ContextAttributes,
+    ) -> Result<Vec<ContextDescriptor>, Error> {
+        unsafe {
+            ContextDescriptor::enumerate(
+                self.egl_display,
+                attributes,
+                &CONTEXT_CONFIG_ATTRIBUTES,
+                self.gl_api(),
             )
         }
     }
@@ -101,6 +136,17 @@ impl Device {
                 share_with.map_or(egl::NO_CONTEXT, |ctx| ctx.egl_context),
                 self.gl_api(),
             )?;
+            if descriptor.debug {
+                GL_FUNCTIONS.with(|gl| {
+                    context::install_debug_callback(
+                        gl,
+                        self.egl_display,
+                        egl::NO_SURFACE,
+                        egl_context,
+                        *next_context_id,
+                    )
+                });
+            }
 
             let context = Context {
                 egl_context,
@@ -108,6 +154,7 @@ impl Device {
                 framebuffer: Framebuffer::None,
                 context_is_owned: true,
             };
+            leaks::track(TrackedObject::Context(context.id));
             next_context_id.0 += 1;
             Ok(context)
         }
@@ -134,6 +181,7 @@ impl Device {
             }),
             context_is_owned: false,
         };
+        leaks::track(TrackedObject::Context(context.id));
         next_context_id.0 += 1;
 
         Ok(context)
@@ -168,6 +216,8 @@ impl Device {
             context.egl_context = egl::NO_CONTEXT;
         });
 
+        debug::set_debug_callback(context.id, None);
+        leaks::untrack(TrackedObject::Context(context.id));
         Ok(())
     }
 
@@ -199,7 +249,7 @@ impl Device {
                     context.egl_context,
                 );
                 if result == egl::FALSE {
-                    let err = egl.GetError().to_windowing_api_error();
+                    let err = egl.GetError().to_native_error("eglMakeCurrent");
                     return Err(Error::MakeCurrentFailed(err));
                 }
                 Ok(())
@@ -228,13 +278,56 @@ impl Device {
         EGL_FUNCTIONS.with(|egl| unsafe { egl.GetCurrentContext() == context.egl_context })
     }
 
+    /// Inserts a fence into the command stream of the given context.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn create_fence(&self, _: &Context) -> Result<Fence, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
+    /// Makes the given context wait for a fence to be signaled.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn wait_fence(&self, _: &Context, _: &Fence) -> Result<(), Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
+    /// Destroys a fence.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn destroy_fence(&self, _: &Context, fence: Fence) -> Result<(), (Error, Fence)> {
+        Err((Error::UnsupportedOnThisPlatform, fence))
+    }
+
     /// Returns the attributes that the context descriptor was created with.
     #[inline]
     pub fn context_descriptor_attributes(
         &self,
         context_descriptor: &ContextDescriptor,
     ) -> ContextAttributes {
-        unsafe { context_descriptor.attributes(self.egl_display) }
+        // Surfaces on this backend are always single-sampled, and use the depth buffer of their
+        // EGL config.
+        let mut attributes = unsafe { context_descriptor.attributes(self.egl_display) };
+        attributes
+            .flags
+            .remove(ContextAttributeFlags::SAMPLEABLE_DEPTH);
+        ContextAttributes {
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            ..attributes
+        }
+    }
+
+    /// Describes the EGL config behind the given context descriptor.
+    #[inline]
+    pub fn context_descriptor_info(
+        &self,
+        context_descriptor: &ContextDescriptor,
+    ) -> Result<ContextDescriptorInfo, Error> {
+        unsafe { Ok(context_descriptor.info(self.egl_display)) }
     }
 
     /// Fetches the address of an OpenGL function associated with this context.
@@ -368,6 +461,33 @@ impl Device {
         }
     }
 
+    /// Returns whether the context has been lost to a GPU reset since it was created.
+    ///
+    /// Resets are only reported for contexts created with `ContextAttributeFlags::ROBUST_ACCESS`,
+    /// which requires `EGL_EXT_create_context_robustness`. The context need not be current.
+    pub fn context_reset_status(&self, context: &Context) -> Result<ContextResetStatus, Error> {
+        let _guard = match self.temporarily_make_context_current(context) {
+            Ok(guard) => guard,
+            // Some implementations refuse to make lost contexts current at all.
+            Err(Error::MakeCurrentFailed(NativeError {
+                kind: WindowingApiError::ContextLost,
+                ..
+            })) => return Ok(ContextResetStatus::Unknown),
+            Err(err) => return Err(err),
+        };
+        Ok(GL_FUNCTIONS.with(crate::context::current_context_reset_status))
+    }
+
+    /// Returns the vendor, renderer, and version information of the given context.
+    ///
+    /// The context is temporarily made current if it isn't already.
+    /// If the version string is missing or can't be parsed, this returns an `UnknownGLVersion`
+    /// error.
+    pub fn context_gl_info(&self, context: &Context) -> Result<GLInfo, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        GL_FUNCTIONS.with(GLInfo::current)
+    }
+
     /// Given a context, returns its underlying EGL context and attached surfaces.
     pub fn native_context(&self, context: &Context) -> NativeContext {
         let (egl_draw_surface, egl_read_surface) = match context.framebuffer {
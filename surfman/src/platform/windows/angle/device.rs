@@ -3,13 +3,15 @@
 //! A thread-local handle to the device.
 
 use super::connection::Connection;
+use super::context::{Context, GL_FUNCTIONS};
 use crate::egl;
 use crate::egl::types::{EGLAttrib, EGLDisplay, EGLint, EGLDeviceEXT};
-use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::platform::generic::egl::context::CurrentContextGuard;
+use crate::platform::generic::egl::device::{self, EGL_FUNCTIONS};
 use crate::platform::generic::egl::ffi::{EGL_D3D11_DEVICE_ANGLE, EGL_EXTENSION_FUNCTIONS};
 use crate::platform::generic::egl::ffi::{EGL_NO_DEVICE_EXT, EGL_PLATFORM_DEVICE_EXT};
 use crate::platform::generic::egl::ffi::{EGL_DEVICE_EXT};
-use crate::{Error, GLApi};
+use crate::{DeviceCapabilities, Error, GLApi};
 
 use std::cell::{RefCell, RefMut};
 use std::mem;
@@ -46,6 +48,7 @@ pub struct Device {
     pub(crate) d3d11_device: ComPtr<ID3D11Device>,
     pub(crate) d3d_driver_type: D3D_DRIVER_TYPE,
     pub(crate) display_is_owned: bool,
+    pub(crate) capabilities: Option<DeviceCapabilities>,
 }
 
 pub(crate) enum VendorPreference {
@@ -215,6 +218,7 @@ impl Device {
                     d3d11_device,
                     d3d_driver_type,
                     display_is_owned: true,
+                    capabilities: None,
                 })
             })
         }
@@ -228,6 +232,7 @@ impl Device {
                 d3d11_device: ComPtr::from_raw(native_device.d3d11_device),
                 d3d_driver_type: native_device.d3d_driver_type,
                 display_is_owned: false,
+                capabilities: None,
             })
         }
     }
@@ -268,6 +273,7 @@ impl Device {
                 d3d11_device: ComPtr::from_raw(d3d11_device),
                 d3d_driver_type: D3D_DRIVER_TYPE_UNKNOWN,
                 display_is_owned: false,
+                capabilities: None,
             })
         }
     }
@@ -318,6 +324,43 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GLES
     }
+
+    /// Describes what this device supports.
+    ///
+    /// The first call creates a temporary context to find out, so it's relatively expensive. The
+    /// result is kept, so later calls are cheap.
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        if let Some(ref capabilities) = self.capabilities {
+            return Ok(capabilities.clone());
+        }
+
+        // Destroying the temporary context may leave no context current, so restore the current
+        // one afterward.
+        let _guard = CurrentContextGuard::new();
+        let context_attributes = DeviceCapabilities::probe_context_attributes();
+        let context_descriptor = self.create_context_descriptor(&context_attributes)?;
+        let mut context = self.create_context(&context_descriptor, None)?;
+        let result = self.probe_capabilities(&context);
+        self.destroy_context(&mut context)?;
+        let capabilities = result?;
+        self.capabilities = Some(capabilities.clone());
+        Ok(capabilities)
+    }
+
+    fn probe_capabilities(&self, context: &Context) -> Result<DeviceCapabilities, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        let capabilities = GL_FUNCTIONS.with(DeviceCapabilities::current);
+
+        // Surfaces on this backend are always RGBA8 and can't be read back on the CPU, but they
+        // can be shared with contexts on other threads.
+        Ok(DeviceCapabilities {
+            client_extensions: device::extensions(egl::NO_DISPLAY),
+            display_extensions: device::extensions(self.egl_display),
+            cross_thread_surface_textures: true,
+            widgets: true,
+            ..capabilities
+        })
+    }
 }
 
 impl Drop for Device {
@@ -10,20 +10,20 @@ use crate::egl::types::EGLSurface;
 use crate::egl::{self, EGLint};
 use crate::gl;
 use crate::gl::types::{GLenum, GLint, GLuint};
+use crate::leaks::{self, TrackedObject};
 use crate::platform::generic::egl::device::EGL_FUNCTIONS;
 use crate::platform::generic::egl::error::ToWindowingApiError;
 use crate::platform::generic::egl::ffi::EGL_D3D_TEXTURE_2D_SHARE_HANDLE_ANGLE;
 use crate::platform::generic::egl::ffi::EGL_D3D_TEXTURE_ANGLE;
 use crate::platform::generic::egl::ffi::EGL_DXGI_KEYED_MUTEX_ANGLE;
 use crate::platform::generic::egl::ffi::EGL_EXTENSION_FUNCTIONS;
-use crate::{Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
+use crate::{Error, SurfaceAccess, SurfaceFormat, SurfaceID, SurfaceInfo, SurfaceType};
 
 use euclid::default::Size2D;
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
 use std::os::raw::c_void;
 use std::ptr;
-use std::thread;
 use winapi::shared::dxgi::IDXGIKeyedMutex;
 use winapi::shared::winerror::S_OK;
 use winapi::um::d3d11;
@@ -49,12 +49,14 @@ const SURFACE_GL_TEXTURE_TARGET: GLenum = gl::TEXTURE_2D;
 ///
 /// Depending on the platform, each surface may be internally double-buffered.
 ///
-/// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
+/// Surfaces must be destroyed with the `destroy_surface()` method. Dropping one without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Surface {
     pub(crate) egl_surface: EGLSurface,
     pub(crate) size: Size2D<i32>,
     pub(crate) context_id: ContextID,
     pub(crate) context_descriptor: ContextDescriptor,
+    pub(crate) access: SurfaceAccess,
     pub(crate) win32_objects: Win32Objects,
 }
 
@@ -85,8 +87,8 @@ impl Debug for Surface {
 
 impl Drop for Surface {
     fn drop(&mut self) {
-        if self.egl_surface != egl::NO_SURFACE && !thread::panicking() {
-            panic!("Should have destroyed the surface first with `destroy_surface()`!")
+        if self.egl_surface != egl::NO_SURFACE {
+            leaks::dropped_without_destroying(TrackedObject::Surface(self.id()));
         }
     }
 }
@@ -130,13 +132,15 @@ impl Device {
     pub fn create_surface(
         &mut self,
         context: &Context,
-        _: SurfaceAccess,
+        access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
         match surface_type {
-            SurfaceType::Generic { ref size } => self.create_pbuffer_surface(context, size, None),
+            SurfaceType::Generic { ref size } => {
+                self.create_pbuffer_surface(context, access, size, None)
+            }
             SurfaceType::Widget { ref native_widget } => {
-                self.create_window_surface(context, native_widget)
+                self.create_window_surface(context, access, native_widget)
             }
         }
     }
@@ -145,6 +149,7 @@ impl Device {
     fn create_pbuffer_surface(
         &mut self,
         context: &Context,
+        access: SurfaceAccess,
         size: &Size2D<i32>,
         texture: Option<ComPtr<d3d11::ID3D11Texture2D>>,
     ) -> Result<Surface, Error> {
@@ -220,11 +225,13 @@ impl Device {
                     Synchronization::None
                 };
 
+                leaks::track(TrackedObject::Surface(SurfaceID(egl_surface as usize)));
                 Ok(Surface {
                     egl_surface,
                     size: *size,
                     context_id: context.id,
                     context_descriptor,
+                    access,
                     win32_objects: Win32Objects::Pbuffer {
                         share_handle,
                         synchronization,
@@ -243,12 +250,13 @@ impl Device {
         size: &Size2D<i32>,
         texture: ComPtr<d3d11::ID3D11Texture2D>,
     ) -> Result<Surface, Error> {
-        self.create_pbuffer_surface(context, size, Some(texture))
+        self.create_pbuffer_surface(context, SurfaceAccess::GPUOnly, size, Some(texture))
     }
 
     fn create_window_surface(
         &mut self,
         context: &Context,
+        access: SurfaceAccess,
         native_widget: &NativeWidget,
     ) -> Result<Surface, Error> {
         let context_descriptor = self.context_descriptor(context);
@@ -282,11 +290,13 @@ impl Device {
                 assert_ne!(width, 0);
                 assert_ne!(height, 0);
 
+                leaks::track(TrackedObject::Surface(SurfaceID(egl_surface as usize)));
                 Ok(Surface {
                     egl_surface,
                     size: Size2D::new(width, height),
                     context_id: context.id,
                     context_descriptor,
+                    access,
                     win32_objects: Win32Objects::Window,
                 })
             })
@@ -341,7 +351,9 @@ impl Device {
                     pbuffer_attributes.as_ptr(),
                 );
                 if local_egl_surface == egl::NO_SURFACE {
-                    let windowing_api_error = egl.GetError().to_windowing_api_error();
+                    let windowing_api_error = egl
+                        .GetError()
+                        .to_native_error("eglCreatePbufferFromClientBuffer");
                     return Err((Error::SurfaceImportFailed(windowing_api_error), surface));
                 }
 
@@ -399,7 +411,7 @@ impl Device {
                         egl::BACK_BUFFER as GLint,
                     ) == egl::FALSE
                     {
-                        let windowing_api_error = egl.GetError().to_windowing_api_error();
+                        let windowing_api_error = egl.GetError().to_native_error("eglBindTexImage");
                         return Err((
                             Error::SurfaceTextureCreationFailed(windowing_api_error),
                             surface,
@@ -423,6 +435,7 @@ impl Device {
                     gl.BindTexture(gl::TEXTURE_2D, 0);
                     debug_assert_eq!(gl.GetError(), gl::NO_ERROR);
 
+                    leaks::track(TrackedObject::SurfaceTexture(surface.id()));
                     Ok(SurfaceTexture {
                         surface,
                         local_egl_surface,
@@ -458,8 +471,8 @@ impl Device {
     /// The supplied context must be the context the surface is associated with, or this returns
     /// an `IncompatibleSurface` error.
     ///
-    /// You must explicitly call this method to dispose of a surface. Otherwise, a panic occurs in
-    /// the `drop` method.
+    /// You must explicitly call this method to dispose of a surface. Otherwise, the surface leaks
+    /// when it's dropped, and the leak is reported as chosen with `set_leak_policy()`.
     pub fn destroy_surface(
         &self,
         context: &mut Context,
@@ -478,6 +491,7 @@ impl Device {
                     self.make_no_context_current()?;
                 }
 
+                leaks::untrack(TrackedObject::Surface(surface.id()));
                 egl.DestroySurface(self.egl_display, surface.egl_surface);
                 surface.egl_surface = egl::NO_SURFACE;
                 if let Win32Objects::Pbuffer {
@@ -503,6 +517,7 @@ impl Device {
         _: &mut Context,
         mut surface_texture: SurfaceTexture,
     ) -> Result<Surface, (Error, SurfaceTexture)> {
+        leaks::untrack(TrackedObject::SurfaceTexture(surface_texture.surface.id()));
         unsafe {
             GL_FUNCTIONS.with(|gl| gl.DeleteTextures(1, &surface_texture.gl_texture));
             surface_texture.gl_texture = 0;
@@ -581,6 +596,8 @@ impl Device {
             id: surface.id(),
             context_id: surface.context_id,
             framebuffer_object: 0,
+            format: SurfaceFormat::RGBA8,
+            access: surface.access,
         }
     }
 
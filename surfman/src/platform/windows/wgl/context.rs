@@ -5,9 +5,12 @@
 use super::device::{DCGuard, Device, HiddenWindow};
 use super::surface::{Surface, Win32Objects};
 use crate::context::{self, CREATE_CONTEXT_MUTEX};
+use crate::debug;
+use crate::leaks::{self, TrackedObject};
 use crate::surface::Framebuffer;
-use crate::{ContextAttributeFlags, ContextAttributes, ContextID, Error, GLVersion};
-use crate::{SurfaceInfo, WindowingApiError};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextID, DepthFormat, Error, GLVersion};
+use crate::{ContextDescriptorInfo, ContextPriority, ContextResetStatus};
+use crate::{GLInfo, NativeError, SurfaceInfo, WindowingApiError};
 
 use crate::gl;
 use crate::gl::types::{GLenum, GLint, GLuint};
@@ -124,7 +127,8 @@ pub struct ContextDescriptor {
 /// OpenGL objects may not be shared across contexts directly, but surface textures effectively
 /// allow for sharing of texture data. Contexts are local to a single thread and device.
 ///
-/// A context must be explicitly destroyed with `destroy_context()`, or a panic will occur.
+/// A context must be explicitly destroyed with `destroy_context()`. Dropping it without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Context {
     pub(crate) glrc: HGLRC,
     pub(crate) id: ContextID,
@@ -141,6 +145,15 @@ enum ContextStatus {
     Destroyed,
 }
 
+impl Drop for Context {
+    #[inline]
+    fn drop(&mut self) {
+        if self.status != ContextStatus::Destroyed {
+            leaks::dropped_without_destroying_no_panic(TrackedObject::Context(self.id));
+        }
+    }
+}
+
 /// Wrapper for a WGL `HGLRC`.
 #[derive(Clone)]
 pub struct NativeContext(pub HGLRC);
@@ -158,6 +171,12 @@ lazy_static! {
         thread::spawn(extension_loader_thread).join().unwrap();
 }
 
+/// A point in the command stream of a context that other contexts can wait for.
+///
+/// This backend doesn't support fences, so none can be created.
+#[derive(Debug)]
+pub enum Fence {}
+
 impl Device {
     /// Creates a context descriptor with the given attributes.
     ///
@@ -224,7 +243,9 @@ impl Device {
                 &mut pixel_format_count,
             );
             if ok == FALSE {
-                return Err(Error::PixelFormatSelectionFailed(WindowingApiError::Failed));
+                return Err(Error::PixelFormatSelectionFailed(
+                    NativeError::in_operation(WindowingApiError::Failed, "wglChoosePixelFormatARB"),
+                ));
             }
             if pixel_format_count == 0 {
                 return Err(Error::NoPixelFormatFound);
@@ -238,6 +259,18 @@ impl Device {
         }
     }
 
+    /// Returns a context descriptor for every pixel format of the device.
+    ///
+    /// This backend can't enumerate pixel formats, so this always returns
+    /// `UnsupportedOnThisPlatform`.
+    #[inline]
+    pub fn enumerate_context_descriptors(
+        &self,
+        _: &ContextAttributes,
+    ) -> Result<Vec<ContextDescriptor>, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
     /// Creates a new OpenGL context.
     ///
     /// The context initially has no surface attached. Until a surface is bound to it, rendering
@@ -287,7 +320,10 @@ impl Device {
                     wgl_attributes.as_ptr(),
                 );
                 if glrc.is_null() {
-                    return Err(Error::ContextCreationFailed(WindowingApiError::Failed));
+                    return Err(Error::ContextCreationFailed(NativeError::in_operation(
+                        WindowingApiError::Failed,
+                        "wglCreateContextAttribsARB",
+                    )));
                 }
 
                 // Temporarily make the context current.
@@ -308,6 +344,7 @@ impl Device {
                 framebuffer: Framebuffer::None,
                 status: ContextStatus::Owned,
             };
+            leaks::track(TrackedObject::Context(context.id));
             next_context_id.0 += 1;
             Ok(context)
         }
@@ -343,6 +380,7 @@ impl Device {
             framebuffer: Framebuffer::External(()),
             status: ContextStatus::Referenced,
         };
+        leaks::track(TrackedObject::Context(context.id));
         next_context_id.0 += 1;
         Ok(context)
     }
@@ -371,6 +409,8 @@ impl Device {
 
         context.glrc = ptr::null_mut();
         context.status = ContextStatus::Destroyed;
+        debug::set_debug_callback(context.id, None);
+        leaks::untrack(TrackedObject::Context(context.id));
         Ok(())
     }
 
@@ -382,7 +422,10 @@ impl Device {
 
             let _guard = self.temporarily_make_context_current(context);
 
-            let gl_version = GLVersion::current(&context.gl);
+            // Every WGL context supports at least OpenGL 1.1, so assume that if the version
+            // string can't be parsed.
+            let gl_version =
+                GLVersion::current(&context.gl).unwrap_or_else(|_| GLVersion::new(1, 1));
             let compatibility_profile =
                 context::current_context_uses_compatibility_profile(&context.gl);
 
@@ -394,6 +437,30 @@ impl Device {
         }
     }
 
+    /// Inserts a fence into the command stream of the given context.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn create_fence(&self, _: &Context) -> Result<Fence, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
+    /// Makes the given context wait for a fence to be signaled.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn wait_fence(&self, _: &Context, _: &Fence) -> Result<(), Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
+    /// Destroys a fence.
+    ///
+    /// This backend doesn't support fences, so this returns an `UnsupportedOnThisPlatform` error.
+    #[inline]
+    pub fn destroy_fence(&self, _: &Context, fence: Fence) -> Result<(), (Error, Fence)> {
+        Err((Error::UnsupportedOnThisPlatform, fence))
+    }
+
     /// Returns the attributes that the context descriptor was created with.
     #[allow(non_snake_case)]
     pub fn context_descriptor_attributes(
@@ -436,6 +503,9 @@ impl Device {
             let mut attributes = ContextAttributes {
                 version: context_descriptor.gl_version,
                 flags: ContextAttributeFlags::empty(),
+                samples: 0,
+                depth_format: DepthFormat::Depth24,
+                priority: ContextPriority::Medium,
             };
             if alpha_bits > 0 {
                 attributes.flags.insert(ContextAttributeFlags::ALPHA);
@@ -451,6 +521,18 @@ impl Device {
         }
     }
 
+    /// Describes the pixel format behind the given context descriptor.
+    ///
+    /// This backend doesn't support pixel format introspection, so this always returns
+    /// `UnsupportedOnThisPlatform`.
+    #[inline]
+    pub fn context_descriptor_info(
+        &self,
+        _: &ContextDescriptor,
+    ) -> Result<ContextDescriptorInfo, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
+
     pub(crate) fn temporarily_bind_framebuffer<'a>(
         &self,
         context: &'a Context,
@@ -482,7 +564,10 @@ impl Device {
             if ok != FALSE {
                 Ok(())
             } else {
-                Err(Error::MakeCurrentFailed(WindowingApiError::Failed))
+                Err(Error::MakeCurrentFailed(NativeError::in_operation(
+                    WindowingApiError::Failed,
+                    "wglMakeCurrent",
+                )))
             }
         }
     }
@@ -498,7 +583,10 @@ impl Device {
             if ok != FALSE {
                 Ok(())
             } else {
-                Err(Error::MakeCurrentFailed(WindowingApiError::Failed))
+                Err(Error::MakeCurrentFailed(NativeError::in_operation(
+                    WindowingApiError::Failed,
+                    "wglMakeCurrent",
+                )))
             }
         }
     }
@@ -613,6 +701,24 @@ impl Device {
         }
     }
 
+    /// Returns whether the context has been lost to a GPU reset since it was created.
+    ///
+    /// This backend doesn't create robust contexts, so this always returns `NoReset`.
+    #[inline]
+    pub fn context_reset_status(&self, _: &Context) -> Result<ContextResetStatus, Error> {
+        Ok(ContextResetStatus::NoReset)
+    }
+
+    /// Returns the vendor, renderer, and version information of the given context.
+    ///
+    /// The context is temporarily made current if it isn't already.
+    /// If the version string is missing or can't be parsed, this returns an `UnknownGLVersion`
+    /// error.
+    pub fn context_gl_info(&self, context: &Context) -> Result<GLInfo, Error> {
+        let _guard = self.temporarily_make_context_current(context)?;
+        GLInfo::current(&context.gl)
+    }
+
     /// Given a context, returns its underlying `HGLRC`.
     #[inline]
     pub fn native_context(&self, context: &Context) -> NativeContext {
@@ -4,7 +4,7 @@
 
 use super::connection::Connection;
 use super::context::WGL_EXTENSION_FUNCTIONS;
-use crate::{Error, GLApi};
+use crate::{DeviceCapabilities, Error, GLApi};
 
 use std::marker::PhantomData;
 use std::mem;
@@ -237,6 +237,15 @@ impl Device {
     pub fn gl_api(&self) -> GLApi {
         GLApi::GL
     }
+
+    /// Describes what this device supports.
+    ///
+    /// This backend doesn't support capability queries yet, so this always returns
+    /// `UnsupportedOnThisPlatform`.
+    #[inline]
+    pub fn capabilities(&mut self) -> Result<DeviceCapabilities, Error> {
+        Err(Error::UnsupportedOnThisPlatform)
+    }
 }
 
 impl Adapter {
@@ -4,20 +4,20 @@
 
 use super::context::{self, Context, WGL_EXTENSION_FUNCTIONS};
 use super::device::Device;
-use crate::error::WindowingApiError;
+use crate::error::{NativeError, NativeErrorCode, WindowingApiError};
 use crate::renderbuffers::Renderbuffers;
-use crate::{ContextID, Error, SurfaceAccess, SurfaceID, SurfaceInfo, SurfaceType};
+use crate::{ContextID, Error, SurfaceAccess, SurfaceFormat, SurfaceID, SurfaceInfo, SurfaceType};
 
 use crate::gl;
 use crate::gl::types::{GLenum, GLint, GLuint};
 use crate::gl_utils;
+use crate::leaks::{self, TrackedObject};
 use euclid::default::Size2D;
 use std::fmt::{self, Debug, Formatter};
 use std::marker::PhantomData;
 use std::mem;
 use std::os::raw::c_void;
 use std::ptr;
-use std::thread;
 use winapi::shared::dxgi::IDXGIResource;
 use winapi::shared::dxgiformat::DXGI_FORMAT_R8G8B8A8_UNORM;
 use winapi::shared::dxgitype::DXGI_SAMPLE_DESC;
@@ -54,10 +54,12 @@ const WGL_ACCESS_READ_WRITE_NV: GLenum = 0x0001;
 ///
 /// Depending on the platform, each surface may be internally double-buffered.
 ///
-/// Surfaces must be destroyed with the `destroy_surface()` method, or a panic will occur.
+/// Surfaces must be destroyed with the `destroy_surface()` method. Dropping one without
+/// destroying it leaks it, and reports the leak as chosen with `set_leak_policy()`.
 pub struct Surface {
     pub(crate) size: Size2D<i32>,
     pub(crate) context_id: ContextID,
+    pub(crate) access: SurfaceAccess,
     pub(crate) win32_objects: Win32Objects,
     pub(crate) destroyed: bool,
 }
@@ -104,8 +106,8 @@ impl Debug for Surface {
 
 impl Drop for Surface {
     fn drop(&mut self) {
-        if !self.destroyed && !thread::panicking() {
-            panic!("Should have destroyed the surface first with `destroy_surface()`!")
+        if !self.destroyed {
+            leaks::dropped_without_destroying(TrackedObject::Surface(self.id()));
         }
     }
 }
@@ -132,20 +134,23 @@ impl Device {
     pub fn create_surface(
         &mut self,
         context: &Context,
-        _: SurfaceAccess,
+        access: SurfaceAccess,
         surface_type: SurfaceType<NativeWidget>,
     ) -> Result<Surface, Error> {
-        match surface_type {
-            SurfaceType::Generic { size } => self.create_generic_surface(context, &size),
+        let surface = match surface_type {
+            SurfaceType::Generic { size } => self.create_generic_surface(context, access, &size)?,
             SurfaceType::Widget { native_widget } => {
-                self.create_widget_surface(context, native_widget)
+                self.create_widget_surface(context, access, native_widget)?
             }
-        }
+        };
+        leaks::track(TrackedObject::Surface(surface.id()));
+        Ok(surface)
     }
 
     fn create_generic_surface(
         &mut self,
         context: &Context,
+        access: SurfaceAccess,
         size: &Size2D<i32>,
     ) -> Result<Surface, Error> {
         let dx_interop_functions = match WGL_EXTENSION_FUNCTIONS.dx_interop_functions {
@@ -179,7 +184,11 @@ impl Device {
                 &mut d3d11_texture,
             );
             if !winerror::SUCCEEDED(result) {
-                return Err(Error::SurfaceCreationFailed(WindowingApiError::Failed));
+                return Err(Error::SurfaceCreationFailed(NativeError::new(
+                    WindowingApiError::Failed,
+                    NativeErrorCode::HRESULT(result),
+                    "ID3D11Device::CreateTexture2D",
+                )));
             }
             assert!(!d3d11_texture.is_null());
             let d3d11_texture = ComPtr::from_raw(d3d11_texture);
@@ -227,7 +236,10 @@ impl Device {
                     "Unable to share surface between OpenGL and DirectX. OS error '{}'.",
                     msg
                 );
-                return Err(Error::SurfaceCreationFailed(WindowingApiError::Failed));
+                return Err(Error::SurfaceCreationFailed(NativeError::in_operation(
+                    WindowingApiError::Failed,
+                    "wglDXRegisterObjectNV",
+                )));
             }
 
             // Build our FBO.
@@ -256,6 +268,7 @@ impl Device {
             Ok(Surface {
                 size: *size,
                 context_id: context.id,
+                access,
                 win32_objects: Win32Objects::Texture {
                     d3d11_texture,
                     dxgi_share_handle,
@@ -272,6 +285,7 @@ impl Device {
     fn create_widget_surface(
         &mut self,
         context: &Context,
+        access: SurfaceAccess,
         native_widget: NativeWidget,
     ) -> Result<Surface, Error> {
         unsafe {
@@ -296,6 +310,7 @@ impl Device {
                     widget_rect.bottom - widget_rect.top,
                 ),
                 context_id: context.id,
+                access,
                 win32_objects: Win32Objects::Widget {
                     window_handle: native_widget.window_handle,
                 },
@@ -309,8 +324,8 @@ impl Device {
     /// The supplied context must be the context the surface is associated with, or this returns
     /// an `IncompatibleSurface` error.
     ///
-    /// You must explicitly call this method to dispose of a surface. Otherwise, a panic occurs in
-    /// the `drop` method.
+    /// You must explicitly call this method to dispose of a surface. Otherwise, the surface leaks
+    /// when it's dropped, and the leak is reported as chosen with `set_leak_policy()`.
     pub fn destroy_surface(
         &self,
         context: &mut Context,
@@ -355,6 +370,7 @@ impl Device {
                 Win32Objects::Widget { window_handle: _ } => {}
             }
 
+            leaks::untrack(TrackedObject::Surface(surface.id()));
             surface.destroyed = true;
         }
 
@@ -403,7 +419,11 @@ impl Device {
             );
             if !winerror::SUCCEEDED(result) || local_d3d11_texture.is_null() {
                 return Err((
-                    Error::SurfaceImportFailed(WindowingApiError::Failed),
+                    Error::SurfaceImportFailed(NativeError::new(
+                        WindowingApiError::Failed,
+                        NativeErrorCode::HRESULT(result),
+                        "OpenSharedResource",
+                    )),
                     surface,
                 ));
             }
@@ -458,6 +478,7 @@ impl Device {
             );
 
             // Finish up.
+            leaks::track(TrackedObject::SurfaceTexture(surface.id()));
             Ok(SurfaceTexture {
                 surface,
                 local_d3d11_texture,
@@ -512,6 +533,8 @@ impl Device {
             surface_texture.gl_texture = 0;
         }
 
+        leaks::untrack(TrackedObject::SurfaceTexture(surface_texture.surface.id()));
+
         Ok(surface_texture.surface)
     }
 
@@ -629,6 +652,8 @@ impl Device {
                 Win32Objects::Texture { gl_framebuffer, .. } => gl_framebuffer,
                 Win32Objects::Widget { .. } => 0,
             },
+            format: SurfaceFormat::RGBA8,
+            access: surface.access,
         }
     }
 
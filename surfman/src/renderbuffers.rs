@@ -2,77 +2,53 @@
 //
 //! A utility module for backends that wrap surfaces in OpenGL FBOs.
 
-use crate::context::{ContextAttributeFlags, ContextAttributes};
+use crate::context::{ContextAttributeFlags, ContextAttributes, DepthFormat};
 use crate::gl;
-use crate::gl::types::GLuint;
+use crate::gl::types::{GLenum, GLint, GLsizei, GLuint};
 use crate::Gl;
 
 use euclid::default::Size2D;
+use std::ptr;
 
+// These are owned by a surface, which is responsible for destroying them, and for reporting them
+// as leaked if it's dropped without being destroyed.
 pub(crate) enum Renderbuffers {
     IndividualDepthStencil { depth: GLuint, stencil: GLuint },
     CombinedDepthStencil(GLuint),
 }
 
-impl Drop for Renderbuffers {
-    fn drop(&mut self) {
-        match *self {
-            Renderbuffers::IndividualDepthStencil {
-                depth: 0,
-                stencil: 0,
-            }
-            | Renderbuffers::CombinedDepthStencil(0) => {}
-            _ => panic!("Should have destroyed the FBO renderbuffers with `destroy()`!"),
-        }
-    }
-}
-
 impl Renderbuffers {
+    #[allow(dead_code)]
     pub(crate) fn new(
         gl: &Gl,
         size: &Size2D<i32>,
         attributes: &ContextAttributes,
+    ) -> Renderbuffers {
+        Renderbuffers::new_multisampled(gl, size, attributes, 0)
+    }
+
+    // Allocates renderbuffers with the given number of samples per pixel, or single-sampled ones
+    // if `samples` is 0.
+    pub(crate) fn new_multisampled(
+        gl: &Gl,
+        size: &Size2D<i32>,
+        attributes: &ContextAttributes,
+        samples: GLsizei,
     ) -> Renderbuffers {
         unsafe {
-            if attributes
-                .flags
-                .contains(ContextAttributeFlags::DEPTH | ContextAttributeFlags::STENCIL)
-            {
-                let mut renderbuffer = 0;
-                gl.GenRenderbuffers(1, &mut renderbuffer);
-                gl.BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
-                gl.RenderbufferStorage(
-                    gl::RENDERBUFFER,
-                    gl::DEPTH24_STENCIL8,
-                    size.width,
-                    size.height,
-                );
-                gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
+            let (depth_format, stencil_format) = depth_stencil_formats(attributes);
+            if is_packed_depth_stencil_format(depth_format) {
+                let renderbuffer = create_renderbuffer(gl, samples, depth_format, size);
                 return Renderbuffers::CombinedDepthStencil(renderbuffer);
             }
 
             let (mut depth_renderbuffer, mut stencil_renderbuffer) = (0, 0);
-            if attributes.flags.contains(ContextAttributeFlags::DEPTH) {
-                gl.GenRenderbuffers(1, &mut depth_renderbuffer);
-                gl.BindRenderbuffer(gl::RENDERBUFFER, depth_renderbuffer);
-                gl.RenderbufferStorage(
-                    gl::RENDERBUFFER,
-                    gl::DEPTH_COMPONENT24,
-                    size.width,
-                    size.height,
-                );
+            if depth_format != 0 {
+                depth_renderbuffer = create_renderbuffer(gl, samples, depth_format, size);
             }
-            if attributes.flags.contains(ContextAttributeFlags::STENCIL) {
-                gl.GenRenderbuffers(1, &mut stencil_renderbuffer);
-                gl.BindRenderbuffer(gl::RENDERBUFFER, stencil_renderbuffer);
-                gl.RenderbufferStorage(
-                    gl::RENDERBUFFER,
-                    gl::STENCIL_INDEX8,
-                    size.width,
-                    size.height,
-                );
+            if stencil_format != 0 {
+                stencil_renderbuffer = create_renderbuffer(gl, samples, stencil_format, size);
             }
-            gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
 
             Renderbuffers::IndividualDepthStencil {
                 depth: depth_renderbuffer,
@@ -147,3 +123,117 @@ impl Renderbuffers {
         }
     }
 }
+
+// Returns the internal formats of the depth and stencil buffers that surfaces with the given
+// attributes need, or 0 for buffers that they don't need. If depth and stencil are packed, the
+// packed format is returned as the depth format, and the stencil format is 0.
+fn depth_stencil_formats(attributes: &ContextAttributes) -> (GLenum, GLenum) {
+    let depth = attributes.flags.contains(ContextAttributeFlags::DEPTH);
+    let stencil = attributes.flags.contains(ContextAttributeFlags::STENCIL);
+    match (depth, stencil, attributes.depth_format) {
+        // There's no packed format with 16-bit depth, and separate depth and stencil buffers are
+        // often unsupported, so round 16-bit depth up.
+        (true, true, DepthFormat::Depth16) | (true, true, DepthFormat::Depth24) => {
+            (gl::DEPTH24_STENCIL8, 0)
+        }
+        (true, true, DepthFormat::Depth32F) => (gl::DEPTH32F_STENCIL8, 0),
+        (true, false, DepthFormat::Depth16) => (gl::DEPTH_COMPONENT16, 0),
+        (true, false, DepthFormat::Depth24) => (gl::DEPTH_COMPONENT24, 0),
+        (true, false, DepthFormat::Depth32F) => (gl::DEPTH_COMPONENT32F, 0),
+        (false, true, _) => (0, gl::STENCIL_INDEX8),
+        (false, false, _) => (0, 0),
+    }
+}
+
+fn is_packed_depth_stencil_format(format: GLenum) -> bool {
+    format == gl::DEPTH24_STENCIL8 || format == gl::DEPTH32F_STENCIL8
+}
+
+// Creates a texture to serve as the depth buffer of a framebuffer for surfaces with the given
+// attributes, and as the stencil buffer too if the depth format is packed with stencil. Returns
+// the texture and the framebuffer attachment point that it belongs to, or `None` if the
+// attributes don't call for a depth buffer.
+pub(crate) unsafe fn create_depth_texture(
+    gl: &Gl,
+    size: &Size2D<i32>,
+    attributes: &ContextAttributes,
+) -> Option<(GLuint, GLenum)> {
+    let (internal_format, _) = depth_stencil_formats(attributes);
+    let (format, type_, attachment) = match internal_format {
+        0 => return None,
+        gl::DEPTH_COMPONENT16 => (
+            gl::DEPTH_COMPONENT,
+            gl::UNSIGNED_SHORT,
+            gl::DEPTH_ATTACHMENT,
+        ),
+        gl::DEPTH_COMPONENT24 => (gl::DEPTH_COMPONENT, gl::UNSIGNED_INT, gl::DEPTH_ATTACHMENT),
+        gl::DEPTH_COMPONENT32F => (gl::DEPTH_COMPONENT, gl::FLOAT, gl::DEPTH_ATTACHMENT),
+        gl::DEPTH24_STENCIL8 => (
+            gl::DEPTH_STENCIL,
+            gl::UNSIGNED_INT_24_8,
+            gl::DEPTH_STENCIL_ATTACHMENT,
+        ),
+        _ => (
+            gl::DEPTH_STENCIL,
+            gl::FLOAT_32_UNSIGNED_INT_24_8_REV,
+            gl::DEPTH_STENCIL_ATTACHMENT,
+        ),
+    };
+
+    let mut old_texture_object = 0;
+    gl.GetIntegerv(gl::TEXTURE_BINDING_2D, &mut old_texture_object);
+    let mut texture_object = 0;
+    gl.GenTextures(1, &mut texture_object);
+    gl.BindTexture(gl::TEXTURE_2D, texture_object);
+    gl.TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        internal_format as GLint,
+        size.width,
+        size.height,
+        0,
+        format,
+        type_,
+        ptr::null(),
+    );
+    // Depth textures can't be filtered on OpenGL ES.
+    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+    gl.TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+    gl.BindTexture(gl::TEXTURE_2D, old_texture_object as GLuint);
+    Some((texture_object, attachment))
+}
+
+// Creates a renderbuffer with the given storage, multisampled if `samples` is nonzero.
+pub(crate) unsafe fn create_renderbuffer(
+    gl: &Gl,
+    samples: GLsizei,
+    internal_format: GLenum,
+    size: &Size2D<i32>,
+) -> GLuint {
+    let mut renderbuffer = 0;
+    gl.GenRenderbuffers(1, &mut renderbuffer);
+    gl.BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+    renderbuffer_storage(gl, samples, internal_format, size);
+    gl.BindRenderbuffer(gl::RENDERBUFFER, 0);
+    renderbuffer
+}
+
+// Allocates storage for the currently-bound renderbuffer, multisampled if `samples` is nonzero.
+unsafe fn renderbuffer_storage(
+    gl: &Gl,
+    samples: GLsizei,
+    internal_format: GLenum,
+    size: &Size2D<i32>,
+) {
+    if samples > 0 {
+        gl.RenderbufferStorageMultisample(
+            gl::RENDERBUFFER,
+            samples,
+            internal_format,
+            size.width,
+            size.height,
+        );
+    } else {
+        gl.RenderbufferStorage(gl::RENDERBUFFER, internal_format, size.width, size.height);
+    }
+}
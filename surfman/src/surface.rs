@@ -28,6 +28,10 @@ pub struct SurfaceInfo {
     ///
     /// This is only valid when the surface is actually attached to a context.
     pub framebuffer_object: GLuint,
+    /// The format of the surface's color buffer.
+    pub format: SurfaceFormat,
+    /// How the CPU can access the surface, as requested when it was created.
+    pub access: SurfaceAccess,
 }
 
 // The default framebuffer for a context.
@@ -44,7 +48,7 @@ pub(crate) enum Framebuffer<S, E> {
 /// A unique ID per allocated surface.
 ///
 /// If you destroy a surface and then create a new one, the ID may be reused.
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct SurfaceID(pub usize);
 
 impl Display for SurfaceID {
@@ -92,6 +96,38 @@ pub enum SurfaceAccess {
     GPUCPUWriteCombined,
 }
 
+/// The format of the color buffer of a surface.
+///
+/// Formats other than `RGBA8` can only be requested for generic surfaces, and only on some
+/// backends. If the driver can't create or render to a surface of the requested format, surface
+/// creation fails with an `UnsupportedSurfaceFormat` error.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum SurfaceFormat {
+    /// 8 bits per channel, in RGBA order. This is the default, and is supported everywhere.
+    RGBA8,
+    /// 8 bits per channel, in BGRA order.
+    ///
+    /// This is mostly useful for surfaces whose data is accessed by the CPU or shared with APIs
+    /// that prefer BGRA, such as many compositors.
+    BGRA8,
+    /// A 16-bit floating point value per channel, in RGBA order, for high dynamic range content.
+    RGBA16F,
+    /// 10 bits per color channel and 2 bits of alpha, for content with a wide color gamut.
+    RGB10A2,
+    /// 8 bits per channel, in RGBA order, with the color channels stored sRGB-encoded.
+    ///
+    /// On desktop OpenGL, writes to the surface are only encoded if `GL_FRAMEBUFFER_SRGB` is
+    /// enabled; on OpenGL ES, they always are.
+    SRGB8A8,
+}
+
+impl Default for SurfaceFormat {
+    #[inline]
+    fn default() -> SurfaceFormat {
+        SurfaceFormat::RGBA8
+    }
+}
+
 /// Information specific to the type of surface: generic or widget.
 pub enum SurfaceType<NativeWidget> {
     /// An off-screen surface that has a pixel size. Generic surfaces can sometimes be shown on
@@ -0,0 +1,292 @@
+// surfman/surfman/src/surface_pool.rs
+//
+//! A cache of generic surfaces, for applications that create and destroy many of them.
+
+use crate::device::Device as DeviceInterface;
+use crate::{ContextAttributeFlags, ContextID, DepthFormat, Error, SurfaceAccess, SurfaceFormat};
+use crate::{SurfaceInfo, SurfaceType};
+
+use euclid::default::Size2D;
+use std::fmt::{self, Debug, Formatter};
+
+/// A cache of generic surfaces that are no longer in use but may be reused.
+///
+/// Creating a surface allocates several GPU objects, which is expensive for applications that
+/// churn through many surfaces of the same size. Instead of destroying surfaces, such applications
+/// can hand them back to a pool with `release_surface()` and get them back from
+/// `create_surface()`. A cached surface is only reused for a request with the same context, size,
+/// surface access, and format, and only if the context's surface attributes (flags, sample count,
+/// and depth format) haven't changed.
+///
+/// The pool keeps the estimated memory usage of its cached surfaces under a budget by destroying
+/// the least recently released ones. Surfaces can only be destroyed with the context that created
+/// them, so surfaces belonging to other contexts are never evicted implicitly; use `trim()` or
+/// `clear()` with each context for that. In particular, clear the pool of a context's surfaces
+/// before destroying that context.
+///
+/// Like surfaces, cached surfaces must be destroyed explicitly. Dropping a pool that still holds
+/// surfaces will panic.
+pub struct SurfacePool<Device>
+where
+    Device: DeviceInterface,
+{
+    entries: Vec<SurfacePoolEntry<Device::Surface>>,
+    budget: usize,
+    cached_bytes: usize,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct SurfacePoolKey {
+    context_id: ContextID,
+    size: Size2D<i32>,
+    surface_access: SurfaceAccess,
+    format: SurfaceFormat,
+    flags: ContextAttributeFlags,
+    samples: u8,
+    depth_format: DepthFormat,
+}
+
+struct SurfacePoolEntry<Surface> {
+    key: SurfacePoolKey,
+    surface: Surface,
+    bytes: usize,
+}
+
+impl<Device> Debug for SurfacePool<Device>
+where
+    Device: DeviceInterface,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("SurfacePool")
+            .field("surfaces", &self.entries.len())
+            .field("budget", &self.budget)
+            .field("cached_bytes", &self.cached_bytes)
+            .finish()
+    }
+}
+
+impl<Device> SurfacePool<Device>
+where
+    Device: DeviceInterface,
+{
+    /// Creates an empty pool that caches up to roughly `budget` bytes of surfaces.
+    pub fn new(budget: usize) -> SurfacePool<Device> {
+        SurfacePool {
+            entries: vec![],
+            budget,
+            cached_bytes: 0,
+        }
+    }
+
+    /// Returns the memory budget of this pool, in bytes.
+    #[inline]
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Changes the memory budget of this pool.
+    ///
+    /// Lowering the budget doesn't destroy anything by itself; surfaces are evicted as they're
+    /// released, or explicitly with `trim()`.
+    #[inline]
+    pub fn set_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    /// Returns the estimated memory used by the surfaces in this pool, in bytes.
+    #[inline]
+    pub fn cached_bytes(&self) -> usize {
+        self.cached_bytes
+    }
+
+    /// Returns the number of surfaces in this pool.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if this pool holds no surfaces.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns a generic RGBA8 surface for the given context, reusing a cached one if possible.
+    ///
+    /// The contents of a reused surface are undefined.
+    pub fn create_surface(
+        &mut self,
+        device: &mut Device,
+        context: &Device::Context,
+        surface_access: SurfaceAccess,
+        size: Size2D<i32>,
+    ) -> Result<Device::Surface, Error> {
+        let key = SurfacePoolKey::new(device, context, surface_access, SurfaceFormat::RGBA8, size);
+        match self.entries.iter().rposition(|entry| entry.key == key) {
+            Some(index) => {
+                let entry = self.entries.remove(index);
+                self.cached_bytes -= entry.bytes;
+                Ok(entry.surface)
+            }
+            None => device.create_surface(context, surface_access, SurfaceType::Generic { size }),
+        }
+    }
+
+    /// Hands a surface back to this pool for reuse.
+    ///
+    /// The supplied context must be the context the surface is associated with, or an
+    /// `IncompatibleSurface` error is returned alongside the surface. The surface is only handed
+    /// out again for requests matching the access and format it was created with.
+    ///
+    /// If caching the surface would exceed the budget, the least recently released surfaces of the
+    /// same context are destroyed to make room. If that isn't enough, the surface is destroyed
+    /// instead of being cached.
+    pub fn release_surface(
+        &mut self,
+        device: &Device,
+        context: &mut Device::Context,
+        mut surface: Device::Surface,
+    ) -> Result<(), (Error, Device::Surface)> {
+        let SurfaceInfo {
+            context_id,
+            size,
+            access,
+            format,
+            ..
+        } = device.surface_info(&surface);
+        if context_id != device.context_id(context) {
+            return Err((Error::IncompatibleSurface, surface));
+        }
+
+        let key = SurfacePoolKey::new(device, context, access, format, size);
+        let bytes = key.estimated_bytes();
+        if bytes <= self.budget {
+            if let Err(err) = self.trim(device, context, self.budget - bytes) {
+                return Err((err, surface));
+            }
+        }
+        if self.cached_bytes + bytes > self.budget {
+            return match device.destroy_surface(context, &mut surface) {
+                Ok(()) => Ok(()),
+                Err(err) => Err((err, surface)),
+            };
+        }
+
+        self.entries.push(SurfacePoolEntry {
+            key,
+            surface,
+            bytes,
+        });
+        self.cached_bytes += bytes;
+        Ok(())
+    }
+
+    /// Destroys the least recently released surfaces of the given context until the pool uses at
+    /// most `target_bytes`, or until none of that context's surfaces remain.
+    pub fn trim(
+        &mut self,
+        device: &Device,
+        context: &mut Device::Context,
+        target_bytes: usize,
+    ) -> Result<(), Error> {
+        let context_id = device.context_id(context);
+        while self.cached_bytes > target_bytes {
+            let index = match self
+                .entries
+                .iter()
+                .position(|entry| entry.key.context_id == context_id)
+            {
+                Some(index) => index,
+                None => break,
+            };
+            let mut entry = self.entries.remove(index);
+            if let Err(err) = device.destroy_surface(context, &mut entry.surface) {
+                self.entries.insert(index, entry);
+                return Err(err);
+            }
+            self.cached_bytes -= entry.bytes;
+        }
+        Ok(())
+    }
+
+    /// Destroys every cached surface of the given context.
+    pub fn clear(&mut self, device: &Device, context: &mut Device::Context) -> Result<(), Error> {
+        let context_id = device.context_id(context);
+        while let Some(index) = self
+            .entries
+            .iter()
+            .position(|entry| entry.key.context_id == context_id)
+        {
+            let mut entry = self.entries.remove(index);
+            if let Err(err) = device.destroy_surface(context, &mut entry.surface) {
+                self.entries.insert(index, entry);
+                return Err(err);
+            }
+            self.cached_bytes -= entry.bytes;
+        }
+        Ok(())
+    }
+}
+
+impl SurfacePoolKey {
+    fn new<Device>(
+        device: &Device,
+        context: &Device::Context,
+        surface_access: SurfaceAccess,
+        format: SurfaceFormat,
+        size: Size2D<i32>,
+    ) -> SurfacePoolKey
+    where
+        Device: DeviceInterface,
+    {
+        let context_descriptor = device.context_descriptor(context);
+        let context_attributes = device.context_descriptor_attributes(&context_descriptor);
+        SurfacePoolKey {
+            context_id: device.context_id(context),
+            size,
+            surface_access,
+            format,
+            flags: context_attributes.flags,
+            samples: context_attributes.samples,
+            depth_format: context_attributes.depth_format,
+        }
+    }
+
+    // Estimates the GPU memory used by a surface: its color buffer, plus depth and stencil buffers
+    // if requested, which are packed together if both are present. Multisampled surfaces have a
+    // multisampled copy of each buffer, and keep the single-sampled color buffer to resolve into.
+    fn estimated_bytes(&self) -> usize {
+        let color_bytes = color_bytes_per_pixel(self.format);
+        let depth_stencil_bytes = match (
+            self.flags.contains(ContextAttributeFlags::DEPTH),
+            self.flags.contains(ContextAttributeFlags::STENCIL),
+        ) {
+            (false, false) => 0,
+            (false, true) => 1,
+            (true, false) => match self.depth_format {
+                DepthFormat::Depth16 => 2,
+                DepthFormat::Depth24 | DepthFormat::Depth32F => 4,
+            },
+            (true, true) => match self.depth_format {
+                DepthFormat::Depth16 | DepthFormat::Depth24 => 4,
+                DepthFormat::Depth32F => 8,
+            },
+        };
+        let bytes_per_pixel = if self.samples > 1 {
+            color_bytes + (color_bytes + depth_stencil_bytes) * self.samples as usize
+        } else {
+            color_bytes + depth_stencil_bytes
+        };
+        self.size.width.max(0) as usize * self.size.height.max(0) as usize * bytes_per_pixel
+    }
+}
+
+fn color_bytes_per_pixel(format: SurfaceFormat) -> usize {
+    match format {
+        SurfaceFormat::RGBA8
+        | SurfaceFormat::BGRA8
+        | SurfaceFormat::RGB10A2
+        | SurfaceFormat::SRGB8A8 => 4,
+        SurfaceFormat::RGBA16F => 8,
+    }
+}
@@ -0,0 +1,258 @@
+// surfman/surfman/src/swap_chain.rs
+//
+//! A pool of generic surfaces that rotate through a context.
+
+use crate::device::Device as DeviceInterface;
+use crate::{ContextID, Error, SurfaceAccess, SurfaceType};
+
+use euclid::default::Size2D;
+use std::fmt::{self, Debug, Formatter};
+
+/// A set of generic surfaces that take turns being rendered to by a single context.
+///
+/// A swap chain keeps one surface, the *back buffer*, attached to its context. Calling
+/// `swap_buffers()` detaches the back buffer, making it the *front buffer*, and attaches a fresh
+/// back buffer. The consumer can then claim the front buffer with `take_surface()`, wrap it in a
+/// surface texture to read from it, and hand it back with `recycle_surface()` once it's done.
+/// Surfaces that are handed back are reused for later back buffers instead of being allocated
+/// anew.
+///
+/// All methods that take a context must be passed the context that the swap chain was created
+/// with, or an `IncompatibleContext` error is returned. That context should be current when
+/// calling them.
+///
+/// Like surfaces, swap chains must be destroyed explicitly, with `destroy()`. Otherwise, a panic
+/// occurs when the surfaces they hold are dropped.
+///
+/// A swap chain is `Send` if the surfaces of its device are.
+pub struct SwapChain<Device>
+where
+    Device: DeviceInterface,
+{
+    context_id: ContextID,
+    size: Size2D<i32>,
+    surface_access: SurfaceAccess,
+    front_buffer: Option<Device::Surface>,
+    recycled_surfaces: Vec<Device::Surface>,
+}
+
+impl<Device> Debug for SwapChain<Device>
+where
+    Device: DeviceInterface,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        f.debug_struct("SwapChain")
+            .field("context_id", &self.context_id)
+            .field("size", &self.size)
+            .field("surface_access", &self.surface_access)
+            .field("has_front_buffer", &self.front_buffer.is_some())
+            .field("recycled_surfaces", &self.recycled_surfaces.len())
+            .finish()
+    }
+}
+
+impl<Device> SwapChain<Device>
+where
+    Device: DeviceInterface,
+{
+    /// Creates a new swap chain for the given context and attaches its first back buffer to it.
+    ///
+    /// The context must not have a surface attached already, or a `SurfaceAlreadyBound` error is
+    /// returned.
+    pub fn new(
+        device: &mut Device,
+        context: &mut Device::Context,
+        surface_access: SurfaceAccess,
+        size: Size2D<i32>,
+    ) -> Result<SwapChain<Device>, Error> {
+        let surface_type = SurfaceType::Generic { size };
+        let mut back_buffer = device.create_surface(context, surface_access, surface_type)?;
+        if let Err((err, returned_surface)) = device.bind_surface_to_context(context, back_buffer) {
+            back_buffer = returned_surface;
+            device.destroy_surface(context, &mut back_buffer)?;
+            return Err(err);
+        }
+
+        Ok(SwapChain {
+            context_id: device.context_id(context),
+            size,
+            surface_access,
+            front_buffer: None,
+            recycled_surfaces: vec![],
+        })
+    }
+
+    /// Returns the ID of the context that this swap chain renders with.
+    #[inline]
+    pub fn context_id(&self) -> ContextID {
+        self.context_id
+    }
+
+    /// Returns the size of the surfaces in this swap chain.
+    #[inline]
+    pub fn size(&self) -> Size2D<i32> {
+        self.size
+    }
+
+    /// Returns how the CPU can access the surfaces in this swap chain.
+    #[inline]
+    pub fn surface_access(&self) -> SurfaceAccess {
+        self.surface_access
+    }
+
+    /// Makes the back buffer the front buffer and attaches a new back buffer to the context.
+    ///
+    /// The new back buffer is a recycled surface if one is available; otherwise it is freshly
+    /// created. If the previous front buffer was never taken with `take_surface()`, it is
+    /// recycled.
+    ///
+    /// Any pending OpenGL commands targeting the old back buffer are flushed when it's detached
+    /// from the context, as with `Device::unbind_surface_from_context()`, so the new front buffer
+    /// is safe to read from immediately when this function returns.
+    pub fn swap_buffers(
+        &mut self,
+        device: &mut Device,
+        context: &mut Device::Context,
+    ) -> Result<(), Error> {
+        self.check_context(device, context)?;
+
+        let new_back_buffer = self.next_back_buffer(device, context)?;
+        let old_back_buffer = device.unbind_surface_from_context(context)?;
+        if let Err((err, new_back_buffer)) =
+            device.bind_surface_to_context(context, new_back_buffer)
+        {
+            self.recycled_surfaces.push(new_back_buffer);
+            if let Some(old_back_buffer) = old_back_buffer {
+                device
+                    .bind_surface_to_context(context, old_back_buffer)
+                    .map_err(|(err, _)| err)?;
+            }
+            return Err(err);
+        }
+
+        if let Some(old_back_buffer) = old_back_buffer {
+            if let Some(old_front_buffer) = self.front_buffer.replace(old_back_buffer) {
+                self.recycled_surfaces.push(old_front_buffer);
+            }
+        }
+        Ok(())
+    }
+
+    /// Takes ownership of the front buffer, if there is one.
+    ///
+    /// Once you're done with the surface, hand it back with `recycle_surface()` so that it can be
+    /// reused, or destroy it yourself.
+    #[inline]
+    pub fn take_surface(&mut self) -> Option<Device::Surface> {
+        self.front_buffer.take()
+    }
+
+    /// Returns a surface to this swap chain so that it can be reused as a back buffer.
+    ///
+    /// The surface must have been created with this swap chain's context, or an
+    /// `IncompatibleSurface` error is returned alongside the surface. It need not be the same size
+    /// as the swap chain; stale surfaces are resized when they are next used.
+    pub fn recycle_surface(
+        &mut self,
+        device: &Device,
+        surface: Device::Surface,
+    ) -> Result<(), (Error, Device::Surface)> {
+        if device.surface_info(&surface).context_id != self.context_id {
+            return Err((Error::IncompatibleSurface, surface));
+        }
+        self.recycled_surfaces.push(surface);
+        Ok(())
+    }
+
+    /// Resizes every surface that this swap chain holds, including the attached back buffer.
+    ///
+    /// The contents of the back buffer are undefined afterward. The front buffer, if one is
+    /// pending, keeps its old size and contents; it is resized if it comes back through
+    /// `recycle_surface()`.
+    pub fn resize(
+        &mut self,
+        device: &mut Device,
+        context: &mut Device::Context,
+        size: Size2D<i32>,
+    ) -> Result<(), Error> {
+        self.check_context(device, context)?;
+
+        if let Some(mut back_buffer) = device.unbind_surface_from_context(context)? {
+            let result = device.resize_surface(context, &mut back_buffer, size);
+            device
+                .bind_surface_to_context(context, back_buffer)
+                .map_err(|(err, _)| err)?;
+            result?;
+        }
+        for surface in &mut self.recycled_surfaces {
+            device.resize_surface(context, surface, size)?;
+        }
+
+        self.size = size;
+        Ok(())
+    }
+
+    /// Destroys every surface that this swap chain holds, including the attached back buffer.
+    ///
+    /// Surfaces that were taken with `take_surface()` and not recycled are not destroyed; they
+    /// remain the caller's responsibility.
+    ///
+    /// If destroying a surface fails, the remaining surfaces are still destroyed, and the first
+    /// error is returned.
+    pub fn destroy(
+        &mut self,
+        device: &mut Device,
+        context: &mut Device::Context,
+    ) -> Result<(), Error> {
+        self.check_context(device, context)?;
+
+        let mut surfaces = vec![];
+        let mut result = match device.unbind_surface_from_context(context) {
+            Ok(back_buffer) => {
+                surfaces.extend(back_buffer);
+                Ok(())
+            }
+            Err(err) => Err(err),
+        };
+        surfaces.extend(self.front_buffer.take());
+        surfaces.append(&mut self.recycled_surfaces);
+
+        for mut surface in surfaces {
+            if let Err(err) = device.destroy_surface(context, &mut surface) {
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+        result
+    }
+
+    fn check_context(&self, device: &Device, context: &Device::Context) -> Result<(), Error> {
+        if device.context_id(context) == self.context_id {
+            Ok(())
+        } else {
+            Err(Error::IncompatibleContext)
+        }
+    }
+
+    fn next_back_buffer(
+        &mut self,
+        device: &mut Device,
+        context: &Device::Context,
+    ) -> Result<Device::Surface, Error> {
+        let mut surface = match self.recycled_surfaces.pop() {
+            Some(surface) => surface,
+            None => {
+                let surface_type = SurfaceType::Generic { size: self.size };
+                return device.create_surface(context, self.surface_access, surface_type);
+            }
+        };
+        if device.surface_info(&surface).size != self.size {
+            if let Err(err) = device.resize_surface(context, &mut surface, self.size) {
+                self.recycled_surfaces.push(surface);
+                return Err(err);
+            }
+        }
+        Ok(surface)
+    }
+}
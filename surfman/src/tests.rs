@@ -12,14 +12,34 @@ use super::connection::Connection;
 use super::context::{Context, ContextDescriptor, NativeContext};
 use super::device::{Adapter, Device};
 use super::surface::Surface;
+#[cfg(linux)]
+use super::surface::SurfaceShareHandle;
+#[cfg(linux)]
+use crate::device::Device as DeviceInterface;
 use crate::gl;
 use crate::gl::types::{GLenum, GLuint};
-use crate::{ContextAttributeFlags, ContextAttributes, Error, GLApi, GLVersion, Gl, SurfaceAccess};
-use crate::{SurfaceType, WindowingApiError};
+#[cfg(linux)]
+use crate::gles::{self, Gles2};
+#[cfg(linux)]
+use crate::platform::generic::egl::device::EGL_FUNCTIONS;
+use crate::{leak_policy, set_leak_policy, LeakPolicy, TrackedObject};
+use crate::{set_debug_callback, DebugMessage, DebugSeverity, GLApi, GLVersion, Gl};
+#[cfg(linux)]
+use crate::{set_egl_functions, set_egl_library_path};
+use crate::{ContextAttributeFlags, ContextAttributes, ContextPriority, ContextResetStatus};
+use crate::{DepthFormat, Error, NativeError, NativeErrorCode};
+use crate::{SurfaceAccess, SurfaceFormat, SurfacePool, SurfaceType, SwapChain, WindowingApiError};
 
 use euclid::default::Size2D;
+use std::error::Error as StdError;
+#[cfg(linux)]
+use std::ffi::CStr;
+#[cfg(linux)]
+use std::os::raw::c_char;
 use std::os::raw::c_void;
-use std::sync::mpsc;
+#[cfg(linux)]
+use std::os::unix::net::UnixStream;
+use std::sync::{mpsc, Mutex};
 use std::thread;
 
 static GL_VERSIONS: [GLVersion; 6] = [
@@ -103,9 +123,15 @@ pub fn test_context_creation() {
     };
 
     for &version in versions {
-        for flag_bits in 0..(ContextAttributeFlags::all().bits() + 1) {
+        for flag_bits in 0..=ContextAttributeFlags::all().bits() {
             let flags = ContextAttributeFlags::from_bits_truncate(flag_bits);
-            let attributes = ContextAttributes { version, flags };
+            let attributes = ContextAttributes {
+                version,
+                flags,
+                samples: 0,
+                depth_format: DepthFormat::Depth24,
+                priority: ContextPriority::Medium,
+            };
             let descriptor = match device.create_context_descriptor(&attributes) {
                 Ok(descriptor) => descriptor,
                 Err(Error::UnsupportedGLProfile) | Err(Error::UnsupportedGLVersion) => {
@@ -117,16 +143,23 @@ pub fn test_context_creation() {
 
             match device.create_context(&descriptor, None) {
                 Ok(mut context) => {
-                    // Verify that the attributes round-trip.
+                    // Verify that the attributes round-trip. Debug and robust contexts always
+                    // check for errors, so they can't be no-error contexts.
                     let actual_descriptor = device.context_descriptor(&context);
                     let actual_attributes =
                         device.context_descriptor_attributes(&actual_descriptor);
-                    if !actual_attributes.flags.contains(attributes.flags) {
+                    let mut expected_flags = attributes.flags;
+                    if expected_flags.intersects(
+                        ContextAttributeFlags::DEBUG | ContextAttributeFlags::ROBUST_ACCESS,
+                    ) {
+                        expected_flags.remove(ContextAttributeFlags::NO_ERROR);
+                    }
+                    if !actual_attributes.flags.contains(expected_flags) {
                         device.destroy_context(&mut context).unwrap();
                         panic!(
                             "Expected at least attribute flags {:?} for {:?} {:?} but got \
                                 {:?}",
-                            attributes.flags, gl_api, version, actual_attributes.flags
+                            expected_flags, gl_api, version, actual_attributes.flags
                         );
                     }
                     if actual_attributes.version.major < attributes.version.major
@@ -142,9 +175,13 @@ pub fn test_context_creation() {
 
                     device.destroy_context(&mut context).unwrap();
                 }
-                Err(Error::ContextCreationFailed(WindowingApiError::BadPixelFormat))
-                | Err(Error::ContextCreationFailed(WindowingApiError::BadConfig))
-                | Err(Error::ContextCreationFailed(WindowingApiError::BadMatch)) => {
+                Err(Error::ContextCreationFailed(NativeError {
+                    kind:
+                        WindowingApiError::BadPixelFormat
+                        | WindowingApiError::BadConfig
+                        | WindowingApiError::BadMatch,
+                    ..
+                })) => {
                     // This is OK, as it just means the GL implementation didn't support the
                     // requested GL version.
                 }
@@ -179,6 +216,9 @@ pub fn test_newly_created_contexts_are_not_current() {
         .create_context_descriptor(&ContextAttributes {
             version: GLVersion::new(3, 0),
             flags: ContextAttributeFlags::empty(),
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
         })
         .unwrap();
 
@@ -247,6 +287,9 @@ pub fn test_context_sharing() {
         .create_context_descriptor(&ContextAttributes {
             version: GLVersion::new(3, 0),
             flags: ContextAttributeFlags::empty(),
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
         })
         .unwrap();
 
@@ -287,6 +330,9 @@ pub fn test_generic_surface_creation() {
         .create_context_descriptor(&ContextAttributes {
             version: GLVersion::new(3, 0),
             flags: ContextAttributeFlags::empty(),
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
         })
         .unwrap();
 
@@ -649,209 +695,1554 @@ pub fn test_cross_thread_surface_texture_blit_framebuffer() {
     }
 }
 
-// Tests that surface textures are not upside-down.
+// Tests that fences make a consumer context wait for rendering done on another thread.
+#[cfg(linux)]
 #[cfg_attr(not(feature = "sm-test"), test)]
-pub fn test_surface_texture_right_side_up() {
+pub fn test_cross_thread_fence() {
     let mut env = match BasicEnvironment::new() {
         None => return,
         Some(env) => env,
     };
 
-    unsafe {
-        clear(&env.gl, &[255, 0, 0, 255]);
-        clear_bottom_row(&env.gl, &[0, 255, 0, 255]);
-        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+    let (to_main_sender, to_main_receiver) = mpsc::channel();
+    let (to_worker_sender, to_worker_receiver) = mpsc::channel();
 
-        let subsurface = env
-            .device
-            .unbind_surface_from_context(&mut env.context)
-            .unwrap()
+    let other_connection = env.connection.clone();
+    let other_adapter = env.adapter.clone();
+    let other_context_descriptor = env.context_descriptor.clone();
+    thread::spawn(move || {
+        let mut device = other_connection.create_device(&other_adapter).unwrap();
+        let mut context = device
+            .create_context(&other_context_descriptor, None)
             .unwrap();
-        let subsurface_texture = env
-            .device
-            .create_surface_texture(&mut env.context, subsurface)
+        let gl = Gl::load_with(|symbol| device.get_proc_address(&context, symbol));
+
+        let surface = make_surface(&mut device, &context);
+        device
+            .bind_surface_to_context(&mut context, surface)
             .unwrap();
+        device.make_context_current(&context).unwrap();
+        bind_context_fbo(&gl, &device, &context);
 
-        let main_surface = make_surface(&mut env.device, &env.context);
-        env.device
-            .bind_surface_to_context(&mut env.context, main_surface)
+        // Don't read the pixels back, since that would synchronize implicitly.
+        clear(&gl, &[0, 0, 255, 255]);
+        let fence = device.create_fence(&context).unwrap();
+
+        let surface = device
+            .unbind_surface_from_context(&mut context)
+            .unwrap()
             .unwrap();
+        to_main_sender.send((surface, fence)).unwrap();
 
-        let main_framebuffer_object = env
+        let (mut surface, fence) = to_worker_receiver.recv().unwrap();
+        device.destroy_fence(&context, fence).unwrap();
+        device.destroy_surface(&mut context, &mut surface).unwrap();
+        device.destroy_context(&mut context).unwrap();
+    });
+
+    unsafe {
+        let (blue_surface, fence) = to_main_receiver.recv().unwrap();
+        env.device.wait_fence(&env.context, &fence).unwrap();
+
+        let blue_surface_texture = env
             .device
-            .context_surface_info(&env.context)
-            .unwrap()
-            .unwrap()
-            .framebuffer_object;
-        env.gl
-            .BindFramebuffer(gl::FRAMEBUFFER, main_framebuffer_object);
-        clear(&env.gl, &[255, 0, 0, 255]);
-        assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+            .create_surface_texture(&mut env.context, blue_surface)
+            .unwrap();
+        env.device.make_context_current(&env.context).unwrap();
 
-        let mut subframebuffer_object = make_fbo(
+        let blue_framebuffer_object = make_fbo(
             &env.gl,
             env.device.surface_gl_texture_target(),
-            env.device.surface_texture_object(&subsurface_texture),
+            env.device.surface_texture_object(&blue_surface_texture),
         );
-
-        // Blit to main framebuffer.
-        blit_fbo(&env.gl, main_framebuffer_object, subframebuffer_object);
-        env.gl
-            .BindFramebuffer(gl::FRAMEBUFFER, main_framebuffer_object);
-        check_gl(&env.gl);
-        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
-        assert_eq!(
-            get_pixel_from_second_from_bottom_row(&env.gl),
-            [255, 0, 0, 255]
+        blit_fbo(
+            &env.gl,
+            context_fbo(&env.device, &env.context),
+            blue_framebuffer_object,
         );
+        bind_context_fbo(&env.gl, &env.device, &env.context);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 0, 255, 255]);
 
         // Clean up.
         env.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
         check_gl(&env.gl);
-        env.gl.DeleteFramebuffers(1, &mut subframebuffer_object);
+        env.gl.DeleteFramebuffers(1, &blue_framebuffer_object);
 
-        let mut subsurface = env
+        let blue_surface = env
             .device
-            .destroy_surface_texture(&mut env.context, subsurface_texture)
-            .unwrap();
-        env.device
-            .destroy_surface(&mut env.context, &mut subsurface)
+            .destroy_surface_texture(&mut env.context, blue_surface_texture)
             .unwrap();
+        to_worker_sender.send((blue_surface, fence)).unwrap();
+
         env.device.destroy_context(&mut env.context).unwrap();
     }
 }
 
-#[cfg(not(target_os = "android"))]
+// Tests that sync_files can be exported for surfaces and imported as fences, where the driver
+// supports it.
+#[cfg(linux)]
 #[cfg_attr(not(feature = "sm-test"), test)]
-pub fn test_depth_and_stencil() {
-    let connection = Connection::new().unwrap();
-    let adapter = connection
-        .create_low_power_adapter()
-        .expect("Failed to create adapter!");
-    let mut device = match connection.create_device(&adapter) {
-        Ok(device) => device,
-        Err(Error::RequiredExtensionUnavailable) => {
-            // Can't run these tests on this hardware.
-            return;
-        }
-        Err(err) => panic!("Failed to create device: {:?}", err),
+pub fn test_surface_fence_fd() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
     };
 
-    // Can't readback from the depth or stencil buffer on OpenGL ES, so just disable this test.
-    if device.gl_api() == GLApi::GLES {
-        return;
-    }
-
-    // Check depth.
-
-    let depth_context_descriptor = device
-        .create_context_descriptor(&ContextAttributes {
-            version: GLVersion::new(3, 0),
-            flags: ContextAttributeFlags::DEPTH,
-        })
+    let mut other_context = env
+        .device
+        .create_context(&env.context_descriptor, None)
         .unwrap();
 
-    let mut depth_context = device
-        .create_context(&depth_context_descriptor, None)
-        .unwrap();
-    let depth_surface = make_surface(&mut device, &depth_context);
-    device
-        .bind_surface_to_context(&mut depth_context, depth_surface)
+    clear(&env.gl, &[0, 255, 0, 255]);
+    let surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
         .unwrap();
-    device.make_context_current(&depth_context).unwrap();
 
-    let gl = Gl::load_with(|symbol| device.get_proc_address(&depth_context, symbol));
+    // Only the context that renders to the surface can export a fence for it.
+    match env.device.export_surface_fence_fd(&other_context, &surface) {
+        Err(Error::IncompatibleSurface) => {}
+        Err(err) => panic!("Expected `IncompatibleSurface` but got {:?}", err),
+        Ok(_) => panic!("Exporting a fence from the wrong context should have failed!"),
+    }
 
-    unsafe {
-        let framebuffer_object = device
-            .context_surface_info(&depth_context)
-            .unwrap()
-            .unwrap()
-            .framebuffer_object;
-        gl.BindFramebuffer(gl::FRAMEBUFFER, framebuffer_object);
-        gl.Viewport(0, 0, 640, 480);
+    match env.device.export_surface_fence_fd(&env.context, &surface) {
+        Ok(fd) => {
+            assert!(fd >= 0);
+            let fence = env.device.import_fence_fd(&other_context, fd).unwrap();
+            env.device.wait_fence(&other_context, &fence).unwrap();
+            env.device.destroy_fence(&other_context, fence).unwrap();
+        }
+        Err(Error::RequiredExtensionUnavailable) => {}
+        Err(err) => panic!("Failed to export fence: {:?}", err),
+    }
 
-        gl.ClearDepth(0.5);
-        gl.Clear(gl::DEPTH_BUFFER_BIT);
+    env.device.destroy_context(&mut other_context).unwrap();
+    env.device
+        .bind_surface_to_context(&mut env.context, surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
 
-        let mut depth_value: f32 = -1.0;
-        gl.ReadPixels(
-            0,
-            0,
-            1,
-            1,
-            gl::DEPTH_COMPONENT,
-            gl::FLOAT,
-            (&mut depth_value) as *mut f32 as *mut c_void,
-        );
-        assert!(approx_eq(depth_value, 0.5));
+// Tests that fences can be used through the `Device` trait, so that code generic over backends
+// can synchronize contexts.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_fence_through_device_trait() {
+    fn wait_on_own_fence<D: DeviceInterface>(device: &D, context: &D::Context) {
+        let fence = device.create_fence(context).unwrap();
+        device.wait_fence(context, &fence).unwrap();
+        if let Err((err, _)) = device.destroy_fence(context, fence) {
+            panic!("Failed to destroy fence: {:?}", err);
+        }
     }
 
-    device.destroy_context(&mut depth_context).unwrap();
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+    clear(&env.gl, &[255, 0, 0, 255]);
+    wait_on_own_fence(&env.device, &env.context);
+    env.device.destroy_context(&mut env.context).unwrap();
+}
 
-    // Check stencil.
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_swap_chain() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
 
-    let stencil_context_descriptor = device
-        .create_context_descriptor(&ContextAttributes {
-            version: GLVersion::new(3, 0),
-            flags: ContextAttributeFlags::STENCIL,
-        })
-        .unwrap();
+    unsafe {
+        let mut surface = env
+            .device
+            .unbind_surface_from_context(&mut env.context)
+            .unwrap()
+            .unwrap();
+        env.device
+            .destroy_surface(&mut env.context, &mut surface)
+            .unwrap();
 
-    let mut stencil_context = device
-        .create_context(&stencil_context_descriptor, None)
-        .unwrap();
-    let stencil_surface = make_surface(&mut device, &stencil_context);
-    device
-        .bind_surface_to_context(&mut stencil_context, stencil_surface)
+        let mut swap_chain = SwapChain::new(
+            &mut env.device,
+            &mut env.context,
+            SurfaceAccess::GPUOnly,
+            Size2D::new(640, 480),
+        )
         .unwrap();
-    device.make_context_current(&stencil_context).unwrap();
+        assert!(swap_chain.take_surface().is_none());
 
-    let gl = Gl::load_with(|symbol| device.get_proc_address(&stencil_context, symbol));
+        bind_context_fbo(&env.gl, &env.device, &env.context);
+        clear(&env.gl, &[0, 255, 0, 255]);
+        swap_chain
+            .swap_buffers(&mut env.device, &mut env.context)
+            .unwrap();
 
-    unsafe {
-        let framebuffer_object = device
-            .context_surface_info(&stencil_context)
-            .unwrap()
-            .unwrap()
-            .framebuffer_object;
-        gl.BindFramebuffer(gl::FRAMEBUFFER, framebuffer_object);
-        gl.Viewport(0, 0, 640, 480);
+        // The front buffer should hold what we rendered, and the new back buffer should be a
+        // different surface.
+        let green_surface = swap_chain.take_surface().unwrap();
+        assert!(swap_chain.take_surface().is_none());
+        let green_surface_id = env.device.surface_info(&green_surface).id;
+        assert_ne!(
+            env.device
+                .context_surface_info(&env.context)
+                .unwrap()
+                .unwrap()
+                .id,
+            green_surface_id
+        );
 
-        gl.ClearStencil(99);
-        gl.Clear(gl::STENCIL_BUFFER_BIT);
+        let green_surface_texture = env
+            .device
+            .create_surface_texture(&mut env.context, green_surface)
+            .unwrap();
+        let green_framebuffer_object = make_fbo(
+            &env.gl,
+            env.device.surface_gl_texture_target(),
+            env.device.surface_texture_object(&green_surface_texture),
+        );
+        bind_context_fbo(&env.gl, &env.device, &env.context);
+        clear(&env.gl, &[255, 0, 0, 255]);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+        blit_fbo(
+            &env.gl,
+            context_fbo(&env.device, &env.context),
+            green_framebuffer_object,
+        );
+        bind_context_fbo(&env.gl, &env.device, &env.context);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+        env.gl.DeleteFramebuffers(1, &green_framebuffer_object);
 
-        let mut stencil_value: u8 = 200;
-        gl.ReadPixels(
-            0,
-            0,
-            1,
-            1,
-            gl::STENCIL_INDEX,
-            gl::UNSIGNED_BYTE,
-            (&mut stencil_value) as *mut u8 as *mut c_void,
+        // A recycled surface should become the next back buffer.
+        let green_surface = env
+            .device
+            .destroy_surface_texture(&mut env.context, green_surface_texture)
+            .unwrap();
+        swap_chain
+            .recycle_surface(&env.device, green_surface)
+            .unwrap();
+        swap_chain
+            .swap_buffers(&mut env.device, &mut env.context)
+            .unwrap();
+        assert_eq!(
+            env.device
+                .context_surface_info(&env.context)
+                .unwrap()
+                .unwrap()
+                .id,
+            green_surface_id
         );
-        assert_eq!(stencil_value, 99);
-    }
 
-    device.destroy_context(&mut stencil_context).unwrap();
+        // Resizing should resize the back buffer.
+        swap_chain
+            .resize(&mut env.device, &mut env.context, Size2D::new(320, 240))
+            .unwrap();
+        assert_eq!(swap_chain.size(), Size2D::new(320, 240));
+        assert_eq!(
+            env.device
+                .context_surface_info(&env.context)
+                .unwrap()
+                .unwrap()
+                .size,
+            Size2D::new(320, 240)
+        );
+
+        swap_chain
+            .destroy(&mut env.device, &mut env.context)
+            .unwrap();
+        assert!(env
+            .device
+            .context_surface_info(&env.context)
+            .unwrap()
+            .is_none());
+        env.device.destroy_context(&mut env.context).unwrap();
+    }
 }
 
-// Make sure that the current native context can be fetched and that they can be correctly wrapped
-// in `surfman` contexts.
 #[cfg_attr(not(feature = "sm-test"), test)]
-pub fn test_get_native_context() {
+pub fn test_surface_pool() {
     let mut env = match BasicEnvironment::new() {
         None => return,
         Some(env) => env,
     };
 
-    let native_context = NativeContext::current().unwrap();
+    let size = Size2D::new(640, 480);
+    let surface_bytes = 640 * 480 * 4;
+    let mut pool = SurfacePool::new(surface_bytes * 2);
 
-    unsafe {
-        clear(&env.gl, &[0, 255, 0, 255]);
+    // Released surfaces should be handed back for matching requests only.
+    let surface = pool
+        .create_surface(&mut env.device, &env.context, SurfaceAccess::GPUOnly, size)
+        .unwrap();
+    let surface_id = env.device.surface_info(&surface).id;
+    pool.release_surface(&env.device, &mut env.context, surface)
+        .unwrap();
+    assert_eq!(pool.len(), 1);
+    assert_eq!(pool.cached_bytes(), surface_bytes);
 
-        let mut other_context = env
+    let mut other_surface = pool
+        .create_surface(&mut env.device, &env.context, SurfaceAccess::GPUCPU, size)
+        .unwrap();
+    assert_ne!(env.device.surface_info(&other_surface).id, surface_id);
+    assert_eq!(pool.len(), 1);
+    env.device
+        .destroy_surface(&mut env.context, &mut other_surface)
+        .unwrap();
+
+    let surface = pool
+        .create_surface(&mut env.device, &env.context, SurfaceAccess::GPUOnly, size)
+        .unwrap();
+    assert_eq!(env.device.surface_info(&surface).id, surface_id);
+    assert!(pool.is_empty());
+    assert_eq!(pool.cached_bytes(), 0);
+
+    // The pool should stay within its budget.
+    let mut surfaces = vec![surface];
+    for _ in 0..2 {
+        surfaces.push(
+            pool.create_surface(&mut env.device, &env.context, SurfaceAccess::GPUOnly, size)
+                .unwrap(),
+        );
+    }
+    for surface in surfaces {
+        pool.release_surface(&env.device, &mut env.context, surface)
+            .unwrap();
+    }
+    assert_eq!(pool.len(), 2);
+    assert_eq!(pool.cached_bytes(), surface_bytes * 2);
+
+    pool.trim(&env.device, &mut env.context, surface_bytes)
+        .unwrap();
+    assert_eq!(pool.len(), 1);
+    pool.clear(&env.device, &mut env.context).unwrap();
+    assert!(pool.is_empty());
+    assert_eq!(pool.cached_bytes(), 0);
+
+    // Surfaces are cached under the access and format they were created with, and their size
+    // estimate accounts for the format.
+    let cpu_surface = env
+        .device
+        .create_surface(
+            &env.context,
+            SurfaceAccess::GPUCPU,
+            SurfaceType::Generic { size },
+        )
+        .unwrap();
+    let cpu_surface_id = env.device.surface_info(&cpu_surface).id;
+    pool.release_surface(&env.device, &mut env.context, cpu_surface)
+        .unwrap();
+    let mut surface = pool
+        .create_surface(&mut env.device, &env.context, SurfaceAccess::GPUOnly, size)
+        .unwrap();
+    assert_ne!(env.device.surface_info(&surface).id, cpu_surface_id);
+    env.device
+        .destroy_surface(&mut env.context, &mut surface)
+        .unwrap();
+    pool.clear(&env.device, &mut env.context).unwrap();
+
+    match env.device.create_surface_with_format(
+        &env.context,
+        SurfaceAccess::GPUOnly,
+        SurfaceType::Generic { size },
+        SurfaceFormat::RGBA16F,
+    ) {
+        Ok(float_surface) => {
+            let float_surface_id = env.device.surface_info(&float_surface).id;
+            pool.release_surface(&env.device, &mut env.context, float_surface)
+                .unwrap();
+            assert_eq!(pool.cached_bytes(), surface_bytes * 2);
+            let mut surface = pool
+                .create_surface(&mut env.device, &env.context, SurfaceAccess::GPUOnly, size)
+                .unwrap();
+            assert_ne!(env.device.surface_info(&surface).id, float_surface_id);
+            assert_eq!(
+                env.device.surface_info(&surface).format,
+                SurfaceFormat::RGBA8
+            );
+            env.device
+                .destroy_surface(&mut env.context, &mut surface)
+                .unwrap();
+            pool.clear(&env.device, &mut env.context).unwrap();
+        }
+        Err(Error::UnsupportedSurfaceFormat) => {}
+        Err(err) => panic!("Failed to create RGBA16F surface: {:?}", err),
+    }
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_resize_generic_surface() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    unsafe {
+        let mut surface = env
+            .device
+            .unbind_surface_from_context(&mut env.context)
+            .unwrap()
+            .unwrap();
+        let old_info = env.device.surface_info(&surface);
+
+        // Invalid sizes should fail and leave the surface alone.
+        match env
+            .device
+            .resize_surface(&env.context, &mut surface, Size2D::new(0, 0))
+        {
+            Err(Error::SurfaceCreationFailed(_)) => {}
+            other => panic!("Expected a surface creation failure, got {:?}", other),
+        }
+        assert_eq!(env.device.surface_info(&surface).size, old_info.size);
+
+        let new_size = Size2D::new(1024, 768);
+        env.device
+            .resize_surface(&env.context, &mut surface, new_size)
+            .unwrap();
+        let new_info = env.device.surface_info(&surface);
+        assert_eq!(new_info.size, new_size);
+        assert_eq!(new_info.framebuffer_object, old_info.framebuffer_object);
+        assert_ne!(new_info.id, old_info.id);
+
+        // Pixels outside the old bounds should now be renderable.
+        env.device
+            .bind_surface_to_context(&mut env.context, surface)
+            .unwrap();
+        bind_context_fbo(&env.gl, &env.device, &env.context);
+        check_gl(&env.gl);
+        assert_eq!(
+            env.gl.CheckFramebufferStatus(gl::FRAMEBUFFER),
+            gl::FRAMEBUFFER_COMPLETE
+        );
+        clear(&env.gl, &[0, 0, 255, 255]);
+        let mut pixel: [u8; 4] = [0; 4];
+        env.gl.ReadPixels(
+            1000,
+            700,
+            1,
+            1,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixel.as_mut_ptr() as *mut c_void,
+        );
+        check_gl(&env.gl);
+        assert_eq!(pixel, [0, 0, 255, 255]);
+
+        env.device.destroy_context(&mut env.context).unwrap();
+    }
+}
+
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_multisampled_surface() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    let context_descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::DEPTH | ContextAttributeFlags::STENCIL,
+            samples: 4,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    assert_eq!(
+        device
+            .context_descriptor_attributes(&context_descriptor)
+            .samples,
+        4
+    );
+
+    let mut context = device.create_context(&context_descriptor, None).unwrap();
+    device.make_context_current(&context).unwrap();
+    let gl = Gl::load_with(|symbol| device.get_proc_address(&context, symbol));
+
+    unsafe {
+        let mut surface = make_surface(&mut device, &context);
+        for &(size, color) in &[
+            (Size2D::new(640, 480), [0, 255, 0, 255]),
+            (Size2D::new(320, 240), [255, 0, 0, 255]),
+        ] {
+            device.resize_surface(&context, &mut surface, size).unwrap();
+            device
+                .bind_surface_to_context(&mut context, surface)
+                .unwrap();
+            bind_context_fbo(&gl, &device, &context);
+            assert_eq!(
+                gl.CheckFramebufferStatus(gl::FRAMEBUFFER),
+                gl::FRAMEBUFFER_COMPLETE
+            );
+
+            // We should be rendering to multisampled storage if the implementation supports it.
+            let (mut max_samples, mut samples) = (0, 0);
+            gl.GetIntegerv(gl::MAX_SAMPLES, &mut max_samples);
+            gl.GetIntegerv(gl::SAMPLES, &mut samples);
+            assert_eq!(samples, max_samples.min(4));
+            check_gl(&gl);
+
+            // Unbinding should resolve into the texture that other contexts see.
+            clear(&gl, &color);
+            surface = device
+                .unbind_surface_from_context(&mut context)
+                .unwrap()
+                .unwrap();
+            let surface_texture = device
+                .create_surface_texture(&mut context, surface)
+                .unwrap();
+            let framebuffer_object = make_fbo(
+                &gl,
+                device.surface_gl_texture_target(),
+                device.surface_texture_object(&surface_texture),
+            );
+            assert_eq!(get_pixel_from_bottom_row(&gl), color);
+            check_gl(&gl);
+
+            gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl.DeleteFramebuffers(1, &framebuffer_object);
+            surface = device
+                .destroy_surface_texture(&mut context, surface_texture)
+                .unwrap();
+        }
+
+        device.destroy_surface(&mut context, &mut surface).unwrap();
+        device.destroy_context(&mut context).unwrap();
+    }
+}
+
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_surface_formats() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let mut main_surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        env.device.surface_info(&main_surface).format,
+        SurfaceFormat::RGBA8
+    );
+
+    for &(format, bytes_per_pixel) in &[
+        (SurfaceFormat::RGBA8, 4),
+        (SurfaceFormat::BGRA8, 4),
+        (SurfaceFormat::RGBA16F, 8),
+        (SurfaceFormat::RGB10A2, 4),
+        (SurfaceFormat::SRGB8A8, 4),
+    ] {
+        let surface_type = SurfaceType::Generic {
+            size: Size2D::new(640, 480),
+        };
+        let surface = match env.device.create_surface_with_format(
+            &env.context,
+            SurfaceAccess::GPUCPU,
+            surface_type,
+            format,
+        ) {
+            Ok(surface) => surface,
+            Err(Error::UnsupportedSurfaceFormat) if format != SurfaceFormat::RGBA8 => continue,
+            Err(err) => panic!("Failed to create {:?} surface: {:?}", format, err),
+        };
+        assert_eq!(env.device.surface_info(&surface).format, format);
+
+        env.device
+            .bind_surface_to_context(&mut env.context, surface)
+            .unwrap();
+        bind_context_fbo(&env.gl, &env.device, &env.context);
+        unsafe {
+            assert_eq!(
+                env.gl.CheckFramebufferStatus(gl::FRAMEBUFFER),
+                gl::FRAMEBUFFER_COMPLETE
+            );
+            clear(&env.gl, &[255, 0, 0, 255]);
+            assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+            check_gl(&env.gl);
+        }
+
+        // CPU access sees the pixels in the surface's own format.
+        let mut surface = env
+            .device
+            .unbind_surface_from_context(&mut env.context)
+            .unwrap()
+            .unwrap();
+        {
+            let mut guard = env.device.lock_surface_data(&mut surface).unwrap();
+            assert_eq!(guard.stride(), 640 * bytes_per_pixel);
+            assert_eq!(guard.data().len(), 640 * 480 * bytes_per_pixel);
+            match format {
+                SurfaceFormat::RGBA8 | SurfaceFormat::SRGB8A8 => {
+                    assert_eq!(guard.data()[0..4], [255, 0, 0, 255])
+                }
+                SurfaceFormat::BGRA8 => assert_eq!(guard.data()[0..4], [0, 0, 255, 255]),
+                SurfaceFormat::RGBA16F | SurfaceFormat::RGB10A2 => {}
+            }
+        }
+        env.device
+            .destroy_surface(&mut env.context, &mut surface)
+            .unwrap();
+    }
+
+    env.device
+        .destroy_surface(&mut env.context, &mut main_surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_depth_formats() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    for &(depth_format, depth_size, component_type) in &[
+        (DepthFormat::Depth16, 16, gl::UNSIGNED_NORMALIZED),
+        (DepthFormat::Depth24, 24, gl::UNSIGNED_NORMALIZED),
+        (DepthFormat::Depth32F, 32, gl::FLOAT),
+    ] {
+        for &flags in &[
+            ContextAttributeFlags::DEPTH,
+            ContextAttributeFlags::DEPTH | ContextAttributeFlags::STENCIL,
+            ContextAttributeFlags::DEPTH | ContextAttributeFlags::SAMPLEABLE_DEPTH,
+            ContextAttributeFlags::DEPTH
+                | ContextAttributeFlags::STENCIL
+                | ContextAttributeFlags::SAMPLEABLE_DEPTH,
+        ] {
+            let context_descriptor = device
+                .create_context_descriptor(&ContextAttributes {
+                    version: GLVersion::new(3, 0),
+                    flags,
+                    samples: 0,
+                    depth_format,
+                    priority: ContextPriority::Medium,
+                })
+                .unwrap();
+            let attributes = device.context_descriptor_attributes(&context_descriptor);
+            assert_eq!(attributes.depth_format, depth_format);
+            assert!(attributes.flags.contains(flags));
+
+            let mut context = device.create_context(&context_descriptor, None).unwrap();
+            let surface = make_surface(&mut device, &context);
+            device
+                .bind_surface_to_context(&mut context, surface)
+                .unwrap();
+            device.make_context_current(&context).unwrap();
+            let gl = Gl::load_with(|symbol| device.get_proc_address(&context, symbol));
+
+            unsafe {
+                bind_context_fbo(&gl, &device, &context);
+                assert_eq!(
+                    gl.CheckFramebufferStatus(gl::FRAMEBUFFER),
+                    gl::FRAMEBUFFER_COMPLETE
+                );
+                let (mut actual_depth_size, mut actual_component_type) = (0, 0);
+                let (mut stencil_size, mut depth_object_type) = (0, 0);
+                gl.GetFramebufferAttachmentParameteriv(
+                    gl::FRAMEBUFFER,
+                    gl::DEPTH_ATTACHMENT,
+                    gl::FRAMEBUFFER_ATTACHMENT_DEPTH_SIZE,
+                    &mut actual_depth_size,
+                );
+                gl.GetFramebufferAttachmentParameteriv(
+                    gl::FRAMEBUFFER,
+                    gl::DEPTH_ATTACHMENT,
+                    gl::FRAMEBUFFER_ATTACHMENT_COMPONENT_TYPE,
+                    &mut actual_component_type,
+                );
+                gl.GetFramebufferAttachmentParameteriv(
+                    gl::FRAMEBUFFER,
+                    gl::DEPTH_ATTACHMENT,
+                    gl::FRAMEBUFFER_ATTACHMENT_OBJECT_TYPE,
+                    &mut depth_object_type,
+                );
+                if flags.contains(ContextAttributeFlags::STENCIL) {
+                    gl.GetFramebufferAttachmentParameteriv(
+                        gl::FRAMEBUFFER,
+                        gl::STENCIL_ATTACHMENT,
+                        gl::FRAMEBUFFER_ATTACHMENT_STENCIL_SIZE,
+                        &mut stencil_size,
+                    );
+                }
+                check_gl(&gl);
+
+                // 16-bit depth gets rounded up when it's packed with stencil.
+                if flags.contains(ContextAttributeFlags::STENCIL) {
+                    assert!(actual_depth_size >= depth_size);
+                } else {
+                    assert_eq!(actual_depth_size, depth_size);
+                }
+                assert_eq!(actual_component_type as GLenum, component_type);
+                if flags.contains(ContextAttributeFlags::SAMPLEABLE_DEPTH) {
+                    assert_eq!(depth_object_type as GLenum, gl::TEXTURE);
+                } else {
+                    assert_eq!(depth_object_type as GLenum, gl::RENDERBUFFER);
+                }
+                if flags.contains(ContextAttributeFlags::STENCIL) {
+                    assert_eq!(stencil_size, 8);
+                } else {
+                    assert_eq!(stencil_size, 0);
+                }
+            }
+
+            let mut surface = device
+                .unbind_surface_from_context(&mut context)
+                .unwrap()
+                .unwrap();
+            device.destroy_surface(&mut context, &mut surface).unwrap();
+            device.destroy_context(&mut context).unwrap();
+        }
+    }
+}
+
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_sampleable_depth() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    // Multisampled surfaces resolve their depth buffer into the texture too.
+    for &samples in &[0, 4] {
+        let context_descriptor = device
+            .create_context_descriptor(&ContextAttributes {
+                version: GLVersion::new(3, 0),
+                flags: ContextAttributeFlags::DEPTH | ContextAttributeFlags::SAMPLEABLE_DEPTH,
+                samples,
+                depth_format: DepthFormat::Depth32F,
+                priority: ContextPriority::Medium,
+            })
+            .unwrap();
+        let mut context = device.create_context(&context_descriptor, None).unwrap();
+        let surface = make_surface(&mut device, &context);
+        device
+            .bind_surface_to_context(&mut context, surface)
+            .unwrap();
+        device.make_context_current(&context).unwrap();
+        let gl = Gl::load_with(|symbol| device.get_proc_address(&context, symbol));
+
+        unsafe {
+            bind_context_fbo(&gl, &device, &context);
+            gl.ClearDepth(0.25);
+            gl.Clear(gl::DEPTH_BUFFER_BIT);
+            check_gl(&gl);
+
+            let surface = device
+                .unbind_surface_from_context(&mut context)
+                .unwrap()
+                .unwrap();
+            let surface_texture = device
+                .create_surface_texture(&mut context, surface)
+                .unwrap();
+            let depth_texture_object = device.surface_texture_depth_object(&surface_texture);
+            assert_ne!(depth_texture_object, 0);
+
+            // Read the depth values back through a framebuffer of our own.
+            let mut framebuffer_object = 0;
+            gl.GenFramebuffers(1, &mut framebuffer_object);
+            gl.BindFramebuffer(gl::FRAMEBUFFER, framebuffer_object);
+            gl.FramebufferTexture2D(
+                gl::FRAMEBUFFER,
+                gl::DEPTH_ATTACHMENT,
+                device.surface_gl_texture_target(),
+                depth_texture_object,
+                0,
+            );
+            gl.DrawBuffer(gl::NONE);
+            gl.ReadBuffer(gl::NONE);
+            assert_eq!(
+                gl.CheckFramebufferStatus(gl::FRAMEBUFFER),
+                gl::FRAMEBUFFER_COMPLETE
+            );
+            let mut depth = 0.0f32;
+            gl.ReadPixels(
+                0,
+                0,
+                1,
+                1,
+                gl::DEPTH_COMPONENT,
+                gl::FLOAT,
+                &mut depth as *mut f32 as *mut c_void,
+            );
+            check_gl(&gl);
+            assert!(approx_eq(depth, 0.25));
+
+            gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+            gl.DeleteFramebuffers(1, &framebuffer_object);
+            let mut surface = device
+                .destroy_surface_texture(&mut context, surface_texture)
+                .unwrap();
+            device.destroy_surface(&mut context, &mut surface).unwrap();
+        }
+        device.destroy_context(&mut context).unwrap();
+    }
+}
+
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_context_reset_status() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let robust_context_descriptor = env
+        .device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::ROBUST_ACCESS,
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    let robust_context_attributes = env
+        .device
+        .context_descriptor_attributes(&robust_context_descriptor);
+    let mut robust_context = env
+        .device
+        .create_context(&robust_context_descriptor, None)
+        .unwrap();
+
+    if robust_context_attributes
+        .flags
+        .contains(ContextAttributeFlags::ROBUST_ACCESS)
+    {
+        env.device.make_context_current(&robust_context).unwrap();
+        let mut reset_notification_strategy = 0;
+        unsafe {
+            env.gl.GetIntegerv(
+                gl::RESET_NOTIFICATION_STRATEGY_ARB,
+                &mut reset_notification_strategy,
+            );
+        }
+        check_gl(&env.gl);
+        assert_eq!(
+            reset_notification_strategy as GLenum,
+            gl::LOSE_CONTEXT_ON_RESET_ARB
+        );
+        assert_eq!(
+            env.device.context_reset_status(&robust_context).unwrap(),
+            ContextResetStatus::NoReset
+        );
+    }
+
+    // Querying a context that isn't current leaves the current context alone.
+    env.device.make_context_current(&env.context).unwrap();
+    assert_eq!(
+        env.device.context_reset_status(&robust_context).unwrap(),
+        ContextResetStatus::NoReset
+    );
+    assert_eq!(
+        env.device.context_reset_status(&env.context).unwrap(),
+        ContextResetStatus::NoReset
+    );
+    assert_eq!(
+        NativeContext::current().unwrap().egl_context,
+        env.device.native_context(&env.context).egl_context
+    );
+
+    env.device.destroy_context(&mut robust_context).unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_debug_context() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let debug_context_descriptor = env
+        .device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::DEBUG,
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    assert!(env
+        .device
+        .context_descriptor_attributes(&debug_context_descriptor)
+        .flags
+        .contains(ContextAttributeFlags::DEBUG));
+    let mut debug_context = env
+        .device
+        .create_context(&debug_context_descriptor, None)
+        .unwrap();
+    let debug_context_id = env.device.context_id(&debug_context);
+
+    let (sender, receiver) = mpsc::channel();
+    let sender = Mutex::new(sender);
+    set_debug_callback(
+        debug_context_id,
+        Some(Box::new(move |message: &DebugMessage| {
+            sender
+                .lock()
+                .unwrap()
+                .send((message.context_id, message.message_type, message.severity))
+                .unwrap();
+        })),
+    );
+
+    env.device.make_context_current(&debug_context).unwrap();
+    unsafe {
+        let mut context_flags = 0;
+        env.gl.GetIntegerv(gl::CONTEXT_FLAGS, &mut context_flags);
+        assert_ne!(context_flags as GLenum & gl::CONTEXT_FLAG_DEBUG_BIT, 0);
+
+        // Provoke an error, which is reported synchronously.
+        env.gl.BindTexture(0xdead, 0);
+        assert_eq!(env.gl.GetError(), gl::INVALID_ENUM);
+    }
+    assert!(receiver
+        .try_iter()
+        .any(|message| message == (debug_context_id, gl::DEBUG_TYPE_ERROR, DebugSeverity::High)));
+
+    // A panicking callback doesn't unwind into the OpenGL implementation.
+    set_debug_callback(
+        debug_context_id,
+        Some(Box::new(|_: &DebugMessage| panic!("debug callback panic"))),
+    );
+    unsafe {
+        env.gl.BindTexture(0xdead, 0);
+        assert_eq!(env.gl.GetError(), gl::INVALID_ENUM);
+    }
+
+    // Messages go back to the log once the callback is removed.
+    set_debug_callback(debug_context_id, None);
+    unsafe {
+        env.gl.BindTexture(0xdead, 0);
+        assert_eq!(env.gl.GetError(), gl::INVALID_ENUM);
+    }
+    assert!(receiver.try_recv().is_err());
+
+    env.device.destroy_context(&mut debug_context).unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that surface textures are not upside-down.
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_surface_texture_right_side_up() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    unsafe {
+        clear(&env.gl, &[255, 0, 0, 255]);
+        clear_bottom_row(&env.gl, &[0, 255, 0, 255]);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+
+        let subsurface = env
+            .device
+            .unbind_surface_from_context(&mut env.context)
+            .unwrap()
+            .unwrap();
+        let subsurface_texture = env
+            .device
+            .create_surface_texture(&mut env.context, subsurface)
+            .unwrap();
+
+        let main_surface = make_surface(&mut env.device, &env.context);
+        env.device
+            .bind_surface_to_context(&mut env.context, main_surface)
+            .unwrap();
+
+        let main_framebuffer_object = env
+            .device
+            .context_surface_info(&env.context)
+            .unwrap()
+            .unwrap()
+            .framebuffer_object;
+        env.gl
+            .BindFramebuffer(gl::FRAMEBUFFER, main_framebuffer_object);
+        clear(&env.gl, &[255, 0, 0, 255]);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+
+        let mut subframebuffer_object = make_fbo(
+            &env.gl,
+            env.device.surface_gl_texture_target(),
+            env.device.surface_texture_object(&subsurface_texture),
+        );
+
+        // Blit to main framebuffer.
+        blit_fbo(&env.gl, main_framebuffer_object, subframebuffer_object);
+        env.gl
+            .BindFramebuffer(gl::FRAMEBUFFER, main_framebuffer_object);
+        check_gl(&env.gl);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+        assert_eq!(
+            get_pixel_from_second_from_bottom_row(&env.gl),
+            [255, 0, 0, 255]
+        );
+
+        // Clean up.
+        env.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+        check_gl(&env.gl);
+        env.gl.DeleteFramebuffers(1, &mut subframebuffer_object);
+
+        let mut subsurface = env
+            .device
+            .destroy_surface_texture(&mut env.context, subsurface_texture)
+            .unwrap();
+        env.device
+            .destroy_surface(&mut env.context, &mut subsurface)
+            .unwrap();
+        env.device.destroy_context(&mut env.context).unwrap();
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_depth_and_stencil() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => {
+            // Can't run these tests on this hardware.
+            return;
+        }
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    // Can't readback from the depth or stencil buffer on OpenGL ES, so just disable this test.
+    if device.gl_api() == GLApi::GLES {
+        return;
+    }
+
+    // Check depth.
+
+    let depth_context_descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::DEPTH,
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+
+    let mut depth_context = device
+        .create_context(&depth_context_descriptor, None)
+        .unwrap();
+    let depth_surface = make_surface(&mut device, &depth_context);
+    device
+        .bind_surface_to_context(&mut depth_context, depth_surface)
+        .unwrap();
+    device.make_context_current(&depth_context).unwrap();
+
+    let gl = Gl::load_with(|symbol| device.get_proc_address(&depth_context, symbol));
+
+    unsafe {
+        let framebuffer_object = device
+            .context_surface_info(&depth_context)
+            .unwrap()
+            .unwrap()
+            .framebuffer_object;
+        gl.BindFramebuffer(gl::FRAMEBUFFER, framebuffer_object);
+        gl.Viewport(0, 0, 640, 480);
+
+        gl.ClearDepth(0.5);
+        gl.Clear(gl::DEPTH_BUFFER_BIT);
+
+        let mut depth_value: f32 = -1.0;
+        gl.ReadPixels(
+            0,
+            0,
+            1,
+            1,
+            gl::DEPTH_COMPONENT,
+            gl::FLOAT,
+            (&mut depth_value) as *mut f32 as *mut c_void,
+        );
+        assert!(approx_eq(depth_value, 0.5));
+    }
+
+    device.destroy_context(&mut depth_context).unwrap();
+
+    // Check stencil.
+
+    let stencil_context_descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::STENCIL,
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+
+    let mut stencil_context = device
+        .create_context(&stencil_context_descriptor, None)
+        .unwrap();
+    let stencil_surface = make_surface(&mut device, &stencil_context);
+    device
+        .bind_surface_to_context(&mut stencil_context, stencil_surface)
+        .unwrap();
+    device.make_context_current(&stencil_context).unwrap();
+
+    let gl = Gl::load_with(|symbol| device.get_proc_address(&stencil_context, symbol));
+
+    unsafe {
+        let framebuffer_object = device
+            .context_surface_info(&stencil_context)
+            .unwrap()
+            .unwrap()
+            .framebuffer_object;
+        gl.BindFramebuffer(gl::FRAMEBUFFER, framebuffer_object);
+        gl.Viewport(0, 0, 640, 480);
+
+        gl.ClearStencil(99);
+        gl.Clear(gl::STENCIL_BUFFER_BIT);
+
+        let mut stencil_value: u8 = 200;
+        gl.ReadPixels(
+            0,
+            0,
+            1,
+            1,
+            gl::STENCIL_INDEX,
+            gl::UNSIGNED_BYTE,
+            (&mut stencil_value) as *mut u8 as *mut c_void,
+        );
+        assert_eq!(stencil_value, 99);
+    }
+
+    device.destroy_context(&mut stencil_context).unwrap();
+}
+
+// Make sure that the current native context can be fetched and that they can be correctly wrapped
+// in `surfman` contexts.
+// Tests that the CPU can read and write the pixels of generic surfaces.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_lock_surface_data() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let mut main_surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    let surface = env
+        .device
+        .create_surface(
+            &env.context,
+            SurfaceAccess::GPUCPU,
+            SurfaceType::Generic {
+                size: Size2D::new(640, 480),
+            },
+        )
+        .unwrap();
+    env.device
+        .bind_surface_to_context(&mut env.context, surface)
+        .unwrap();
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    clear(&env.gl, &[255, 0, 0, 255]);
+
+    let mut surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    {
+        let mut guard = env.device.lock_surface_data(&mut surface).unwrap();
+        assert_eq!(guard.stride(), 640 * 4);
+        assert_eq!(guard.data().len(), 640 * 480 * 4);
+        assert_eq!(guard.data()[0..4], [255, 0, 0, 255]);
+        for pixel in guard.data().chunks_mut(4) {
+            pixel.copy_from_slice(&[0, 0, 255, 255]);
+        }
+    }
+
+    env.device
+        .bind_surface_to_context(&mut env.context, surface)
+        .unwrap();
+    bind_context_fbo(&env.gl, &env.device, &env.context);
+    assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 0, 255, 255]);
+
+    // Surfaces without CPU access can't be locked.
+    match env.device.lock_surface_data(&mut main_surface) {
+        Err(Error::SurfaceDataInaccessible) => {}
+        Err(err) => panic!("Expected `SurfaceDataInaccessible` but got {:?}", err),
+        Ok(_) => panic!("Locking a GPU-only surface should have failed!"),
+    }
+
+    env.device
+        .destroy_surface(&mut env.context, &mut main_surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that generic surfaces can be exported as dma-bufs, where the driver supports it.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_export_surface_dmabuf() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    let descriptor = match env.device.export_surface_dmabuf(&surface) {
+        Ok(descriptor) => descriptor,
+        Err(Error::RequiredExtensionUnavailable) => {
+            println!("skipping test_export_surface_dmabuf: dma-buf export is unsupported here");
+            env.device
+                .bind_surface_to_context(&mut env.context, surface)
+                .unwrap();
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to export surface: {:?}", err),
+    };
+
+    assert_eq!(descriptor.size, Size2D::new(640, 480));
+    assert!(!descriptor.planes.is_empty());
+    for plane in &descriptor.planes {
+        assert!(plane.fd >= 0);
+        assert!(plane.stride >= 640 * 4);
+        unsafe {
+            libc::close(plane.fd);
+        }
+    }
+
+    env.device
+        .bind_surface_to_context(&mut env.context, surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that a dma-buf exported from one surface can be sampled through a surface texture in
+// another context, where the driver supports it.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_import_surface_dmabuf() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    clear(&env.gl, &[255, 0, 0, 255]);
+    unsafe {
+        env.gl.Flush();
+    }
+
+    let surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    let descriptor = match env.device.export_surface_dmabuf(&surface) {
+        Ok(descriptor) => descriptor,
+        Err(Error::RequiredExtensionUnavailable) => {
+            println!("skipping test_import_surface_dmabuf: dma-buf export is unsupported here");
+            env.device
+                .bind_surface_to_context(&mut env.context, surface)
+                .unwrap();
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to export surface: {:?}", err),
+    };
+
+    let mut other_context = env
+        .device
+        .create_context(&env.context_descriptor, None)
+        .unwrap();
+    let other_surface = make_surface(&mut env.device, &other_context);
+    env.device
+        .bind_surface_to_context(&mut other_context, other_surface)
+        .unwrap();
+    env.device.make_context_current(&other_context).unwrap();
+
+    let surface_texture = env
+        .device
+        .create_surface_texture_from_dmabuf(&mut other_context, &descriptor);
+    for plane in &descriptor.planes {
+        unsafe {
+            libc::close(plane.fd);
+        }
+    }
+    let surface_texture = match surface_texture {
+        Ok(surface_texture) => surface_texture,
+        Err(Error::RequiredExtensionUnavailable) => {
+            env.device.destroy_context(&mut other_context).unwrap();
+            env.device
+                .bind_surface_to_context(&mut env.context, surface)
+                .unwrap();
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to import surface: {:?}", err),
+    };
+
+    unsafe {
+        let other_framebuffer_object = context_fbo(&env.device, &other_context);
+        let imported_framebuffer_object = make_fbo(
+            &env.gl,
+            env.device.surface_gl_texture_target(),
+            env.device.surface_texture_object(&surface_texture),
+        );
+        blit_fbo(
+            &env.gl,
+            other_framebuffer_object,
+            imported_framebuffer_object,
+        );
+        env.gl
+            .BindFramebuffer(gl::FRAMEBUFFER, other_framebuffer_object);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [255, 0, 0, 255]);
+
+        env.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+        env.gl.DeleteFramebuffers(1, &imported_framebuffer_object);
+    }
+
+    let mut imported_surface = env
+        .device
+        .destroy_surface_texture(&mut other_context, surface_texture)
+        .unwrap();
+    env.device
+        .destroy_surface(&mut other_context, &mut imported_surface)
+        .unwrap();
+    env.device.destroy_context(&mut other_context).unwrap();
+
+    env.device
+        .bind_surface_to_context(&mut env.context, surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that surfaces imported from dma-bufs refuse to be resized, since they don't own their
+// storage.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_resize_imported_surface_dmabuf() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    let descriptor = match env.device.export_surface_dmabuf(&surface) {
+        Ok(descriptor) => descriptor,
+        Err(Error::RequiredExtensionUnavailable) => {
+            println!(
+                "skipping test_resize_imported_surface_dmabuf: dma-buf export is unsupported here"
+            );
+            env.device
+                .bind_surface_to_context(&mut env.context, surface)
+                .unwrap();
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to export surface: {:?}", err),
+    };
+
+    let imported_surface =
+        env.device
+            .create_surface_from_dmabuf(&env.context, SurfaceAccess::GPUOnly, &descriptor);
+    for plane in &descriptor.planes {
+        unsafe {
+            libc::close(plane.fd);
+        }
+    }
+    match imported_surface {
+        Ok(mut imported_surface) => {
+            let size = env.device.surface_info(&imported_surface).size;
+            match env.device.resize_surface(
+                &env.context,
+                &mut imported_surface,
+                Size2D::new(size.width * 2, size.height * 2),
+            ) {
+                Err(Error::UnsupportedOnThisPlatform) => {}
+                result => panic!("Resized an imported surface: {:?}", result),
+            }
+            assert_eq!(env.device.surface_info(&imported_surface).size, size);
+            env.device
+                .destroy_surface(&mut env.context, &mut imported_surface)
+                .unwrap();
+        }
+        Err(Error::RequiredExtensionUnavailable) => {}
+        Err(err) => panic!("Failed to import surface: {:?}", err),
+    }
+
+    env.device
+        .bind_surface_to_context(&mut env.context, surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that surfaces can be shared over a Unix socket, where the driver supports it.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_surface_share_handle() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    clear(&env.gl, &[0, 255, 0, 255]);
+    unsafe {
+        env.gl.Flush();
+    }
+
+    let surface = env
+        .device
+        .unbind_surface_from_context(&mut env.context)
+        .unwrap()
+        .unwrap();
+    let share_handle = match env.device.create_surface_share_handle(&surface) {
+        Ok(share_handle) => share_handle,
+        Err(Error::RequiredExtensionUnavailable) => {
+            env.device
+                .bind_surface_to_context(&mut env.context, surface)
+                .unwrap();
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to create share handle: {:?}", err),
+    };
+
+    let (sender, receiver) = UnixStream::pair().unwrap();
+    share_handle.send(&sender).unwrap();
+    let received_share_handle = SurfaceShareHandle::recv(&receiver).unwrap();
+    assert_eq!(
+        received_share_handle.descriptor().size,
+        share_handle.descriptor().size
+    );
+    assert_eq!(
+        received_share_handle.descriptor().planes.len(),
+        share_handle.descriptor().planes.len()
+    );
+    drop(share_handle);
+
+    let mut other_context = env
+        .device
+        .create_context(&env.context_descriptor, None)
+        .unwrap();
+    let other_surface = make_surface(&mut env.device, &other_context);
+    env.device
+        .bind_surface_to_context(&mut other_context, other_surface)
+        .unwrap();
+    env.device.make_context_current(&other_context).unwrap();
+
+    let surface_texture = match env
+        .device
+        .import_shared_surface(&mut other_context, &received_share_handle)
+    {
+        Ok(surface_texture) => surface_texture,
+        Err(Error::RequiredExtensionUnavailable) => {
+            env.device.destroy_context(&mut other_context).unwrap();
+            env.device
+                .bind_surface_to_context(&mut env.context, surface)
+                .unwrap();
+            env.device.destroy_context(&mut env.context).unwrap();
+            return;
+        }
+        Err(err) => panic!("Failed to import shared surface: {:?}", err),
+    };
+
+    unsafe {
+        let other_framebuffer_object = context_fbo(&env.device, &other_context);
+        let imported_framebuffer_object = make_fbo(
+            &env.gl,
+            env.device.surface_gl_texture_target(),
+            env.device.surface_texture_object(&surface_texture),
+        );
+        blit_fbo(
+            &env.gl,
+            other_framebuffer_object,
+            imported_framebuffer_object,
+        );
+        env.gl
+            .BindFramebuffer(gl::FRAMEBUFFER, other_framebuffer_object);
+        assert_eq!(get_pixel_from_bottom_row(&env.gl), [0, 255, 0, 255]);
+
+        env.gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+        env.gl.DeleteFramebuffers(1, &imported_framebuffer_object);
+    }
+
+    let mut imported_surface = env
+        .device
+        .destroy_surface_texture(&mut other_context, surface_texture)
+        .unwrap();
+    env.device
+        .destroy_surface(&mut other_context, &mut imported_surface)
+        .unwrap();
+    env.device.destroy_context(&mut other_context).unwrap();
+
+    env.device
+        .bind_surface_to_context(&mut env.context, surface)
+        .unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_get_native_context() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let native_context = NativeContext::current().unwrap();
+
+    unsafe {
+        clear(&env.gl, &[0, 255, 0, 255]);
+
+        let mut other_context = env
             .device
             .create_context_from_native_context(native_context)
             .unwrap();
@@ -865,6 +2256,535 @@ pub fn test_get_native_context() {
     env.device.destroy_context(&mut env.context).unwrap();
 }
 
+// Tests that OpenGL ES can be chosen instead of OpenGL on the Linux backends.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_gles_context() {
+    let mut connection = Connection::new().unwrap();
+    connection.set_gl_api(GLApi::GLES).unwrap();
+    assert_eq!(connection.gl_api(), GLApi::GLES);
+
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => return,
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+    assert_eq!(device.gl_api(), GLApi::GLES);
+    assert_eq!(device.connection().gl_api(), GLApi::GLES);
+
+    // OpenGL ES has no compatibility profile.
+    match device.create_context_descriptor(&ContextAttributes {
+        version: GLVersion::new(3, 0),
+        flags: ContextAttributeFlags::COMPATIBILITY_PROFILE,
+        samples: 0,
+        depth_format: DepthFormat::Depth24,
+        priority: ContextPriority::Medium,
+    }) {
+        Err(Error::UnsupportedGLProfile) => {}
+        other => panic!("Expected `UnsupportedGLProfile`, got {:?}", other.err()),
+    }
+
+    let context_descriptor = match device.create_context_descriptor(&ContextAttributes {
+        version: GLVersion::new(3, 0),
+        flags: ContextAttributeFlags::empty(),
+        samples: 0,
+        depth_format: DepthFormat::Depth24,
+        priority: ContextPriority::Medium,
+    }) {
+        Ok(context_descriptor) => context_descriptor,
+        Err(Error::NoPixelFormatFound) => return,
+        Err(err) => panic!("Failed to create context descriptor: {:?}", err),
+    };
+    let mut context = device.create_context(&context_descriptor, None).unwrap();
+    let surface = make_surface(&mut device, &context);
+    device
+        .bind_surface_to_context(&mut context, surface)
+        .unwrap();
+    device.make_context_current(&context).unwrap();
+
+    let gl = Gl::load_with(|symbol| device.get_proc_address(&context, symbol));
+    unsafe {
+        let version = CStr::from_ptr(gl.GetString(gl::VERSION) as *const c_char);
+        let version = version.to_str().unwrap();
+        assert!(version.starts_with("OpenGL ES"), "{}", version);
+    }
+
+    // The OpenGL ES bindings agree with the version that the descriptor reports.
+    let gles = Gles2::load_with(|symbol| device.get_proc_address(&context, symbol));
+    let (mut major_version, mut minor_version) = (0, 0);
+    unsafe {
+        gles.GetIntegerv(gles::MAJOR_VERSION, &mut major_version);
+        gles.GetIntegerv(gles::MINOR_VERSION, &mut minor_version);
+        assert_eq!(gles.GetError(), gles::NO_ERROR);
+    }
+    let version = device
+        .context_descriptor_attributes(&device.context_descriptor(&context))
+        .version;
+    assert!(version.major >= 3);
+    assert_eq!(
+        (major_version, minor_version),
+        (version.major as i32, version.minor as i32)
+    );
+
+    bind_context_fbo(&gl, &device, &context);
+    unsafe {
+        gl.Viewport(0, 0, 640, 480);
+    }
+    clear(&gl, &[255, 0, 0, 255]);
+    assert_eq!(get_pixel_from_bottom_row(&gl), [255, 0, 0, 255]);
+
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that contexts fall back to the highest supported version when the requested version is
+// unavailable, and that the achieved version is reported.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_context_version_fallback() {
+    let connection = Connection::new().unwrap();
+    let adapter = connection
+        .create_low_power_adapter()
+        .expect("Failed to create adapter!");
+    let mut device = match connection.create_device(&adapter) {
+        Ok(device) => device,
+        Err(Error::RequiredExtensionUnavailable) => return,
+        Err(err) => panic!("Failed to create device: {:?}", err),
+    };
+
+    // No implementation supports this version.
+    let requested_version = GLVersion::new(9, 0);
+    let context_descriptor = device
+        .create_context_descriptor(&ContextAttributes {
+            version: requested_version,
+            flags: ContextAttributeFlags::empty(),
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    let mut context = device.create_context(&context_descriptor, None).unwrap();
+
+    let achieved_descriptor = device.context_descriptor(&context);
+    let achieved_version = device
+        .context_descriptor_attributes(&achieved_descriptor)
+        .version;
+    assert!(achieved_version.major < requested_version.major);
+    assert!(achieved_version.major >= 2);
+
+    // Asking for the achieved version directly gets the same version.
+    let mut other_context = device.create_context(&achieved_descriptor, None).unwrap();
+    let other_descriptor = device.context_descriptor(&other_context);
+    assert_eq!(
+        device
+            .context_descriptor_attributes(&other_descriptor)
+            .version,
+        achieved_version
+    );
+
+    device.destroy_context(&mut other_context).unwrap();
+    device.destroy_context(&mut context).unwrap();
+}
+
+// Tests that no-error contexts are created where supported, and that context priorities are
+// reported.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_no_error_context_and_priority() {
+    // This is `GL_CONTEXT_FLAG_NO_ERROR_BIT_KHR`, which our OpenGL bindings don't include.
+    const CONTEXT_FLAG_NO_ERROR_BIT: GLenum = 0x8;
+
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    // Debug contexts always check for errors.
+    let debug_context_descriptor = env
+        .device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::NO_ERROR | ContextAttributeFlags::DEBUG,
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::Medium,
+        })
+        .unwrap();
+    assert!(!env
+        .device
+        .context_descriptor_attributes(&debug_context_descriptor)
+        .flags
+        .contains(ContextAttributeFlags::NO_ERROR));
+
+    let context_descriptor = env
+        .device
+        .create_context_descriptor(&ContextAttributes {
+            version: GLVersion::new(3, 0),
+            flags: ContextAttributeFlags::NO_ERROR,
+            samples: 0,
+            depth_format: DepthFormat::Depth24,
+            priority: ContextPriority::High,
+        })
+        .unwrap();
+    let attributes = env
+        .device
+        .context_descriptor_attributes(&context_descriptor);
+    let mut context = env
+        .device
+        .create_context(&context_descriptor, None)
+        .unwrap();
+
+    // The granted priority is at most the one asked for.
+    let context_attributes = env
+        .device
+        .context_descriptor_attributes(&env.device.context_descriptor(&context));
+    match attributes.priority {
+        ContextPriority::High => {}
+        ContextPriority::Medium => {
+            assert_eq!(context_attributes.priority, ContextPriority::Medium)
+        }
+        ContextPriority::Low => panic!("Got a lower priority than requested!"),
+    }
+
+    env.device.make_context_current(&context).unwrap();
+    unsafe {
+        let mut context_flags = 0;
+        env.gl.GetIntegerv(gl::CONTEXT_FLAGS, &mut context_flags);
+        assert_eq!(
+            context_flags as GLenum & CONTEXT_FLAG_NO_ERROR_BIT != 0,
+            attributes.flags.contains(ContextAttributeFlags::NO_ERROR)
+        );
+    }
+    assert_eq!(
+        context_attributes
+            .flags
+            .contains(ContextAttributeFlags::NO_ERROR),
+        attributes.flags.contains(ContextAttributeFlags::NO_ERROR)
+    );
+
+    env.device.destroy_context(&mut context).unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that every context descriptor that `enumerate_context_descriptors()` returns matches the
+// requested attributes, and that contexts can be created from any of them.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_enumerate_context_descriptors() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let attributes = ContextAttributes {
+        version: GLVersion::new(3, 0),
+        flags: ContextAttributeFlags::ALPHA | ContextAttributeFlags::DEPTH,
+        samples: 0,
+        depth_format: DepthFormat::Depth24,
+        priority: ContextPriority::Medium,
+    };
+    let context_descriptors = env
+        .device
+        .enumerate_context_descriptors(&attributes)
+        .unwrap();
+    assert!(!context_descriptors.is_empty());
+
+    // The first descriptor is the one that `create_context_descriptor()` picks.
+    let chosen_context_descriptor = env.device.create_context_descriptor(&attributes).unwrap();
+    let chosen_info = env
+        .device
+        .context_descriptor_info(&chosen_context_descriptor)
+        .unwrap();
+    assert_eq!(
+        env.device
+            .context_descriptor_info(&context_descriptors[0])
+            .unwrap()
+            .id,
+        chosen_info.id
+    );
+
+    // Every config is listed once, whether or not it matches the attributes.
+    let infos: Vec<_> = context_descriptors
+        .iter()
+        .map(|context_descriptor| {
+            env.device
+                .context_descriptor_info(context_descriptor)
+                .unwrap()
+        })
+        .collect();
+    for (index, info) in infos.iter().enumerate() {
+        assert!(infos[..index].iter().all(|other| other.id != info.id));
+    }
+
+    // Contexts created from any usable descriptor use its config.
+    let (context_descriptor, info) = context_descriptors
+        .iter()
+        .zip(infos.iter())
+        .rev()
+        .find(|&(_, info)| {
+            (info.red_bits, info.green_bits, info.blue_bits) == (8, 8, 8)
+                && info.surface_types & chosen_info.surface_types == chosen_info.surface_types
+                && info.renderable_types & chosen_info.renderable_types
+                    == chosen_info.renderable_types
+        })
+        .unwrap();
+    let mut context = env.device.create_context(context_descriptor, None).unwrap();
+    let context_info = env
+        .device
+        .context_descriptor_info(&env.device.context_descriptor(&context))
+        .unwrap();
+    assert_eq!(context_info, *info);
+
+    env.device.destroy_context(&mut context).unwrap();
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that the EGL library can't be swapped out once connections are using it.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_egl_library_is_fixed_once_loaded() {
+    let connection = Connection::new().unwrap();
+    drop(connection);
+
+    match set_egl_library_path("libEGL.so.1") {
+        Err(Error::GLLibraryAlreadyLoaded) => {}
+        result => panic!("Expected `GLLibraryAlreadyLoaded` but got {:?}!", result),
+    }
+    let egl = EGL_FUNCTIONS.with(|egl| egl.clone());
+    match set_egl_functions(egl) {
+        Err(Error::GLLibraryAlreadyLoaded) => {}
+        result => panic!("Expected `GLLibraryAlreadyLoaded` but got {:?}!", result),
+    }
+}
+
+// Tests that the version strings of various OpenGL implementations can be parsed.
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_gl_version_parsing() {
+    let cases = [
+        ("4.6.0 NVIDIA 535.54.03", Some(GLVersion::new(4, 6))),
+        ("4.5 (Core Profile) Mesa 22.3.6", Some(GLVersion::new(4, 5))),
+        ("3.1 Metal - 76.3", Some(GLVersion::new(3, 1))),
+        ("OpenGL ES 3.2 Mesa 22.3.6", Some(GLVersion::new(3, 2))),
+        (
+            "OpenGL ES 3.0 (ANGLE 2.1.0 git hash: 8a4dd8b2a7c5)",
+            Some(GLVersion::new(3, 0)),
+        ),
+        ("OpenGL ES-CM 1.1", Some(GLVersion::new(1, 1))),
+        (
+            "OpenGL ES 2.0 build 1.13@2876724",
+            Some(GLVersion::new(2, 0)),
+        ),
+        (
+            "WebGL 1.0 (OpenGL ES 2.0 Chromium)",
+            Some(GLVersion::new(2, 0)),
+        ),
+        ("WebGL 2.0", Some(GLVersion::new(2, 0))),
+        ("", None),
+        ("OpenGL ES", None),
+        ("4", None),
+    ];
+    for &(version_string, expected) in &cases {
+        assert_eq!(
+            GLVersion::parse(version_string),
+            expected,
+            "{:?}",
+            version_string
+        );
+    }
+}
+
+// Tests that errors describe themselves, including the native error code and operation.
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_error_display() {
+    let error = Error::ContextCreationFailed(NativeError::new(
+        WindowingApiError::BadMatch,
+        NativeErrorCode::EGL(0x3009),
+        "eglCreateContext",
+    ));
+    assert_eq!(
+        error.to_string(),
+        "couldn't create a context: bad match (EGL error 0x3009) in eglCreateContext"
+    );
+    let source = StdError::source(&error).expect("Native errors should have a source!");
+    assert_eq!(
+        source.to_string(),
+        "bad match (EGL error 0x3009) in eglCreateContext"
+    );
+
+    let error = Error::MakeCurrentFailed(WindowingApiError::ContextLost.into());
+    assert_eq!(
+        error.to_string(),
+        "couldn't make a context current or not current: context lost"
+    );
+
+    // Errors must be usable as boxed trait objects, as `?` into other error types does.
+    let error: Box<dyn StdError + Send + Sync> = Box::new(Error::UnsupportedGLVersion);
+    assert_eq!(
+        error.to_string(),
+        "the requested OpenGL version is unsupported"
+    );
+    assert!(error.source().is_none());
+}
+
+// Tests that `context_gl_info()` describes the implementation behind a context.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_context_gl_info() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let info = env.device.context_gl_info(&env.context).unwrap();
+    assert_eq!(info.api, env.device.gl_api());
+    assert!(!info.vendor.is_empty());
+    assert!(!info.renderer.is_empty());
+    assert!(info.shading_language_version.is_some());
+
+    // The version matches the one that the descriptor reports.
+    let context_descriptor = env.device.context_descriptor(&env.context);
+    let attributes = env
+        .device
+        .context_descriptor_attributes(&context_descriptor);
+    assert_eq!(info.version, attributes.version);
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Tests that `capabilities()` describes the device, and leaves the current context alone.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_device_capabilities() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    let capabilities = env.device.capabilities().unwrap();
+    assert!(capabilities.max_texture_size > 0);
+    assert!(capabilities.max_renderbuffer_size > 0);
+    assert_eq!(capabilities.surface_formats[0], SurfaceFormat::RGBA8);
+    assert!(capabilities.lock_surface_data);
+    assert!(capabilities.cross_thread_surface_textures);
+    assert!(!capabilities.display_extensions.is_empty());
+
+    // The result is kept, so asking again gives the same answer.
+    assert_eq!(env.device.capabilities().unwrap(), capabilities);
+
+    // The limits match the ones of an ordinary context.
+    unsafe {
+        let mut max_texture_size = 0;
+        env.gl
+            .GetIntegerv(gl::MAX_TEXTURE_SIZE, &mut max_texture_size);
+        assert_eq!(capabilities.max_texture_size, max_texture_size);
+    }
+    assert_eq!(
+        NativeContext::current().unwrap().egl_context,
+        env.device.native_context(&env.context).egl_context
+    );
+
+    // Every format that's reported can be used.
+    for &format in &capabilities.surface_formats {
+        let mut surface = env
+            .device
+            .create_surface_with_format(
+                &env.context,
+                SurfaceAccess::GPUOnly,
+                SurfaceType::Generic {
+                    size: Size2D::new(16, 16),
+                },
+                format,
+            )
+            .unwrap();
+        env.device
+            .destroy_surface(&mut env.context, &mut surface)
+            .unwrap();
+    }
+
+    env.device.destroy_context(&mut env.context).unwrap();
+}
+
+// Sets the leak policy, and restores the previous one when dropped.
+#[cfg(linux)]
+struct LeakPolicyGuard {
+    previous_policy: LeakPolicy,
+}
+
+#[cfg(linux)]
+impl LeakPolicyGuard {
+    fn new(policy: LeakPolicy) -> LeakPolicyGuard {
+        let previous_policy = leak_policy();
+        set_leak_policy(policy);
+        LeakPolicyGuard { previous_policy }
+    }
+}
+
+#[cfg(linux)]
+impl Drop for LeakPolicyGuard {
+    fn drop(&mut self) {
+        set_leak_policy(self.previous_policy);
+    }
+}
+
+// Tests that `leak_report()` lists exactly the objects that haven't been destroyed, and that
+// dropping an undestroyed object follows the leak policy.
+#[cfg(linux)]
+#[cfg_attr(not(feature = "sm-test"), test)]
+pub fn test_leak_report() {
+    let mut env = match BasicEnvironment::new() {
+        None => return,
+        Some(env) => env,
+    };
+
+    // Other tests may be running concurrently, so only look for our own objects.
+    let is_live = |device: &Device, object: TrackedObject| {
+        device
+            .leak_report()
+            .objects
+            .iter()
+            .any(|live_object| live_object.object == object)
+    };
+
+    let context_object = TrackedObject::Context(env.device.context_id(&env.context));
+    assert_eq!(
+        is_live(&env.device, context_object),
+        cfg!(feature = "sm-leak-tracking")
+    );
+
+    let mut surface = make_surface(&mut env.device, &env.context);
+    let surface_object = TrackedObject::Surface(env.device.surface_info(&surface).id);
+    assert_eq!(
+        is_live(&env.device, surface_object),
+        cfg!(feature = "sm-leak-tracking")
+    );
+    env.device
+        .destroy_surface(&mut env.context, &mut surface)
+        .unwrap();
+    assert!(!is_live(&env.device, surface_object));
+
+    // With the `Log` policy, dropping an undestroyed surface doesn't panic, and the surface
+    // stays in the report. The policy is process-wide, so restore it even if this panics.
+    let policy_guard = LeakPolicyGuard::new(LeakPolicy::Log);
+    let surface = make_surface(&mut env.device, &env.context);
+    let surface_object = TrackedObject::Surface(env.device.surface_info(&surface).id);
+    drop(surface);
+    drop(policy_guard);
+    assert_eq!(
+        is_live(&env.device, surface_object),
+        cfg!(feature = "sm-leak-tracking")
+    );
+
+    // Surfaces here used to be leaked silently, so they don't panic even with the `Panic` policy.
+    let policy_guard = LeakPolicyGuard::new(LeakPolicy::Panic);
+    drop(make_surface(&mut env.device, &env.context));
+    drop(policy_guard);
+
+    env.device.destroy_context(&mut env.context).unwrap();
+    assert!(!is_live(&env.device, context_object));
+}
+
 fn bind_context_fbo(gl: &Gl, device: &Device, context: &Context) {
     unsafe {
         gl.BindFramebuffer(gl::FRAMEBUFFER, context_fbo(device, context));
@@ -965,6 +2885,9 @@ impl BasicEnvironment {
             .create_context_descriptor(&ContextAttributes {
                 version: GLVersion::new(3, 0),
                 flags: ContextAttributeFlags::empty(),
+                samples: 0,
+                depth_format: DepthFormat::Depth24,
+                priority: ContextPriority::Medium,
             })
             .unwrap();
 